@@ -0,0 +1,414 @@
+use {
+    crate::analysis::{ratio, BlockAnalysis},
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::{collections::VecDeque, fmt, str::FromStr},
+};
+
+/// Block metric an alert rule is evaluated on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertMetric {
+    /// Violating transactions, summed over the window.
+    ViolationCount,
+    /// Violating transactions as a fraction of analyzed transactions in the window.
+    ViolationRate,
+    /// Largest priority gap of any violation in the window.
+    MaxGap,
+    /// Mean block fill over the window.
+    Fill,
+    /// Zero-priority transactions landing ahead of prioritized ones, summed over the window.
+    ZeroPriorityJumps,
+}
+
+impl AlertMetric {
+    const NAMES: [(&'static str, AlertMetric); 5] = [
+        ("violation_count", AlertMetric::ViolationCount),
+        ("violation_rate", AlertMetric::ViolationRate),
+        ("max_gap", AlertMetric::MaxGap),
+        ("fill", AlertMetric::Fill),
+        ("zero_priority_jumps", AlertMetric::ZeroPriorityJumps),
+    ];
+
+    fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, metric)| *metric == self)
+            .map(|(name, _)| *name)
+            .unwrap_or_default()
+    }
+
+    /// Whether thresholds are fractions, which may be written as percentages.
+    fn is_fraction(self) -> bool {
+        matches!(self, AlertMetric::ViolationRate | AlertMetric::Fill)
+    }
+
+    fn value(self, window: &[&BlockMetrics]) -> f64 {
+        match self {
+            AlertMetric::ViolationCount => window
+                .iter()
+                .map(|block| block.violation_count)
+                .sum::<u64>() as f64,
+            AlertMetric::ViolationRate => ratio(
+                window.iter().map(|block| block.violation_count).sum(),
+                window.iter().map(|block| block.transaction_count).sum(),
+            ),
+            AlertMetric::MaxGap => window
+                .iter()
+                .map(|block| block.max_gap)
+                .max()
+                .unwrap_or_default() as f64,
+            AlertMetric::Fill => {
+                window.iter().map(|block| block.fill).sum::<f64>() / window.len().max(1) as f64
+            }
+            AlertMetric::ZeroPriorityJumps => window
+                .iter()
+                .map(|block| block.zero_priority_jump_count)
+                .sum::<u64>() as f64,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    const SYMBOLS: [(&'static str, Comparison); 4] = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+    ];
+
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+        }
+    }
+}
+
+/// A condition such as `violation_rate > 5% over 20 slots` or `max_gap > 1e9 microlamports`.
+///
+/// The syntax is `<metric> <comparison> <threshold>[%|<unit>] [over <N> slots] [cooldown <N>
+/// slots]`, where the comparison is one of `>`, `>=`, `<`, `<=`. A unit after the threshold is
+/// informational, except `%`, which scales it by 1/100. Without `over`, the rule is evaluated on
+/// each block alone; with it, on the last N blocks, and only once N blocks have been seen.
+///
+/// After a rule fires, it stays quiet while it keeps holding for the same leader, and for
+/// `cooldown` slots in any case.
+#[derive(Clone, Debug)]
+pub struct AlertRule {
+    pub metric: AlertMetric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// Number of most recent blocks the metric is aggregated over.
+    pub window: usize,
+    /// Slots after firing during which the rule does not fire again.
+    pub cooldown: u64,
+    text: String,
+}
+
+impl AlertRule {
+    /// Alerts on any block with at least `count` violations.
+    pub fn violation_count(count: usize) -> Self {
+        Self {
+            metric: AlertMetric::ViolationCount,
+            comparison: Comparison::GreaterOrEqual,
+            threshold: count as f64,
+            window: 1,
+            cooldown: 0,
+            text: format!("violation_count >= {}", count),
+        }
+    }
+}
+
+impl FromStr for AlertRule {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut tokens: Vec<&str> = text.split_whitespace().collect();
+        let cooldown = match tokens.as_slice() {
+            [.., "cooldown", count, "slots" | "slot"] => {
+                let cooldown = count
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid cooldown `{}` in alert `{}`", count, text))?;
+                tokens.truncate(tokens.len() - 3);
+                cooldown
+            }
+            _ => 0,
+        };
+        let window = match tokens.as_slice() {
+            [.., "over", count, "slots" | "slot" | "blocks" | "block"] => {
+                let window = count
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|window| *window > 0)
+                    .ok_or_else(|| format!("invalid window `{}` in alert `{}`", count, text))?;
+                tokens.truncate(tokens.len() - 3);
+                window
+            }
+            _ => 1,
+        };
+
+        let (metric, comparison, threshold, unit) = match tokens.as_slice() {
+            [metric, comparison, threshold] => (*metric, *comparison, *threshold, None),
+            [metric, comparison, threshold, unit] => {
+                (*metric, *comparison, *threshold, Some(*unit))
+            }
+            _ => {
+                return Err(format!(
+                    "expected `<metric> <comparison> <threshold> [over <N> slots] \
+                     [cooldown <N> slots]`, got `{}`",
+                    text
+                ))
+            }
+        };
+        let metric = AlertMetric::NAMES
+            .iter()
+            .find(|(name, _)| *name == metric)
+            .map(|(_, metric)| *metric)
+            .ok_or_else(|| {
+                let names: Vec<_> = AlertMetric::NAMES.iter().map(|(name, _)| *name).collect();
+                format!(
+                    "unknown metric `{}` in alert `{}`, expected one of {}",
+                    metric,
+                    text,
+                    names.join(", ")
+                )
+            })?;
+        let comparison = Comparison::SYMBOLS
+            .iter()
+            .find(|(symbol, _)| *symbol == comparison)
+            .map(|(_, comparison)| *comparison)
+            .ok_or_else(|| format!("unknown comparison `{}` in alert `{}`", comparison, text))?;
+
+        let (threshold, is_percentage) = match threshold.strip_suffix('%') {
+            Some(threshold) => (threshold, true),
+            None => (threshold, unit == Some("%")),
+        };
+        let mut threshold: f64 = threshold
+            .parse()
+            .map_err(|_| format!("invalid threshold `{}` in alert `{}`", threshold, text))?;
+        if is_percentage {
+            if !metric.is_fraction() {
+                return Err(format!(
+                    "{} is not a rate and cannot take a percentage in alert `{}`",
+                    metric.name(),
+                    text
+                ));
+            }
+            threshold /= 100.0;
+        }
+
+        Ok(Self {
+            metric,
+            comparison,
+            threshold,
+            window,
+            cooldown,
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+        })
+    }
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// What alert rules need to remember of each block.
+struct BlockMetrics {
+    violation_count: u64,
+    transaction_count: u64,
+    max_gap: u64,
+    fill: f64,
+    zero_priority_jump_count: u64,
+}
+
+impl BlockMetrics {
+    fn new(analysis: &BlockAnalysis) -> Self {
+        Self {
+            violation_count: analysis.violating_transaction_signatures.len() as u64,
+            transaction_count: analysis.analyzed_transaction_count() as u64,
+            max_gap: analysis
+                .violations()
+                .map(|violation| violation.gap())
+                .max()
+                .unwrap_or_default(),
+            fill: analysis.fill(),
+            zero_priority_jump_count: analysis.zero_priority_jumps().len() as u64,
+        }
+    }
+}
+
+/// A rule that held after a block, with the value it was evaluated on.
+pub struct FiredAlert<'a> {
+    pub slot: Slot,
+    pub leader: Option<Pubkey>,
+    pub rule: &'a AlertRule,
+    pub value: f64,
+}
+
+impl fmt::Display for FiredAlert<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = if self.rule.metric.is_fraction() {
+            format!("{:.2}%", self.value * 100.0)
+        } else {
+            format!("{}", self.value)
+        };
+        write!(f, "slot {}: {} (was {})", self.slot, self.rule, value)?;
+        if let Some(leader) = self.leader {
+            write!(f, ", leader {}", leader)?;
+        }
+        Ok(())
+    }
+}
+
+/// Follow-up to an alert, counting how often its rule held again while suppressed.
+pub struct SuppressedAlerts<'a> {
+    pub rule: &'a AlertRule,
+    pub leader: Option<Pubkey>,
+    /// Slot the alert fired at.
+    pub slot: Slot,
+    /// Slots the rule held at afterwards without firing.
+    pub suppressed_slots: Vec<Slot>,
+}
+
+impl fmt::Display for SuppressedAlerts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} held {} more times after slot {}",
+            self.rule,
+            self.suppressed_slots.len(),
+            self.slot
+        )?;
+        if let Some(leader) = self.leader {
+            write!(f, " under leader {}", leader)?;
+        }
+        let slots: Vec<_> = self
+            .suppressed_slots
+            .iter()
+            .map(|slot| slot.to_string())
+            .collect();
+        write!(f, " (slots {})", slots.join(", "))
+    }
+}
+
+/// What to tell the user after a block.
+pub enum AlertEvent<'a> {
+    Fired(FiredAlert<'a>),
+    Suppressed(SuppressedAlerts<'a>),
+}
+
+impl fmt::Display for AlertEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertEvent::Fired(alert) => write!(f, "ALERT {}", alert),
+            AlertEvent::Suppressed(summary) => write!(f, "ALERT SUMMARY {}", summary),
+        }
+    }
+}
+
+/// The last alert a rule fired and the repeats suppressed since.
+struct Episode {
+    slot: Slot,
+    leader: Option<Pubkey>,
+    suppressed_slots: Vec<Slot>,
+}
+
+impl Episode {
+    /// Whether a repeat at `slot` under `leader` belongs to this alert rather than a new one.
+    fn covers(&self, slot: Slot, leader: Option<Pubkey>, cooldown: u64) -> bool {
+        (leader.is_some() && leader == self.leader) || slot < self.slot.saturating_add(cooldown)
+    }
+}
+
+/// Evaluates alert rules against a stream of blocks in slot order.
+pub struct AlertEvaluator {
+    rules: Vec<AlertRule>,
+    /// Per rule, the alert whose repeats are currently being suppressed.
+    episodes: Vec<Option<Episode>>,
+    /// The most recent blocks, as many as the longest rule window.
+    recent: VecDeque<BlockMetrics>,
+}
+
+impl AlertEvaluator {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            episodes: rules.iter().map(|_| None).collect(),
+            rules,
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Adds `analysis`, produced by `leader`, to the window and returns the rules that now fire,
+    /// along with summaries of alerts whose suppression just ended.
+    pub fn evaluate(
+        &mut self,
+        analysis: &BlockAnalysis,
+        leader: Option<Pubkey>,
+    ) -> Vec<AlertEvent<'_>> {
+        let capacity = self.rules.iter().map(|rule| rule.window).max().unwrap_or(1);
+        if self.recent.len() == capacity {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(BlockMetrics::new(analysis));
+
+        let slot = analysis.slot;
+        let mut events = Vec::new();
+        for (rule, episode) in self.rules.iter().zip(&mut self.episodes) {
+            let value = (self.recent.len() >= rule.window).then(|| {
+                let window: Vec<_> = self
+                    .recent
+                    .range(self.recent.len() - rule.window..)
+                    .collect();
+                rule.metric.value(&window)
+            });
+            let holds = value.is_some_and(|value| rule.comparison.holds(value, rule.threshold));
+
+            if let Some(current) = episode.as_mut() {
+                if current.covers(slot, leader, rule.cooldown) {
+                    if holds {
+                        current.suppressed_slots.push(slot);
+                    }
+                    continue;
+                }
+                let ended = episode.take().expect("episode is present");
+                if !ended.suppressed_slots.is_empty() {
+                    events.push(AlertEvent::Suppressed(SuppressedAlerts {
+                        rule,
+                        leader: ended.leader,
+                        slot: ended.slot,
+                        suppressed_slots: ended.suppressed_slots,
+                    }));
+                }
+            }
+            if let (true, Some(value)) = (holds, value) {
+                *episode = Some(Episode {
+                    slot,
+                    leader,
+                    suppressed_slots: Vec::new(),
+                });
+                events.push(AlertEvent::Fired(FiredAlert {
+                    slot,
+                    leader,
+                    rule,
+                    value,
+                }));
+            }
+        }
+        events
+    }
+}
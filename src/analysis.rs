@@ -0,0 +1,1411 @@
+use {
+    crate::{
+        anonymize::Anonymizer,
+        budget::{
+            get_compute_budget_issues, get_compute_budget_usage, get_compute_unit_limit,
+            ComputeBudgetAdoption, ComputeBudgetIssue, ComputeBudgetUsage,
+        },
+        fees::{base_fee, FeeTotals, PackingEstimate},
+        instructions::{resolve_inner_instructions, InnerInstruction},
+        ordering::OrderingDisorder,
+        priority::{base_fee_priority, display_priority, CuPrice, PriorityModel, Unprioritized},
+        rule::{Conflict, FilterCandidate, ViolationFilter, ViolationRule},
+    },
+    clap::ValueEnum,
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        clock::{Slot, UnixTimestamp},
+        compute_budget,
+        message::{
+            v0::LoadedAddresses, AccountKeys, SanitizedMessage, SimpleAddressLoader,
+            VersionedMessage,
+        },
+        program_utils::limited_deserialize,
+        pubkey::Pubkey,
+        signature::Signature,
+        system_instruction::SystemInstruction,
+        system_program,
+        transaction::{SanitizedVersionedTransaction, TransactionError},
+        vote,
+    },
+    solana_transaction_status::{
+        UiConfirmedBlock, UiInnerInstructions, UiInstruction, UiLoadedAddresses,
+    },
+    std::{
+        collections::{hash_map::Entry, HashMap, HashSet},
+        process::exit,
+        str::FromStr,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+};
+
+/// Maximum compute units a block may consume.
+pub const MAX_BLOCK_UNITS: u64 = 48_000_000;
+/// Maximum compute units a block may spend on transactions write-locking any one account.
+pub const MAX_WRITABLE_ACCOUNT_UNITS: u64 = 12_000_000;
+
+/// Options controlling how transactions are ranked and which take part in the analysis.
+#[derive(Clone, Debug)]
+pub struct AnalysisConfig {
+    /// How transaction priority is computed.
+    pub priority_model: Arc<dyn PriorityModel>,
+    /// Include simple vote transactions in conflict tracking and reported statistics.
+    pub include_votes: bool,
+    /// Only analyze transactions invoking one of these programs, directly or through CPI. All
+    /// transactions are analyzed if empty.
+    pub programs: Vec<Pubkey>,
+    /// Only track conflicts on these accounts. All accounts are tracked if empty.
+    pub accounts: Vec<Pubkey>,
+    /// Only report violations where this fee payer paid for either side.
+    pub fee_payer: Option<Pubkey>,
+    /// Only report violations with one of these transactions on either side. Conflicts are
+    /// still tracked across the whole block.
+    pub signatures: Option<HashSet<Signature>>,
+    /// Accounts excluded from conflict tracking, taking precedence over `accounts`.
+    pub ignore_accounts: Vec<Pubkey>,
+    /// Custom violation predicate replacing the built-in priority comparison.
+    pub violation_rule: Option<Arc<ViolationRule>>,
+    /// Only report violations this expression accepts. Conflicts are still tracked across the
+    /// whole block.
+    pub violation_filter: Option<Arc<ViolationFilter>>,
+    /// Analyze every block twice and fail if the structured results differ, to catch output
+    /// that depends on hash map iteration order.
+    pub verify_determinism: bool,
+    /// Report the wall time spent in each phase of every slot.
+    pub profile: bool,
+    /// Replaces account keys and signatures in every analysis with salted hashes.
+    pub anonymizer: Option<Arc<Anonymizer>>,
+    /// How non-vote transactions that set no compute unit price are treated. They are ranked
+    /// by the priority model like any other if unset.
+    pub unprioritized: Option<Unprioritized>,
+    /// Whether conflicting transactions of equal priority are violations. Ignored with a
+    /// violation rule, which decides for itself.
+    pub tie_policy: TiePolicy,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            priority_model: Arc::new(CuPrice),
+            include_votes: false,
+            programs: Vec::new(),
+            accounts: Vec::new(),
+            fee_payer: None,
+            signatures: None,
+            ignore_accounts: Vec::new(),
+            violation_rule: None,
+            violation_filter: None,
+            verify_determinism: false,
+            profile: false,
+            anonymizer: None,
+            unprioritized: None,
+            tie_policy: TiePolicy::default(),
+        }
+    }
+}
+
+impl AnalysisConfig {
+    fn tracks_account(&self, account: &Pubkey) -> bool {
+        !self.ignore_accounts.contains(account)
+            && (self.accounts.is_empty() || self.accounts.contains(account))
+    }
+
+    fn is_priority_violation(
+        &self,
+        previous: &LastAccessPriority,
+        access: LastAccess,
+        priority: u64,
+        account: &Pubkey,
+        accounts: &[Pubkey],
+    ) -> bool {
+        match &self.violation_rule {
+            Some(rule) => rule.is_violation(&Conflict {
+                prev_access: previous.last_access.as_str(),
+                prev_priority: previous.priority,
+                new_access: access.as_str(),
+                new_priority: priority,
+                account,
+                accounts,
+            }),
+            None => match self.tie_policy {
+                TiePolicy::Never => previous.priority < priority,
+                TiePolicy::Always => previous.priority <= priority,
+            },
+        }
+    }
+
+    fn reports_violation(
+        &self,
+        slot: Slot,
+        previous: &LastAccessPriority,
+        kind: ViolationKind,
+        account: &Pubkey,
+        transaction: &TransactionSummary,
+    ) -> bool {
+        let TransactionSummary {
+            signature,
+            fee_payer,
+            ..
+        } = transaction;
+        self.fee_payer
+            .is_none_or(|payer| payer == previous.fee_payer || payer == *fee_payer)
+            && self.signatures.as_ref().is_none_or(|signatures| {
+                signatures.contains(&previous.signature) || signatures.contains(signature)
+            })
+            && self.violation_filter.as_ref().is_none_or(|filter| {
+                filter.matches(&FilterCandidate {
+                    slot,
+                    account,
+                    kind,
+                    prev_priority: previous.priority,
+                    priority: transaction.priority,
+                    prev_index: previous.index,
+                    index: transaction.index,
+                    prev_fee_payer: &previous.fee_payer,
+                    fee_payer,
+                    program: transaction.primary_program(),
+                    programs: &transaction.program_ids,
+                })
+            })
+    }
+}
+
+/// Whether a conflicting transaction landing after one of equal priority is a violation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TiePolicy {
+    /// Ties are never violations, as any order between them respects priority.
+    #[default]
+    Never,
+    /// Every tie is a violation, holding the scheduler to a strict order in which conflicting
+    /// transactions never churn at equal priority.
+    Always,
+}
+
+/// Order in which violated accounts are listed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum AccountSort {
+    /// Largest priority gap first.
+    #[default]
+    Severity,
+    /// Most violations first.
+    Count,
+    /// By pubkey.
+    Pubkey,
+}
+
+/// Result of running the priority checks over a single block.
+pub struct BlockAnalysis {
+    pub slot: Slot,
+    /// When the block was produced, if the provider reported it.
+    pub block_time: Option<UnixTimestamp>,
+    /// Every transaction in the block, in block order.
+    pub transactions: Vec<TransactionSummary>,
+    pub violated_accounts: HashMap<Pubkey, Vec<Violation>>,
+    pub violating_transaction_signatures: Vec<(Vec<Signature>, Signature)>,
+    /// Number of transactions that write-locked each account.
+    pub write_lock_counts: HashMap<Pubkey, usize>,
+    /// Conflicting accesses checked: each lock taken after a conflicting lock on the same
+    /// account.
+    pub conflict_count: usize,
+    /// Conflicting accesses between transactions of equal priority, in block order.
+    pub ties: Vec<Tie>,
+    /// Total compute units consumed by the block's transactions.
+    pub compute_units_consumed: u64,
+    /// Time spent in each phase of the analysis.
+    pub timings: AnalysisTimings,
+    /// How the block was obtained, filled in by the caller that fetched it.
+    pub fetch: FetchMetadata,
+}
+
+/// How a block was obtained before analysis.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FetchMetadata {
+    /// Wall time spent reading the block from the cache or fetching it from RPC.
+    pub duration: Duration,
+    /// Requests retried after the provider rate limited them.
+    pub rpc_retries: u64,
+    /// Whether the block was read from the cache.
+    pub cache_hit: bool,
+}
+
+/// Wall time spent in the phases of analyzing a block.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AnalysisTimings {
+    /// Decoding and sanitizing transactions.
+    pub decode: Duration,
+    /// Computing priorities with the priority model.
+    pub priority: Duration,
+    /// Tracking account locks and checking conflicting accesses.
+    pub conflicts: Duration,
+    /// The whole analysis, including the phases above.
+    pub total: Duration,
+}
+
+/// Per-transaction figures extracted during analysis.
+pub struct TransactionSummary {
+    pub signature: Signature,
+    /// Position within the block, counting every transaction.
+    pub index: usize,
+    pub priority: u64,
+    /// Whether this is a simple vote transaction.
+    pub is_vote: bool,
+    /// Whether this transaction took part in conflict tracking, rather than being excluded as a
+    /// vote or by the program filter.
+    pub is_analyzed: bool,
+    /// Whether this transaction uses a durable nonce instead of a recent blockhash.
+    pub is_durable_nonce: bool,
+    /// Whether this transaction landed after a lower-priority conflicting transaction.
+    pub is_violation: bool,
+    /// Execution error, if the transaction failed.
+    pub error: Option<TransactionError>,
+    /// Total fee paid, in lamports.
+    pub fee: u64,
+    /// Portion of the fee above the per-signature base fee, in lamports.
+    pub priority_fee: u64,
+    /// Compute units requested, explicitly or by default.
+    pub compute_unit_limit: u32,
+    /// Compute units consumed, if the provider reports them.
+    pub compute_units_consumed: u64,
+    pub fee_payer: Pubkey,
+    /// Programs invoked by top-level instructions, in instruction order without repeats.
+    pub program_ids: Vec<Pubkey>,
+    /// Accounts the runtime write-locks, after demotions.
+    pub writable_accounts: Vec<Pubkey>,
+    /// Accounts the runtime read-locks.
+    pub readonly_accounts: Vec<Pubkey>,
+    /// Compute-budget settings the transaction makes.
+    pub compute_budget_usage: ComputeBudgetUsage,
+    /// Compute-budget usage the runtime would reject or adjust.
+    pub compute_budget_issues: Vec<ComputeBudgetIssue>,
+    /// Instructions invoked through CPI. Only recorded for violating transactions.
+    pub inner_instructions: Vec<InnerInstruction>,
+}
+
+/// A conflicting access between two transactions of equal priority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tie {
+    pub account: Pubkey,
+    pub kind: ViolationKind,
+    pub priority: u64,
+    /// Block positions of the earlier and later transactions.
+    pub previous_index: usize,
+    pub index: usize,
+}
+
+/// One analyzed transaction's lock on an account, as seen in an account timeline.
+pub struct AccountAccess {
+    pub index: usize,
+    pub is_write: bool,
+    pub priority: u64,
+}
+
+impl std::fmt::Display for AccountAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let access = if self.is_write { 'W' } else { 'R' };
+        write!(
+            f,
+            "#{}: {} {}",
+            self.index,
+            access,
+            display_priority(self.priority)
+        )
+    }
+}
+
+/// A higher-priority access to an account landing after a lower-priority conflicting access.
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub previous_priority: u64,
+    pub priority: u64,
+    /// Block positions of the earlier and later transactions.
+    pub previous_index: usize,
+    pub index: usize,
+}
+
+impl Violation {
+    /// How much higher the later transaction's priority was. Zero if a custom violation rule
+    /// flagged an access that was not higher.
+    pub fn gap(&self) -> u64 {
+        self.priority.saturating_sub(self.previous_priority)
+    }
+
+    /// Whether a transaction with no priority at all held the account ahead of a prioritized one,
+    /// the pattern users most often notice.
+    pub fn is_zero_priority_jump(&self) -> bool {
+        self.previous_priority == 0 && self.priority > 0
+    }
+
+    /// How many block positions apart the two transactions landed.
+    pub fn distance(&self) -> usize {
+        self.index - self.previous_index
+    }
+}
+
+/// The pair of conflicting accesses, earlier first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ViolationKind {
+    WriteWrite,
+    ReadWrite,
+    WriteRead,
+}
+
+impl ViolationKind {
+    pub const ALL: [ViolationKind; 3] = [
+        ViolationKind::WriteWrite,
+        ViolationKind::ReadWrite,
+        ViolationKind::WriteRead,
+    ];
+
+    fn new(previous_access: LastAccess, access: LastAccess) -> Self {
+        match (previous_access, access) {
+            (LastAccess::Write, LastAccess::Write) => ViolationKind::WriteWrite,
+            (LastAccess::Read, LastAccess::Write) => ViolationKind::ReadWrite,
+            (LastAccess::Write, LastAccess::Read) => ViolationKind::WriteRead,
+            (LastAccess::Read, LastAccess::Read) => unreachable!("reads do not conflict"),
+        }
+    }
+}
+
+impl std::fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ViolationKind::WriteWrite => "write-write",
+            ViolationKind::ReadWrite => "read-write",
+            ViolationKind::WriteRead => "write-read",
+        })
+    }
+}
+
+impl TransactionSummary {
+    /// The first top-level program other than the compute-budget program, which the
+    /// transaction's compute is attributed to.
+    pub fn primary_program(&self) -> Option<Pubkey> {
+        self.program_ids
+            .iter()
+            .find(|program_id| !compute_budget::check_id(program_id))
+            .copied()
+    }
+
+    /// Accounts locked by both this transaction and the later `other` where at least one of them
+    /// writes, with the kind of conflict.
+    pub fn conflicting_accounts(&self, other: &TransactionSummary) -> Vec<(Pubkey, ViolationKind)> {
+        other
+            .writable_accounts
+            .iter()
+            .chain(&other.readonly_accounts)
+            .filter_map(|account| {
+                let kind = match (self.access(account)?, other.access(account)?) {
+                    (LastAccess::Read, LastAccess::Read) => return None,
+                    (previous_access, access) => ViolationKind::new(previous_access, access),
+                };
+                Some((*account, kind))
+            })
+            .collect()
+    }
+
+    fn access(&self, account: &Pubkey) -> Option<LastAccess> {
+        if self.writable_accounts.contains(account) {
+            Some(LastAccess::Write)
+        } else if self.readonly_accounts.contains(account) {
+            Some(LastAccess::Read)
+        } else {
+            None
+        }
+    }
+}
+
+impl BlockAnalysis {
+    /// Accounts write-locked by the most transactions, in descending order.
+    pub fn top_contended_accounts(&self, count: usize) -> Vec<(Pubkey, usize)> {
+        let mut accounts: Vec<_> = self
+            .write_lock_counts
+            .iter()
+            .map(|(account, count)| (*account, *count))
+            .collect();
+        accounts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        accounts.truncate(count);
+        accounts
+    }
+
+    /// Every analyzed transaction's lock on `account`, in block order.
+    pub fn account_timeline(&self, account: &Pubkey) -> Vec<AccountAccess> {
+        self.analyzed_transactions()
+            .filter_map(|transaction| {
+                let is_write = match transaction.access(account)? {
+                    LastAccess::Write => true,
+                    LastAccess::Read => false,
+                };
+                Some(AccountAccess {
+                    index: transaction.index,
+                    is_write,
+                    priority: transaction.priority,
+                })
+            })
+            .collect()
+    }
+
+    /// The `count` analyzed transactions with the highest priority, highest first, ties in block
+    /// order.
+    pub fn top_priority_transactions(&self, count: usize) -> Vec<&TransactionSummary> {
+        let mut transactions: Vec<_> = self.analyzed_transactions().collect();
+        transactions.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.index.cmp(&b.index)));
+        transactions.truncate(count);
+        transactions
+    }
+
+    /// Priorities of the analyzed transactions that write-locked `account`, in block order.
+    pub fn write_lock_priorities(&self, account: &Pubkey) -> Vec<u64> {
+        self.analyzed_transactions()
+            .filter(|transaction| transaction.writable_accounts.contains(account))
+            .map(|transaction| transaction.priority)
+            .collect()
+    }
+
+    pub fn priority_distribution(&self) -> PriorityDistribution {
+        PriorityDistribution::new(&self.priorities())
+    }
+
+    /// Fraction of analyzed transactions that violated priority ordering.
+    pub fn violation_rate(&self) -> f64 {
+        ratio(
+            self.violating_transaction_signatures.len() as u64,
+            self.analyzed_transaction_count() as u64,
+        )
+    }
+
+    /// Priority of each analyzed transaction, in block order.
+    pub fn priorities(&self) -> Vec<u64> {
+        self.analyzed_transactions()
+            .map(|transaction| transaction.priority)
+            .collect()
+    }
+
+    /// Transactions that took part in the analysis: non-vote transactions, plus votes if
+    /// requested, narrowed by the program filter.
+    pub fn analyzed_transactions(&self) -> impl Iterator<Item = &TransactionSummary> {
+        self.transactions
+            .iter()
+            .filter(|transaction| transaction.is_analyzed)
+    }
+
+    pub fn analyzed_transaction_count(&self) -> usize {
+        self.analyzed_transactions().count()
+    }
+
+    /// Signatures of analyzed transactions that use a durable nonce.
+    pub fn durable_nonce_signatures(&self) -> HashSet<Signature> {
+        self.analyzed_transactions()
+            .filter(|transaction| transaction.is_durable_nonce)
+            .map(|transaction| transaction.signature)
+            .collect()
+    }
+
+    /// Analyzed transactions other than votes.
+    pub fn non_vote_transaction_count(&self) -> usize {
+        self.analyzed_transactions()
+            .filter(|transaction| !transaction.is_vote)
+            .count()
+    }
+
+    /// Accounts write-locked by more than one analyzed transaction, i.e. competed for.
+    pub fn contended_account_count(&self) -> usize {
+        self.write_lock_counts
+            .values()
+            .filter(|count| **count > 1)
+            .count()
+    }
+
+    pub fn tie_stats(&self) -> TieStats {
+        TieStats {
+            conflict_count: self.conflict_count,
+            tie_count: self.ties.len(),
+            zero_priority_tie_count: self.ties.iter().filter(|tie| tie.priority == 0).count(),
+            tied_account_count: self
+                .ties
+                .iter()
+                .map(|tie| tie.account)
+                .collect::<HashSet<_>>()
+                .len(),
+        }
+    }
+
+    /// Violations normalized by the block's traffic and contention.
+    pub fn normalized_rates(&self) -> NormalizedRates {
+        NormalizedRates::new(
+            self.violating_transaction_signatures.len(),
+            self.non_vote_transaction_count(),
+            self.violations().count(),
+            self.contended_account_count(),
+        )
+    }
+
+    pub fn vote_transaction_count(&self) -> usize {
+        self.transactions
+            .iter()
+            .filter(|transaction| transaction.is_vote)
+            .count()
+    }
+
+    /// Fees paid by every transaction in the block, votes included.
+    pub fn fee_totals(&self) -> FeeTotals {
+        FeeTotals::new(self.transactions.iter())
+    }
+
+    /// Compute-budget settings made by the analyzed transactions other than votes, which never
+    /// make any.
+    pub fn compute_budget_adoption(&self) -> ComputeBudgetAdoption {
+        ComputeBudgetAdoption::new(
+            self.analyzed_transactions()
+                .filter(|transaction| !transaction.is_vote),
+        )
+    }
+
+    /// Priority-ordered packing counterfactual over the analyzed transactions.
+    pub fn packing_estimate(&self) -> PackingEstimate {
+        PackingEstimate::new(self.analyzed_transactions())
+    }
+
+    /// Disorder of the analyzed transactions' priority sequence.
+    pub fn ordering_disorder(&self) -> OrderingDisorder {
+        OrderingDisorder::new(&self.priorities())
+    }
+
+    /// Number of consecutive analyzed transaction pairs whose priority does not increase, and
+    /// the total number of such pairs.
+    pub fn adjacent_priority_pairs(&self) -> (u64, u64) {
+        let priorities = self.priorities();
+        let non_increasing = priorities
+            .windows(2)
+            .filter(|pair| pair[1] <= pair[0])
+            .count();
+        (
+            non_increasing as u64,
+            priorities.len().saturating_sub(1) as u64,
+        )
+    }
+
+    pub fn violations(&self) -> impl Iterator<Item = &Violation> {
+        self.violated_accounts.values().flatten()
+    }
+
+    /// Violated accounts with their violations, in a stable order, ties broken by pubkey.
+    pub fn sorted_violated_accounts(&self, sort: AccountSort) -> Vec<(Pubkey, &[Violation])> {
+        let mut accounts: Vec<_> = self
+            .violated_accounts
+            .iter()
+            .map(|(account, violations)| (*account, violations.as_slice()))
+            .collect();
+        let max_gap = |violations: &[Violation]| violations.iter().map(Violation::gap).max();
+        accounts.sort_by(|(a, a_violations), (b, b_violations)| match sort {
+            AccountSort::Severity => max_gap(b_violations)
+                .cmp(&max_gap(a_violations))
+                .then(b_violations.len().cmp(&a_violations.len()))
+                .then(a.cmp(b)),
+            AccountSort::Count => b_violations.len().cmp(&a_violations.len()).then(a.cmp(b)),
+            AccountSort::Pubkey => a.cmp(b),
+        });
+        accounts
+    }
+
+    /// Violations where a zero-priority transaction landed ahead of a prioritized one, with the
+    /// contended account, in block order.
+    pub fn zero_priority_jumps(&self) -> Vec<(Pubkey, &Violation)> {
+        let mut jumps: Vec<_> = self
+            .violated_accounts
+            .iter()
+            .flat_map(|(account, violations)| {
+                violations
+                    .iter()
+                    .filter(|violation| violation.is_zero_priority_jump())
+                    .map(|violation| (*account, violation))
+            })
+            .collect();
+        jumps.sort_by_key(|(account, violation)| (violation.index, *account));
+        jumps
+    }
+
+    /// Fraction of the block compute limit consumed.
+    pub fn fill(&self) -> f64 {
+        ratio(self.compute_units_consumed, MAX_BLOCK_UNITS)
+    }
+}
+
+/// How many of a set of blocks' conflicting accesses were between transactions of equal
+/// priority, which often dominate blocks flagged for churn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TieStats {
+    pub conflict_count: usize,
+    pub tie_count: usize,
+    /// Ties between transactions that both had no priority.
+    pub zero_priority_tie_count: usize,
+    /// Accounts with at least one tie, summed over blocks.
+    pub tied_account_count: usize,
+}
+
+impl TieStats {
+    pub fn add(&mut self, other: TieStats) {
+        self.conflict_count += other.conflict_count;
+        self.tie_count += other.tie_count;
+        self.zero_priority_tie_count += other.zero_priority_tie_count;
+        self.tied_account_count += other.tied_account_count;
+    }
+}
+
+impl std::fmt::Display for TieStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} conflicts ({:.1}%) at equal priority, {} of them at zero priority, on {} \
+             accounts",
+            self.tie_count,
+            self.conflict_count,
+            ratio(self.tie_count as u64, self.conflict_count as u64) * 100.0,
+            self.zero_priority_tie_count,
+            self.tied_account_count
+        )
+    }
+}
+
+/// Violation counts normalized so that blocks of different sizes, and leaders with different
+/// traffic, can be compared.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NormalizedRates {
+    /// Violating transactions per 100 non-vote transactions.
+    pub per_100_transactions: f64,
+    /// Account-level violations per contended account.
+    pub per_contended_account: f64,
+}
+
+impl NormalizedRates {
+    pub fn new(
+        violating_transaction_count: usize,
+        non_vote_transaction_count: usize,
+        account_violation_count: usize,
+        contended_account_count: usize,
+    ) -> Self {
+        Self {
+            per_100_transactions: ratio(
+                violating_transaction_count as u64,
+                non_vote_transaction_count as u64,
+            ) * 100.0,
+            per_contended_account: ratio(
+                account_violation_count as u64,
+                contended_account_count as u64,
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for NormalizedRates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.2} per 100 non-vote transactions, {:.3} per contended account",
+            self.per_100_transactions, self.per_contended_account
+        )
+    }
+}
+
+/// Summary statistics over a set of priorities.
+pub struct PriorityDistribution {
+    pub min: u64,
+    pub p25: u64,
+    pub median: u64,
+    pub p75: u64,
+    pub p90: u64,
+    pub max: u64,
+    pub nonzero_count: usize,
+}
+
+impl PriorityDistribution {
+    pub fn new(priorities: &[u64]) -> Self {
+        let mut sorted = priorities.to_vec();
+        sorted.sort_unstable();
+        Self {
+            min: percentile(&sorted, 0.0),
+            p25: percentile(&sorted, 0.25),
+            median: percentile(&sorted, 0.5),
+            p75: percentile(&sorted, 0.75),
+            p90: percentile(&sorted, 0.9),
+            max: percentile(&sorted, 1.0),
+            nonzero_count: sorted.iter().filter(|priority| **priority > 0).count(),
+        }
+    }
+}
+
+/// `numerator / denominator`, or 0 if the denominator is 0.
+pub fn ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// Nearest-rank percentile of an already sorted slice. Returns 0 for an empty slice.
+pub fn percentile(sorted: &[u64], fraction: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let index = ((sorted.len() - 1) as f64 * fraction).round() as usize;
+    sorted[index]
+}
+
+/// Analyzes a single block with fresh scratch state. Use an [`Analyzer`] to analyze many.
+pub fn analyze_block(
+    slot: Slot,
+    block: UiConfirmedBlock,
+    config: &AnalysisConfig,
+) -> BlockAnalysis {
+    Analyzer::default().analyze(slot, block, config)
+}
+
+/// Scratch state for analyzing blocks: the last access of every account, the violation buffer,
+/// and a [`BlockDecoder`]. Reusing one across blocks keeps range scans and the watcher from
+/// reallocating all of it for every slot.
+#[derive(Default)]
+pub struct Analyzer {
+    last_access_map: HashMap<Pubkey, LastAccessPriority>,
+    violating_signatures: HashSet<Signature>,
+    decoder: BlockDecoder,
+}
+
+impl Analyzer {
+    /// Forgets everything about the previous block while keeping the allocations.
+    pub fn reset(&mut self) {
+        self.last_access_map.clear();
+        self.violating_signatures.clear();
+    }
+
+    pub fn analyze(
+        &mut self,
+        slot: Slot,
+        block: UiConfirmedBlock,
+        config: &AnalysisConfig,
+    ) -> BlockAnalysis {
+        let decoded = self.decoder.decode(slot, block, config);
+        self.analyze_decoded(decoded, config)
+    }
+
+    /// Runs the conflict pass over a block decoded by a [`BlockDecoder`], possibly on another
+    /// thread.
+    pub fn analyze_decoded(
+        &mut self,
+        decoded: DecodedBlock,
+        config: &AnalysisConfig,
+    ) -> BlockAnalysis {
+        let start = Instant::now();
+        self.reset();
+        let Self {
+            last_access_map,
+            violating_signatures,
+            ..
+        } = self;
+        let DecodedBlock {
+            slot,
+            block_time,
+            transactions,
+            compute_units_consumed,
+            timings,
+            fetch,
+        } = decoded;
+        let mut analysis = BlockAnalysis {
+            slot,
+            block_time,
+            transactions: Vec::with_capacity(transactions.len()),
+            violated_accounts: HashMap::new(),
+            violating_transaction_signatures: Vec::new(),
+            write_lock_counts: HashMap::new(),
+            conflict_count: 0,
+            ties: Vec::new(),
+            compute_units_consumed,
+            timings,
+            fetch,
+        };
+
+        for decoded in transactions {
+            let DecodedTransaction {
+                mut summary,
+                transaction,
+                loaded_addresses,
+                inner_instructions,
+            } = decoded;
+            if !summary.is_analyzed {
+                analysis.transactions.push(summary);
+                continue;
+            }
+            let TransactionSummary {
+                signature,
+                index,
+                priority,
+                fee_payer,
+                ..
+            } = summary;
+            let mut is_violation = false;
+
+            let conflicts_start = Instant::now();
+            let locked_accounts = if config.violation_rule.is_some() {
+                [
+                    summary.writable_accounts.as_slice(),
+                    &summary.readonly_accounts,
+                ]
+                .concat()
+            } else {
+                Vec::new()
+            };
+            violating_signatures.clear();
+            for &write_account in summary
+                .writable_accounts
+                .iter()
+                .filter(|account| config.tracks_account(account))
+            {
+                *analysis.write_lock_counts.entry(write_account).or_default() += 1;
+                match last_access_map.entry(write_account) {
+                    Entry::Occupied(mut entry) => {
+                        analysis.conflict_count += 1;
+                        if entry.get().priority == priority {
+                            analysis.ties.push(Tie {
+                                account: write_account,
+                                kind: ViolationKind::new(
+                                    entry.get().last_access,
+                                    LastAccess::Write,
+                                ),
+                                priority,
+                                previous_index: entry.get().index,
+                                index,
+                            });
+                        }
+                        if config.is_priority_violation(
+                            entry.get(),
+                            LastAccess::Write,
+                            priority,
+                            &write_account,
+                            &locked_accounts,
+                        ) && config.reports_violation(
+                            analysis.slot,
+                            entry.get(),
+                            ViolationKind::new(entry.get().last_access, LastAccess::Write),
+                            &write_account,
+                            &summary,
+                        ) {
+                            is_violation = true;
+                            analysis
+                                .violated_accounts
+                                .entry(write_account)
+                                .or_default()
+                                .push(Violation {
+                                    kind: ViolationKind::new(
+                                        entry.get().last_access,
+                                        LastAccess::Write,
+                                    ),
+                                    previous_priority: entry.get().priority,
+                                    priority,
+                                    previous_index: entry.get().index,
+                                    index,
+                                });
+                            violating_signatures.insert(entry.get().signature);
+                        }
+
+                        entry.insert(LastAccessPriority {
+                            last_access: LastAccess::Write,
+                            priority,
+                            signature,
+                            fee_payer,
+                            index,
+                        });
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(LastAccessPriority {
+                            last_access: LastAccess::Write,
+                            priority,
+                            signature,
+                            fee_payer,
+                            index,
+                        });
+                    }
+                }
+            }
+
+            for &read_account in summary
+                .readonly_accounts
+                .iter()
+                .filter(|account| config.tracks_account(account))
+            {
+                match last_access_map.entry(read_account) {
+                    Entry::Occupied(mut entry) => {
+                        if entry.get().last_access == LastAccess::Write {
+                            analysis.conflict_count += 1;
+                            if entry.get().priority == priority {
+                                analysis.ties.push(Tie {
+                                    account: read_account,
+                                    kind: ViolationKind::WriteRead,
+                                    priority,
+                                    previous_index: entry.get().index,
+                                    index,
+                                });
+                            }
+                        }
+                        if entry.get().last_access == LastAccess::Write
+                            && config.is_priority_violation(
+                                entry.get(),
+                                LastAccess::Read,
+                                priority,
+                                &read_account,
+                                &locked_accounts,
+                            )
+                            && config.reports_violation(
+                                analysis.slot,
+                                entry.get(),
+                                ViolationKind::WriteRead,
+                                &read_account,
+                                &summary,
+                            )
+                        {
+                            is_violation = true;
+                            analysis
+                                .violated_accounts
+                                .entry(read_account)
+                                .or_default()
+                                .push(Violation {
+                                    kind: ViolationKind::WriteRead,
+                                    previous_priority: entry.get().priority,
+                                    priority,
+                                    previous_index: entry.get().index,
+                                    index,
+                                });
+                            violating_signatures.insert(entry.get().signature);
+                        }
+
+                        entry.insert(LastAccessPriority {
+                            last_access: LastAccess::Read,
+                            priority,
+                            signature,
+                            fee_payer,
+                            index,
+                        });
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(LastAccessPriority {
+                            last_access: LastAccess::Read,
+                            priority,
+                            signature,
+                            fee_payer,
+                            index,
+                        });
+                    }
+                }
+            }
+
+            analysis.timings.conflicts += conflicts_start.elapsed();
+            summary.is_violation = is_violation;
+            if is_violation {
+                summary.inner_instructions = resolve_inner_instructions(
+                    inner_instructions,
+                    transaction.get_message().message.static_account_keys(),
+                    &loaded_addresses,
+                );
+            }
+            analysis.transactions.push(summary);
+
+            if is_violation {
+                let mut previous_signatures: Vec<_> = violating_signatures.drain().collect();
+                previous_signatures.sort();
+
+                analysis
+                    .violating_transaction_signatures
+                    .push((previous_signatures, signature));
+            }
+        }
+
+        if let Some(anonymizer) = &config.anonymizer {
+            anonymizer.anonymize(&mut analysis);
+        }
+        analysis.timings.total += start.elapsed();
+        analysis
+    }
+}
+
+/// A block's transactions decoded and summarized, ready for the conflict pass.
+pub struct DecodedBlock {
+    slot: Slot,
+    block_time: Option<UnixTimestamp>,
+    transactions: Vec<DecodedTransaction>,
+    compute_units_consumed: u64,
+    timings: AnalysisTimings,
+    /// How the block was obtained, filled in by the caller that fetched it.
+    pub fetch: FetchMetadata,
+}
+
+impl DecodedBlock {
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+}
+
+/// A transaction's summary, along with what the conflict pass needs to resolve its inner
+/// instructions if it turns out to be a violation.
+struct DecodedTransaction {
+    summary: TransactionSummary,
+    transaction: SanitizedVersionedTransaction,
+    loaded_addresses: LoadedAddresses,
+    inner_instructions: Vec<UiInnerInstructions>,
+}
+
+/// Scratch state for decoding blocks: parsed addresses.
+/// Decoding needs no state from other blocks, so blocks can be decoded on several threads ahead
+/// of the conflict pass.
+#[derive(Default)]
+pub struct BlockDecoder {
+    pubkeys: PubkeyParser,
+}
+
+impl BlockDecoder {
+    /// Decodes every transaction of `block` and computes its priority and account locks.
+    pub fn decode(
+        &mut self,
+        slot: Slot,
+        block: UiConfirmedBlock,
+        config: &AnalysisConfig,
+    ) -> DecodedBlock {
+        let start = Instant::now();
+        self.pubkeys.parsed.clear();
+        let Self { pubkeys } = self;
+        let mut header_only_count = 0;
+
+        let transactions = block.transactions.unwrap_or_else(|| {
+            eprintln!("Block does not have transactions, something is misconfigured");
+            exit(1);
+        });
+        let mut decoded = DecodedBlock {
+            slot,
+            block_time: block.block_time,
+            transactions: Vec::with_capacity(transactions.len()),
+            compute_units_consumed: 0,
+            timings: AnalysisTimings::default(),
+            fetch: FetchMetadata::default(),
+        };
+        for (index, transaction) in transactions.into_iter().enumerate() {
+            let meta = transaction.meta.unwrap_or_else(|| {
+                eprintln!("Transactions do not have metadata, something is misconfigured");
+                exit(1);
+            });
+            let decode_start = Instant::now();
+            let versioned_transaction = transaction.transaction.decode().unwrap_or_else(|| {
+                eprintln!("Failed to decode transaction");
+                exit(1);
+            });
+            let signature = *versioned_transaction.signatures.first().unwrap_or_else(|| {
+                eprintln!("Failed to decode transaction: it has no signatures");
+                exit(1);
+            });
+            let sanitized_transaction = SanitizedVersionedTransaction::try_new(
+                versioned_transaction,
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to sanitize transaction: {err}");
+                exit(1);
+            });
+            decoded.timings.decode += decode_start.elapsed();
+            let priority_start = Instant::now();
+            let mut priority = config
+                .priority_model
+                .priority(&sanitized_transaction, &meta);
+            let is_vote = is_simple_vote_transaction(&sanitized_transaction);
+            let compute_budget_usage = get_compute_budget_usage(&sanitized_transaction);
+            let unprioritized = config
+                .unprioritized
+                .filter(|_| !is_vote && !compute_budget_usage.sets_price);
+            match unprioritized {
+                Some(Unprioritized::Zero) => priority = 0,
+                Some(Unprioritized::BaseFee) => {
+                    priority = base_fee_priority(&sanitized_transaction)
+                }
+                Some(Unprioritized::Exclude) | None => {}
+            }
+            decoded.timings.priority += priority_start.elapsed();
+
+            let versioned_message = &sanitized_transaction.get_message().message;
+            let uses_lookup_tables = versioned_message
+                .address_table_lookups()
+                .is_some_and(|lookups| !lookups.is_empty());
+            let loaded_addresses = Option::<UiLoadedAddresses>::from(meta.loaded_addresses)
+                .map(|addresses| LoadedAddresses {
+                    writable: addresses
+                        .writable
+                        .iter()
+                        .map(|address| pubkeys.parse(address))
+                        .collect(),
+                    readonly: addresses
+                        .readonly
+                        .iter()
+                        .map(|address| pubkeys.parse(address))
+                        .collect(),
+                })
+                .filter(|_| uses_lookup_tables);
+            // Without its loaded addresses, or with ones that do not match its lookups, a
+            // transaction using lookup tables is analyzed on the accounts in its message alone.
+            let message = match &loaded_addresses {
+                Some(loaded_addresses) => {
+                    sanitize_message(&sanitized_transaction, loaded_addresses)
+                }
+                None if uses_lookup_tables => None,
+                None => sanitize_message(&sanitized_transaction, &LoadedAddresses::default()),
+            };
+            let accounts = match &message {
+                Some(message) => MessageAccounts::new(message),
+                None => {
+                    header_only_count += 1;
+                    MessageAccounts::from_header(versioned_message)
+                }
+            };
+            let loaded_addresses = loaded_addresses
+                .filter(|_| message.is_some())
+                .unwrap_or_default();
+            let compute_units_consumed =
+                Option::<u64>::from(meta.compute_units_consumed).unwrap_or_default();
+            decoded.compute_units_consumed += compute_units_consumed;
+            let inner_instructions =
+                Option::<Vec<UiInnerInstructions>>::from(meta.inner_instructions)
+                    .unwrap_or_default();
+            let is_analyzed = (config.include_votes || !is_vote)
+                && unprioritized != Some(Unprioritized::Exclude)
+                && (config.programs.is_empty()
+                    || invokes_any_program(
+                        &accounts.program_ids,
+                        &AccountKeys::new(
+                            versioned_message.static_account_keys(),
+                            Some(&loaded_addresses),
+                        ),
+                        &inner_instructions,
+                        &config.programs,
+                    ));
+            let MessageAccounts {
+                writable_accounts,
+                readonly_accounts,
+                fee_payer,
+                program_ids,
+            } = accounts;
+            let summary = TransactionSummary {
+                signature,
+                index,
+                priority,
+                is_vote,
+                is_analyzed,
+                is_durable_nonce: is_durable_nonce_transaction(&sanitized_transaction),
+                is_violation: false,
+                error: meta.err,
+                fee: meta.fee,
+                priority_fee: meta.fee.saturating_sub(base_fee(&sanitized_transaction)),
+                compute_unit_limit: get_compute_unit_limit(&sanitized_transaction),
+                compute_units_consumed,
+                fee_payer,
+                program_ids,
+                writable_accounts,
+                readonly_accounts,
+                compute_budget_usage,
+                compute_budget_issues: get_compute_budget_issues(&sanitized_transaction),
+                inner_instructions: Vec::new(),
+            };
+            decoded.transactions.push(DecodedTransaction {
+                summary,
+                transaction: sanitized_transaction,
+                loaded_addresses,
+                inner_instructions,
+            });
+        }
+
+        if header_only_count > 0 {
+            eprintln!(
+                "Warning: {} transactions in slot {} lack usable loaded addresses, analyzing \
+                 only the accounts in their messages, so conflicts on accounts from lookup \
+                 tables are missed",
+                header_only_count, slot
+            );
+        }
+        decoded.timings.total = start.elapsed();
+        decoded
+    }
+}
+
+/// The transaction's message with `loaded_addresses` applied, or `None` if they do not match
+/// its lookups.
+fn sanitize_message(
+    transaction: &SanitizedVersionedTransaction,
+    loaded_addresses: &LoadedAddresses,
+) -> Option<SanitizedMessage> {
+    SanitizedMessage::try_new(
+        transaction.get_message().clone(),
+        SimpleAddressLoader::Enabled(loaded_addresses.clone()),
+    )
+    .ok()
+}
+
+/// The accounts a transaction locks and the programs it calls at the top level.
+struct MessageAccounts {
+    writable_accounts: Vec<Pubkey>,
+    readonly_accounts: Vec<Pubkey>,
+    fee_payer: Pubkey,
+    program_ids: Vec<Pubkey>,
+}
+
+impl MessageAccounts {
+    /// Accounts the runtime write-locks and read-locks for the transaction, in account key
+    /// order.
+    ///
+    /// Keys come from the message and its loaded addresses, with the runtime's demotions
+    /// applied: program ids, sysvars, and builtin programs are locked readonly even when the
+    /// message marks them writable.
+    fn new(message: &SanitizedMessage) -> Self {
+        let mut writable_accounts = Vec::new();
+        let mut readonly_accounts = Vec::new();
+        for (index, key) in message.account_keys().iter().enumerate() {
+            if message.is_writable(index) {
+                writable_accounts.push(*key);
+            } else {
+                readonly_accounts.push(*key);
+            }
+        }
+        let mut program_ids: Vec<Pubkey> = Vec::new();
+        for (program_id, _) in message.program_instructions_iter() {
+            if !program_ids.contains(program_id) {
+                program_ids.push(*program_id);
+            }
+        }
+        Self {
+            writable_accounts,
+            readonly_accounts,
+            fee_payer: *message.fee_payer(),
+            program_ids,
+        }
+    }
+
+    /// Like [`MessageAccounts::new`], from the message's static account keys and header alone,
+    /// for transactions whose loaded addresses are unavailable.
+    fn from_header(message: &VersionedMessage) -> Self {
+        let static_keys = message.static_account_keys();
+        let mut writable_accounts = Vec::new();
+        let mut readonly_accounts = Vec::new();
+        for (index, key) in static_keys.iter().enumerate() {
+            if message.is_maybe_writable(index) {
+                writable_accounts.push(*key);
+            } else {
+                readonly_accounts.push(*key);
+            }
+        }
+        let mut program_ids: Vec<Pubkey> = Vec::new();
+        for instruction in message.instructions() {
+            if let Some(program_id) = static_keys.get(instruction.program_id_index as usize) {
+                if !program_ids.contains(program_id) {
+                    program_ids.push(*program_id);
+                }
+            }
+        }
+        Self {
+            writable_accounts,
+            readonly_accounts,
+            fee_payer: static_keys[0],
+            program_ids,
+        }
+    }
+}
+
+/// Whether any top-level or inner instruction of the transaction invokes one of `programs`.
+fn invokes_any_program(
+    program_ids: &[Pubkey],
+    account_keys: &AccountKeys,
+    inner_instructions: &[UiInnerInstructions],
+    programs: &[Pubkey],
+) -> bool {
+    let inner_program_ids = inner_instructions
+        .iter()
+        .flat_map(|inner| &inner.instructions)
+        .filter_map(|instruction| match instruction {
+            UiInstruction::Compiled(compiled) => {
+                account_keys.get(compiled.program_id_index as usize)
+            }
+            UiInstruction::Parsed(_) => None,
+        });
+    program_ids
+        .iter()
+        .chain(inner_program_ids)
+        .any(|program_id| programs.contains(program_id))
+}
+
+/// Parses base58 pubkeys, remembering each one, since the loaded addresses of a block's
+/// transactions mostly come from the same few lookup tables and base58 decoding is slow.
+#[derive(Default)]
+struct PubkeyParser {
+    parsed: HashMap<String, Pubkey>,
+}
+
+impl PubkeyParser {
+    fn parse(&mut self, s: &str) -> Pubkey {
+        if let Some(pubkey) = self.parsed.get(s) {
+            return *pubkey;
+        }
+        let pubkey = parse_pubkey(s);
+        self.parsed.insert(s.to_string(), pubkey);
+        pubkey
+    }
+}
+
+fn parse_pubkey(s: impl AsRef<str>) -> Pubkey {
+    Pubkey::from_str(s.as_ref()).unwrap_or_else(|err| {
+        eprintln!("Failed to parse pubkey {}: {}", s.as_ref(), err);
+        exit(1);
+    })
+}
+
+/// Mirrors the runtime's simple vote classification: a vote program instruction first, with at
+/// most two instructions and two signatures.
+fn is_simple_vote_transaction(transaction: &SanitizedVersionedTransaction) -> bool {
+    let message = transaction.get_message();
+    message.message.instructions().len() < 3
+        && message.message.header().num_required_signatures < 3
+        && message
+            .program_instructions_iter()
+            .next()
+            .is_some_and(|(program_id, _)| vote::program::check_id(program_id))
+}
+
+/// Whether the transaction advances a durable nonce in its first instruction, and so was signed
+/// against a nonce rather than a recent blockhash.
+fn is_durable_nonce_transaction(transaction: &SanitizedVersionedTransaction) -> bool {
+    transaction
+        .get_message()
+        .program_instructions_iter()
+        .next()
+        .is_some_and(|(program_id, ix)| {
+            system_program::check_id(program_id)
+                && matches!(
+                    limited_deserialize(&ix.data),
+                    Ok(SystemInstruction::AdvanceNonceAccount)
+                )
+        })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LastAccess {
+    Read,
+    Write,
+}
+
+impl LastAccess {
+    fn as_str(self) -> &'static str {
+        match self {
+            LastAccess::Read => "read",
+            LastAccess::Write => "write",
+        }
+    }
+}
+
+struct LastAccessPriority {
+    last_access: LastAccess,
+    priority: u64,
+    signature: Signature,
+    fee_payer: Pubkey,
+    index: usize,
+}
@@ -0,0 +1,112 @@
+use {
+    crate::analysis::BlockAnalysis,
+    serde_json::Value,
+    solana_sdk::{
+        address_lookup_table, compute_budget, hash::hashv, pubkey::Pubkey, sdk_ids::SDK_IDS,
+        signature::Signature,
+    },
+    std::{collections::HashMap, str::FromStr},
+};
+
+/// Replaces the account keys and transaction signatures in analyses with salted hashes, so
+/// violation evidence can be shared without exposing the accounts involved. Signatures are
+/// replaced too, as any one of them leads straight to its transaction's accounts.
+///
+/// Hashes are stable for a given salt, so the same account keeps the same stand-in across
+/// blocks and runs, and keys and signatures keep their types and formats, so every report keeps
+/// its structure. Builtin programs and sysvars are left as they are, as they identify no one.
+pub struct Anonymizer {
+    salt: Vec<u8>,
+}
+
+// Keeps the salt out of debug output.
+impl std::fmt::Debug for Anonymizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Anonymizer").finish_non_exhaustive()
+    }
+}
+
+impl Anonymizer {
+    pub fn new(salt: &[u8]) -> Self {
+        Self {
+            salt: salt.to_vec(),
+        }
+    }
+
+    pub fn pubkey(&self, pubkey: &Pubkey) -> Pubkey {
+        if SDK_IDS.contains(pubkey)
+            || *pubkey == compute_budget::id()
+            || *pubkey == address_lookup_table::program::id()
+        {
+            return *pubkey;
+        }
+        Pubkey::new_from_array(hashv(&[&self.salt, b"pubkey", pubkey.as_ref()]).to_bytes())
+    }
+
+    pub fn signature(&self, signature: &Signature) -> Signature {
+        let first = hashv(&[&self.salt, b"signature0", signature.as_ref()]);
+        let second = hashv(&[&self.salt, b"signature1", signature.as_ref()]);
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(first.as_ref());
+        bytes[32..].copy_from_slice(second.as_ref());
+        Signature::from(bytes)
+    }
+
+    /// Replaces every account key and signature in `analysis`, including addresses in parsed
+    /// inner instructions.
+    pub fn anonymize(&self, analysis: &mut BlockAnalysis) {
+        self.rekey(&mut analysis.violated_accounts);
+        self.rekey(&mut analysis.write_lock_counts);
+        for tie in &mut analysis.ties {
+            tie.account = self.pubkey(&tie.account);
+        }
+        for (previous_signatures, signature) in &mut analysis.violating_transaction_signatures {
+            for previous_signature in previous_signatures {
+                *previous_signature = self.signature(previous_signature);
+            }
+            *signature = self.signature(signature);
+        }
+        for transaction in &mut analysis.transactions {
+            transaction.signature = self.signature(&transaction.signature);
+            transaction.fee_payer = self.pubkey(&transaction.fee_payer);
+            for pubkey in transaction
+                .program_ids
+                .iter_mut()
+                .chain(&mut transaction.writable_accounts)
+                .chain(&mut transaction.readonly_accounts)
+            {
+                *pubkey = self.pubkey(pubkey);
+            }
+            for instruction in &mut transaction.inner_instructions {
+                instruction.program_id = self.pubkey(&instruction.program_id);
+                if let Some((_, parsed)) = &mut instruction.parsed {
+                    if let Ok(mut value) = serde_json::from_str(parsed) {
+                        self.json(&mut value);
+                        *parsed = value.to_string();
+                    }
+                }
+            }
+        }
+    }
+
+    fn rekey<T>(&self, map: &mut HashMap<Pubkey, T>) {
+        *map = map
+            .drain()
+            .map(|(pubkey, value)| (self.pubkey(&pubkey), value))
+            .collect();
+    }
+
+    /// Replaces every string in `value` that is an account key.
+    fn json(&self, value: &mut Value) {
+        match value {
+            Value::String(string) => {
+                if let Ok(pubkey) = Pubkey::from_str(string) {
+                    *string = self.pubkey(&pubkey).to_string();
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.json(value)),
+            Value::Object(map) => map.values_mut().for_each(|value| self.json(value)),
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
+}
@@ -0,0 +1,877 @@
+use {
+    crate::{analysis::BlockAnalysis, output::OutputFile, report::SCHEMA_VERSION},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        cmp::Reverse,
+        io::Write,
+        path::{Path, PathBuf},
+        process::exit,
+    },
+};
+
+/// Writes every analyzed block as rows of two Arrow IPC files (Feather v2) in a directory, one
+/// row per transaction in `transactions.arrow` and one per account-level violation in
+/// `violations.arrow`, for loading straight into Polars or pyarrow. Each block is written as a
+/// record batch as soon as it is analyzed, so long scans are not held in memory.
+pub struct ArrowReporter {
+    transactions: ArrowFileWriter,
+    violations: ArrowFileWriter,
+}
+
+impl ArrowReporter {
+    const TRANSACTION_FIELDS: [ArrowField; 16] = [
+        ArrowField::new("slot", DataType::UInt64),
+        ArrowField::nullable("leader", DataType::Utf8),
+        ArrowField::new("index", DataType::UInt64),
+        ArrowField::new("signature", DataType::Utf8),
+        ArrowField::new("fee_payer", DataType::Utf8),
+        ArrowField::nullable("program", DataType::Utf8),
+        ArrowField::new("priority", DataType::UInt64),
+        ArrowField::new("fee", DataType::UInt64),
+        ArrowField::new("priority_fee", DataType::UInt64),
+        ArrowField::new("compute_unit_limit", DataType::UInt64),
+        ArrowField::new("compute_units_consumed", DataType::UInt64),
+        ArrowField::new("is_vote", DataType::Bool),
+        ArrowField::new("is_analyzed", DataType::Bool),
+        ArrowField::new("is_durable_nonce", DataType::Bool),
+        ArrowField::new("is_violation", DataType::Bool),
+        ArrowField::new("failed", DataType::Bool),
+    ];
+    const VIOLATION_FIELDS: [ArrowField; 11] = [
+        ArrowField::new("slot", DataType::UInt64),
+        ArrowField::nullable("leader", DataType::Utf8),
+        ArrowField::new("account", DataType::Utf8),
+        ArrowField::new("kind", DataType::Utf8),
+        ArrowField::new("previous_index", DataType::UInt64),
+        ArrowField::new("index", DataType::UInt64),
+        ArrowField::new("previous_signature", DataType::Utf8),
+        ArrowField::new("signature", DataType::Utf8),
+        ArrowField::new("previous_priority", DataType::UInt64),
+        ArrowField::new("priority", DataType::UInt64),
+        ArrowField::new("gap", DataType::UInt64),
+    ];
+
+    /// Writes new files in `dir`, which only replace existing ones once committed.
+    pub fn new(dir: &Path) -> Self {
+        if dir == Path::new("-") {
+            eprintln!("Arrow output is written to a directory and cannot go to stdout");
+            exit(1);
+        }
+        Self {
+            transactions: ArrowFileWriter::new(
+                &dir.join("transactions.arrow"),
+                &Self::TRANSACTION_FIELDS,
+            ),
+            violations: ArrowFileWriter::new(
+                &dir.join("violations.arrow"),
+                &Self::VIOLATION_FIELDS,
+            ),
+        }
+    }
+
+    pub fn write(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        let leader = leader.map(|leader| leader.to_string());
+        let transactions = &analysis.transactions;
+        let uint = |value: fn(&_) -> u64| Column::UInt64(transactions.iter().map(value).collect());
+        let bool = |value: fn(&_) -> bool| Column::Bool(transactions.iter().map(value).collect());
+        self.transactions.write_batch(
+            transactions.len(),
+            vec![
+                Column::UInt64(vec![analysis.slot; transactions.len()]),
+                Column::Utf8(vec![leader.clone(); transactions.len()]),
+                uint(|transaction| transaction.index as u64),
+                Column::Utf8(
+                    transactions
+                        .iter()
+                        .map(|transaction| Some(transaction.signature.to_string()))
+                        .collect(),
+                ),
+                Column::Utf8(
+                    transactions
+                        .iter()
+                        .map(|transaction| Some(transaction.fee_payer.to_string()))
+                        .collect(),
+                ),
+                Column::Utf8(
+                    transactions
+                        .iter()
+                        .map(|transaction| {
+                            transaction
+                                .primary_program()
+                                .map(|program| program.to_string())
+                        })
+                        .collect(),
+                ),
+                uint(|transaction| transaction.priority),
+                uint(|transaction| transaction.fee),
+                uint(|transaction| transaction.priority_fee),
+                uint(|transaction| u64::from(transaction.compute_unit_limit)),
+                uint(|transaction| transaction.compute_units_consumed),
+                bool(|transaction| transaction.is_vote),
+                bool(|transaction| transaction.is_analyzed),
+                bool(|transaction| transaction.is_durable_nonce),
+                bool(|transaction| transaction.is_violation),
+                bool(|transaction| transaction.error.is_some()),
+            ],
+        );
+
+        let mut violations: Vec<_> = analysis
+            .violated_accounts
+            .iter()
+            .flat_map(|(account, violations)| {
+                violations.iter().map(move |violation| (account, violation))
+            })
+            .collect();
+        violations.sort_by_key(|(account, violation)| (violation.index, **account));
+        let signature = |index: usize| Some(transactions[index].signature.to_string());
+        self.violations.write_batch(
+            violations.len(),
+            vec![
+                Column::UInt64(vec![analysis.slot; violations.len()]),
+                Column::Utf8(vec![leader; violations.len()]),
+                Column::Utf8(
+                    violations
+                        .iter()
+                        .map(|(account, _)| Some(account.to_string()))
+                        .collect(),
+                ),
+                Column::Utf8(
+                    violations
+                        .iter()
+                        .map(|(_, violation)| Some(violation.kind.to_string()))
+                        .collect(),
+                ),
+                Column::UInt64(
+                    violations
+                        .iter()
+                        .map(|(_, violation)| violation.previous_index as u64)
+                        .collect(),
+                ),
+                Column::UInt64(
+                    violations
+                        .iter()
+                        .map(|(_, violation)| violation.index as u64)
+                        .collect(),
+                ),
+                Column::Utf8(
+                    violations
+                        .iter()
+                        .map(|(_, violation)| signature(violation.previous_index))
+                        .collect(),
+                ),
+                Column::Utf8(
+                    violations
+                        .iter()
+                        .map(|(_, violation)| signature(violation.index))
+                        .collect(),
+                ),
+                Column::UInt64(
+                    violations
+                        .iter()
+                        .map(|(_, violation)| violation.previous_priority)
+                        .collect(),
+                ),
+                Column::UInt64(
+                    violations
+                        .iter()
+                        .map(|(_, violation)| violation.priority)
+                        .collect(),
+                ),
+                Column::UInt64(
+                    violations
+                        .iter()
+                        .map(|(_, violation)| violation.gap())
+                        .collect(),
+                ),
+            ],
+        );
+    }
+
+    pub fn commit(&mut self) {
+        self.transactions.commit();
+        self.violations.commit();
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ArrowField {
+    name: &'static str,
+    data_type: DataType,
+    nullable: bool,
+}
+
+impl ArrowField {
+    const fn new(name: &'static str, data_type: DataType) -> Self {
+        Self {
+            name,
+            data_type,
+            nullable: false,
+        }
+    }
+
+    const fn nullable(name: &'static str, data_type: DataType) -> Self {
+        Self {
+            name,
+            data_type,
+            nullable: true,
+        }
+    }
+
+    fn table(&self) -> Table {
+        let (type_type, data_type) = match self.data_type {
+            DataType::UInt64 => (
+                TYPE_INT,
+                Table::new(vec![(0, Value::Int(64)), (1, Value::Bool(false))]),
+            ),
+            DataType::Utf8 => (TYPE_UTF8, Table::default()),
+            DataType::Bool => (TYPE_BOOL, Table::default()),
+        };
+        Table::new(vec![
+            (0, Value::String(self.name.to_string())),
+            (1, Value::Bool(self.nullable)),
+            (2, Value::Byte(type_type)),
+            (3, Value::Table(data_type)),
+            (5, Value::Tables(Vec::new())),
+        ])
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DataType {
+    UInt64,
+    Utf8,
+    Bool,
+}
+
+/// One column of a record batch. Only strings can be null.
+#[derive(Clone, Debug, PartialEq)]
+enum Column {
+    UInt64(Vec<u64>),
+    Utf8(Vec<Option<String>>),
+    Bool(Vec<bool>),
+}
+
+// Constants of the Arrow IPC format's flatbuffer schema (Message.fbs, Schema.fbs, File.fbs).
+const METADATA_VERSION_V5: i16 = 4;
+const MESSAGE_SCHEMA: u8 = 1;
+const MESSAGE_RECORD_BATCH: u8 = 3;
+const TYPE_INT: u8 = 2;
+const TYPE_UTF8: u8 = 5;
+const TYPE_BOOL: u8 = 6;
+const MAGIC: &[u8] = b"ARROW1";
+
+/// Writes an Arrow IPC file: the magic, the schema, a record batch per call to
+/// [`ArrowFileWriter::write_batch`], and a footer locating the batches.
+struct ArrowFileWriter {
+    writer: OutputFile,
+    path: PathBuf,
+    schema: Table,
+    position: usize,
+    /// Offset, metadata length and body length of each record batch message.
+    record_batches: Vec<(usize, usize, usize)>,
+}
+
+impl ArrowFileWriter {
+    fn new(path: &Path, fields: &[ArrowField]) -> Self {
+        let schema_version = Table::new(vec![
+            (0, Value::String("schema_version".to_string())),
+            (1, Value::String(SCHEMA_VERSION.to_string())),
+        ]);
+        let schema = Table::new(vec![
+            (
+                1,
+                Value::Tables(fields.iter().map(ArrowField::table).collect()),
+            ),
+            (2, Value::Tables(vec![schema_version])),
+        ]);
+        let mut writer = Self {
+            writer: OutputFile::create(path),
+            path: path.to_path_buf(),
+            schema,
+            position: 0,
+            record_batches: Vec::new(),
+        };
+        writer.write_all(&[MAGIC, &[0, 0]].concat());
+        writer.write_message(MESSAGE_SCHEMA, writer.schema.clone(), &[]);
+        writer
+    }
+
+    /// Writes `columns`, each `length` rows long and in schema order, as one record batch.
+    /// Empty batches are skipped.
+    fn write_batch(&mut self, length: usize, columns: Vec<Column>) {
+        if length == 0 {
+            return;
+        }
+        let mut body = Vec::new();
+        let mut nodes = Vec::new();
+        let mut buffers = Vec::new();
+        let mut push_buffer = |body: &mut Vec<u8>, bytes: &[u8]| {
+            buffers.extend_from_slice(&(body.len() as i64).to_le_bytes());
+            buffers.extend_from_slice(&(bytes.len() as i64).to_le_bytes());
+            body.extend_from_slice(bytes);
+            body.resize(body.len().next_multiple_of(8), 0);
+        };
+        for column in columns {
+            let null_count = match &column {
+                Column::Utf8(values) => values.iter().filter(|value| value.is_none()).count(),
+                Column::UInt64(_) | Column::Bool(_) => 0,
+            };
+            nodes.extend_from_slice(&(length as i64).to_le_bytes());
+            nodes.extend_from_slice(&(null_count as i64).to_le_bytes());
+            match column {
+                Column::UInt64(values) => {
+                    push_buffer(&mut body, &[]);
+                    let values: Vec<_> = values
+                        .iter()
+                        .flat_map(|value| value.to_le_bytes())
+                        .collect();
+                    push_buffer(&mut body, &values);
+                }
+                Column::Bool(values) => {
+                    push_buffer(&mut body, &[]);
+                    push_buffer(&mut body, &bitmap(values.iter().copied()));
+                }
+                Column::Utf8(values) => {
+                    // The validity bitmap can be left out when nothing is null.
+                    let validity = if null_count > 0 {
+                        bitmap(values.iter().map(Option::is_some))
+                    } else {
+                        Vec::new()
+                    };
+                    push_buffer(&mut body, &validity);
+                    let mut offsets = vec![0i32];
+                    let mut data = Vec::new();
+                    for value in &values {
+                        data.extend_from_slice(value.as_deref().unwrap_or_default().as_bytes());
+                        offsets.push(data.len() as i32);
+                    }
+                    let offsets: Vec<_> = offsets
+                        .iter()
+                        .flat_map(|offset| offset.to_le_bytes())
+                        .collect();
+                    push_buffer(&mut body, &offsets);
+                    push_buffer(&mut body, &data);
+                }
+            }
+        }
+
+        let record_batch = Table::new(vec![
+            (0, Value::Long(length as i64)),
+            (1, Value::Structs(nodes.len() / 16, nodes)),
+            (2, Value::Structs(buffers.len() / 16, buffers)),
+        ]);
+        let block = self.write_message(MESSAGE_RECORD_BATCH, record_batch, &body);
+        self.record_batches.push(block);
+    }
+
+    /// Writes the footer and moves the file into place.
+    fn commit(&mut self) {
+        let record_batches = self
+            .record_batches
+            .iter()
+            .flat_map(|(offset, metadata_length, body_length)| {
+                [
+                    (*offset as i64).to_le_bytes(),
+                    // The 4-byte metadata length is followed by 4 bytes of struct padding.
+                    u64::from(*metadata_length as u32).to_le_bytes(),
+                    (*body_length as i64).to_le_bytes(),
+                ]
+            })
+            .flatten()
+            .collect();
+        let footer = Table::new(vec![
+            (0, Value::Short(METADATA_VERSION_V5)),
+            (1, Value::Table(self.schema.clone())),
+            (2, Value::Structs(0, Vec::new())),
+            (3, Value::Structs(self.record_batches.len(), record_batches)),
+        ])
+        .finish();
+        self.write_all(&footer);
+        self.write_all(&(footer.len() as i32).to_le_bytes());
+        self.write_all(MAGIC);
+        self.writer.commit();
+    }
+
+    /// Writes an encapsulated message, returning its offset, metadata length and body length.
+    fn write_message(
+        &mut self,
+        header_type: u8,
+        header: Table,
+        body: &[u8],
+    ) -> (usize, usize, usize) {
+        let metadata = Table::new(vec![
+            (0, Value::Short(METADATA_VERSION_V5)),
+            (1, Value::Byte(header_type)),
+            (2, Value::Table(header)),
+            (3, Value::Long(body.len() as i64)),
+        ])
+        .finish();
+        let offset = self.position;
+        self.write_all(&u32::MAX.to_le_bytes());
+        self.write_all(&(metadata.len() as i32).to_le_bytes());
+        self.write_all(&metadata);
+        self.write_all(body);
+        (offset, 8 + metadata.len(), body.len())
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) {
+        self.writer.write_all(bytes).unwrap_or_else(|err| {
+            eprintln!("Failed to write {}: {}", self.path.display(), err);
+            exit(1);
+        });
+        self.position += bytes.len();
+    }
+}
+
+/// Packs `bits` least significant bit first, as Arrow bitmaps are.
+fn bitmap(bits: impl Iterator<Item = bool>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (index, bit) in bits.enumerate() {
+        if index % 8 == 0 {
+            bytes.push(0);
+        }
+        if bit {
+            *bytes.last_mut().unwrap() |= 1 << (index % 8);
+        }
+    }
+    bytes
+}
+
+/// A flatbuffer table, given as the values of its fields by field slot, which is all the Arrow
+/// metadata needs. Serialized front to back, parents before the children they point to, since
+/// flatbuffer offsets only point forward.
+#[derive(Clone, Default)]
+struct Table {
+    fields: Vec<(u16, Value)>,
+}
+
+#[derive(Clone)]
+enum Value {
+    Bool(bool),
+    Byte(u8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    String(String),
+    Table(Table),
+    Tables(Vec<Table>),
+    /// A vector of 8-byte aligned structs, as their count and bytes.
+    Structs(usize, Vec<u8>),
+}
+
+impl Value {
+    /// Size of the value inside its table: the scalar, or the offset to the value.
+    fn inline_size(&self) -> usize {
+        match self {
+            Value::Bool(_) | Value::Byte(_) => 1,
+            Value::Short(_) => 2,
+            Value::Int(_)
+            | Value::String(_)
+            | Value::Table(_)
+            | Value::Tables(_)
+            | Value::Structs(..) => 4,
+            Value::Long(_) => 8,
+        }
+    }
+}
+
+impl Table {
+    fn new(fields: Vec<(u16, Value)>) -> Self {
+        Self { fields }
+    }
+
+    /// Serializes the table as the root of a flatbuffer, padded to a multiple of 8 bytes.
+    fn finish(&self) -> Vec<u8> {
+        let mut buffer = vec![0; 4];
+        let root = self.write(&mut buffer);
+        patch_offset(&mut buffer, 0, root);
+        buffer.resize(buffer.len().next_multiple_of(8), 0);
+        buffer
+    }
+
+    /// Appends the table's vtable, the table, and then everything it points to, returning the
+    /// table's position.
+    fn write(&self, buffer: &mut Vec<u8>) -> usize {
+        // Fields largest first after the vtable offset, so each is aligned to its size.
+        let mut fields: Vec<_> = self.fields.iter().collect();
+        fields.sort_by_key(|(slot, value)| (Reverse(value.inline_size()), *slot));
+        let mut size = 4usize;
+        let mut layout = Vec::with_capacity(fields.len());
+        for (slot, value) in fields {
+            size = size.next_multiple_of(value.inline_size());
+            layout.push((*slot, value, size));
+            size += value.inline_size();
+        }
+
+        let slot_count = self
+            .fields
+            .iter()
+            .map(|(slot, _)| usize::from(*slot) + 1)
+            .max()
+            .unwrap_or_default();
+        let mut vtable = vec![0u16; 2 + slot_count];
+        vtable[0] = (2 * vtable.len()) as u16;
+        vtable[1] = size as u16;
+        for (slot, _, offset) in &layout {
+            vtable[2 + usize::from(*slot)] = *offset as u16;
+        }
+        buffer.resize(buffer.len().next_multiple_of(2), 0);
+        let vtable_position = buffer.len();
+        buffer.extend(vtable.iter().flat_map(|entry| entry.to_le_bytes()));
+
+        buffer.resize(buffer.len().next_multiple_of(8), 0);
+        let position = buffer.len();
+        buffer.resize(position + size, 0);
+        buffer[position..position + 4]
+            .copy_from_slice(&((position - vtable_position) as i32).to_le_bytes());
+        let mut references = Vec::new();
+        for (_, value, offset) in layout {
+            let at = position + offset;
+            let mut put = |bytes: &[u8]| buffer[at..at + bytes.len()].copy_from_slice(bytes);
+            match value {
+                Value::Bool(value) => put(&[u8::from(*value)]),
+                Value::Byte(value) => put(&[*value]),
+                Value::Short(value) => put(&value.to_le_bytes()),
+                Value::Int(value) => put(&value.to_le_bytes()),
+                Value::Long(value) => put(&value.to_le_bytes()),
+                Value::String(_) | Value::Table(_) | Value::Tables(_) | Value::Structs(..) => {
+                    references.push((at, value))
+                }
+            }
+        }
+
+        for (at, value) in references {
+            let target = match value {
+                Value::String(string) => {
+                    buffer.resize(buffer.len().next_multiple_of(4), 0);
+                    let target = buffer.len();
+                    buffer.extend_from_slice(&(string.len() as u32).to_le_bytes());
+                    buffer.extend_from_slice(string.as_bytes());
+                    buffer.push(0);
+                    target
+                }
+                Value::Table(table) => table.write(buffer),
+                Value::Tables(tables) => {
+                    buffer.resize(buffer.len().next_multiple_of(4), 0);
+                    let target = buffer.len();
+                    buffer.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+                    let elements = buffer.len();
+                    buffer.resize(elements + 4 * tables.len(), 0);
+                    for (index, table) in tables.iter().enumerate() {
+                        let table_position = table.write(buffer);
+                        patch_offset(buffer, elements + 4 * index, table_position);
+                    }
+                    target
+                }
+                Value::Structs(count, bytes) => {
+                    // The length precedes the structs, which must be 8-byte aligned.
+                    buffer.resize((buffer.len() + 4).next_multiple_of(8) - 4, 0);
+                    let target = buffer.len();
+                    buffer.extend_from_slice(&(*count as u32).to_le_bytes());
+                    buffer.extend_from_slice(bytes);
+                    target
+                }
+                Value::Bool(_)
+                | Value::Byte(_)
+                | Value::Short(_)
+                | Value::Int(_)
+                | Value::Long(_) => {
+                    unreachable!()
+                }
+            };
+            patch_offset(buffer, at, target);
+        }
+        position
+    }
+}
+
+/// Points the offset at `at` forward to `target`.
+fn patch_offset(buffer: &mut [u8], at: usize, target: usize) {
+    buffer[at..at + 4].copy_from_slice(&((target - at) as u32).to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{env, fs, process},
+    };
+
+    fn u16_at(bytes: &[u8], at: usize) -> usize {
+        u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap()).into()
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> usize {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize
+    }
+
+    fn i64_at(bytes: &[u8], at: usize) -> i64 {
+        i64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    /// A flatbuffer table read by following its vtable, independently of how [`Table`] lays
+    /// tables out.
+    #[derive(Clone, Copy)]
+    struct TableReader<'a> {
+        bytes: &'a [u8],
+        position: usize,
+        vtable: usize,
+    }
+
+    impl<'a> TableReader<'a> {
+        fn root(bytes: &'a [u8]) -> Self {
+            Self::at(bytes, u32_at(bytes, 0))
+        }
+
+        fn at(bytes: &'a [u8], position: usize) -> Self {
+            let soffset = i32::from_le_bytes(bytes[position..position + 4].try_into().unwrap());
+            Self {
+                bytes,
+                position,
+                vtable: (position as i64 - i64::from(soffset)) as usize,
+            }
+        }
+
+        /// Position of the field in `slot`, if present.
+        fn field(&self, slot: usize) -> Option<usize> {
+            let entry = 4 + 2 * slot;
+            if entry >= u16_at(self.bytes, self.vtable) {
+                return None;
+            }
+            let offset = u16_at(self.bytes, self.vtable + entry);
+            (offset != 0).then_some(self.position + offset)
+        }
+
+        fn scalar<const N: usize>(&self, slot: usize) -> [u8; N] {
+            let at = self.field(slot).expect("scalar field present");
+            assert_eq!(at % N, 0, "field {} is misaligned", slot);
+            self.bytes[at..at + N].try_into().unwrap()
+        }
+
+        fn follow(&self, slot: usize) -> usize {
+            let at = self.field(slot).expect("offset field present");
+            at + u32_at(self.bytes, at)
+        }
+
+        fn table(&self, slot: usize) -> Self {
+            Self::at(self.bytes, self.follow(slot))
+        }
+
+        fn string(&self, slot: usize) -> String {
+            let at = self.follow(slot);
+            let len = u32_at(self.bytes, at);
+            assert_eq!(self.bytes[at + 4 + len], 0, "string is not NUL-terminated");
+            String::from_utf8(self.bytes[at + 4..at + 4 + len].to_vec()).unwrap()
+        }
+
+        fn tables(&self, slot: usize) -> Vec<Self> {
+            let at = self.follow(slot);
+            (0..u32_at(self.bytes, at))
+                .map(|index| {
+                    let element = at + 4 + 4 * index;
+                    Self::at(self.bytes, element + u32_at(self.bytes, element))
+                })
+                .collect()
+        }
+
+        /// The structs of the vector in `slot`, each read as `words` 8-byte words.
+        fn structs(&self, slot: usize, words: usize) -> Vec<Vec<i64>> {
+            let at = self.follow(slot);
+            assert_eq!((at + 4) % 8, 0, "structs are misaligned");
+            (0..u32_at(self.bytes, at))
+                .map(|index| {
+                    (0..words)
+                        .map(|word| i64_at(self.bytes, at + 4 + 8 * (words * index + word)))
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    /// Name, type id and nullability of each field.
+    type Fields = Vec<(String, u8, bool)>;
+    /// Custom metadata key-value pairs.
+    type Metadata = Vec<(String, String)>;
+
+    /// A schema's fields and its custom metadata.
+    fn read_schema(schema: TableReader) -> (Fields, Metadata) {
+        let fields = schema
+            .tables(1)
+            .into_iter()
+            .map(|field| {
+                let [type_type] = field.scalar(2);
+                if type_type == TYPE_INT {
+                    let data_type = field.table(3);
+                    assert_eq!(i32::from_le_bytes(data_type.scalar(0)), 64);
+                    assert_eq!(data_type.scalar(1), [0], "integers are unsigned");
+                }
+                assert!(field.tables(5).is_empty(), "fields have no children");
+                (field.string(0), type_type, field.scalar(1) == [1])
+            })
+            .collect();
+        let metadata = schema
+            .tables(2)
+            .into_iter()
+            .map(|entry| (entry.string(0), entry.string(1)))
+            .collect();
+        (fields, metadata)
+    }
+
+    /// Reads an Arrow IPC file as its schema and record batches, checking the framing, the
+    /// footer's blocks and the alignment of everything along the way.
+    fn read_file(bytes: &[u8]) -> (Fields, Metadata, Vec<Vec<Column>>) {
+        assert_eq!(&bytes[..8], b"ARROW1\0\0");
+        assert!(bytes.ends_with(MAGIC));
+        let footer_end = bytes.len() - MAGIC.len() - 4;
+        let footer_start = footer_end - u32_at(bytes, footer_end);
+        assert_eq!(footer_start % 8, 0);
+        let footer = TableReader::root(&bytes[footer_start..footer_end]);
+        assert_eq!(i16::from_le_bytes(footer.scalar(0)), METADATA_VERSION_V5);
+        let (fields, metadata) = read_schema(footer.table(1));
+
+        let message = |offset: usize| {
+            assert_eq!(offset % 8, 0);
+            assert_eq!(u32_at(bytes, offset), u32::MAX as usize);
+            let metadata_length = u32_at(bytes, offset + 4);
+            assert_eq!((8 + metadata_length) % 8, 0);
+            let message = TableReader::root(&bytes[offset + 8..offset + 8 + metadata_length]);
+            assert_eq!(i16::from_le_bytes(message.scalar(0)), METADATA_VERSION_V5);
+            (message, 8 + metadata_length)
+        };
+        let (schema_message, _) = message(8);
+        assert_eq!(schema_message.scalar(1), [MESSAGE_SCHEMA]);
+        assert_eq!(
+            read_schema(schema_message.table(2)),
+            (fields.clone(), metadata.clone())
+        );
+
+        let batches = footer
+            .structs(3, 3)
+            .into_iter()
+            .map(|block| {
+                let offset = block[0] as usize;
+                let (message, metadata_length) = message(offset);
+                assert_eq!(block[1] as u32 as usize, metadata_length);
+                assert_eq!(message.scalar(1), [MESSAGE_RECORD_BATCH]);
+                assert_eq!(i64::from_le_bytes(message.scalar(3)), block[2]);
+                let body_start = offset + metadata_length;
+                let body = &bytes[body_start..body_start + block[2] as usize];
+                let record_batch = message.table(2);
+                let length = i64::from_le_bytes(record_batch.scalar(0)) as usize;
+                let nodes = record_batch.structs(1, 2);
+                let mut buffers = record_batch.structs(2, 2).into_iter().map(|buffer| {
+                    let (offset, len) = (buffer[0] as usize, buffer[1] as usize);
+                    assert_eq!(offset % 8, 0);
+                    &body[offset..offset + len]
+                });
+                let bit = |bitmap: &[u8], index: usize| bitmap[index / 8] & (1 << (index % 8)) != 0;
+                let columns = fields
+                    .iter()
+                    .zip(nodes)
+                    .map(|((_, type_type, _), node)| {
+                        assert_eq!(node[0] as usize, length);
+                        let validity = buffers.next().unwrap();
+                        let valid = |index| validity.is_empty() || bit(validity, index);
+                        assert_eq!(
+                            (0..length).filter(|index| !valid(*index)).count(),
+                            node[1] as usize
+                        );
+                        match *type_type {
+                            TYPE_INT => {
+                                let values = buffers.next().unwrap();
+                                assert_eq!(values.len(), 8 * length);
+                                Column::UInt64(
+                                    values
+                                        .chunks(8)
+                                        .map(|value| u64::from_le_bytes(value.try_into().unwrap()))
+                                        .collect(),
+                                )
+                            }
+                            TYPE_UTF8 => {
+                                let offsets = buffers.next().unwrap();
+                                let data = buffers.next().unwrap();
+                                let offset = |index: usize| u32_at(offsets, 4 * index);
+                                assert_eq!(offset(length), data.len());
+                                Column::Utf8(
+                                    (0..length)
+                                        .map(|index| {
+                                            valid(index).then(|| {
+                                                String::from_utf8(
+                                                    data[offset(index)..offset(index + 1)].to_vec(),
+                                                )
+                                                .unwrap()
+                                            })
+                                        })
+                                        .collect(),
+                                )
+                            }
+                            TYPE_BOOL => {
+                                let values = buffers.next().unwrap();
+                                Column::Bool((0..length).map(|index| bit(values, index)).collect())
+                            }
+                            type_type => panic!("unexpected type {}", type_type),
+                        }
+                    })
+                    .collect();
+                assert!(buffers.next().is_none(), "every buffer is read");
+                columns
+            })
+            .collect();
+        (fields, metadata, batches)
+    }
+
+    #[test]
+    fn written_file_reads_back() {
+        let path = env::temp_dir().join(format!("priority-checker-{}.arrow", process::id()));
+        let mut writer = ArrowFileWriter::new(
+            &path,
+            &[
+                ArrowField::new("count", DataType::UInt64),
+                ArrowField::nullable("name", DataType::Utf8),
+                ArrowField::new("flag", DataType::Bool),
+            ],
+        );
+        let with_nulls = vec![
+            Column::UInt64((0..10).map(|index| index * 1000).collect()),
+            Column::Utf8(
+                (0..10)
+                    .map(|index| (index % 3 != 0).then(|| format!("name {}", index)))
+                    .collect(),
+            ),
+            Column::Bool((0..10).map(|index| index % 2 == 0).collect()),
+        ];
+        let without_nulls = vec![
+            Column::UInt64(vec![u64::MAX, 7]),
+            Column::Utf8(vec![Some("é".to_string()), Some(String::new())]),
+            Column::Bool(vec![true, true]),
+        ];
+        writer.write_batch(10, with_nulls.clone());
+        writer.write_batch(
+            0,
+            vec![
+                Column::UInt64(Vec::new()),
+                Column::Utf8(Vec::new()),
+                Column::Bool(Vec::new()),
+            ],
+        );
+        writer.write_batch(2, without_nulls.clone());
+        writer.commit();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (fields, metadata, batches) = read_file(&bytes);
+        assert_eq!(
+            fields,
+            [
+                ("count".to_string(), TYPE_INT, false),
+                ("name".to_string(), TYPE_UTF8, true),
+                ("flag".to_string(), TYPE_BOOL, false),
+            ]
+        );
+        assert_eq!(
+            metadata,
+            [("schema_version".to_string(), SCHEMA_VERSION.to_string())]
+        );
+        assert_eq!(batches, [with_nulls, without_nulls]);
+    }
+}
@@ -0,0 +1,57 @@
+use {
+    crate::analysis::{percentile, BlockAnalysis, PriorityDistribution},
+    solana_sdk::pubkey::Pubkey,
+};
+
+/// How competitive a block's blockspace auction was: how full the block got, what the landed
+/// transactions bid, and the lowest bid that still won a write lock on each of its hottest
+/// accounts.
+pub struct AuctionReport {
+    /// Compute units consumed over the block limit.
+    pub fill: f64,
+    pub transaction_count: usize,
+    pub priorities: PriorityDistribution,
+    /// Accounts write-locked by more than one analyzed transaction.
+    pub contended_account_count: usize,
+    /// The most write-locked of the contended accounts, most first.
+    pub hot_accounts: Vec<HotAccountAuction>,
+}
+
+/// Bids that landed on one hot account.
+pub struct HotAccountAuction {
+    pub account: Pubkey,
+    pub write_count: usize,
+    /// Lowest priority of the transactions that write-locked the account, the price that still
+    /// cleared.
+    pub clearing_price: u64,
+    pub median_priority: u64,
+    pub max_priority: u64,
+}
+
+impl AuctionReport {
+    pub fn new(analysis: &BlockAnalysis, hot_account_count: usize) -> Self {
+        let hot_accounts = analysis
+            .top_contended_accounts(hot_account_count)
+            .into_iter()
+            .filter(|(_, write_count)| *write_count > 1)
+            .map(|(account, write_count)| {
+                let mut priorities = analysis.write_lock_priorities(&account);
+                priorities.sort_unstable();
+                HotAccountAuction {
+                    account,
+                    write_count,
+                    clearing_price: percentile(&priorities, 0.0),
+                    median_priority: percentile(&priorities, 0.5),
+                    max_priority: percentile(&priorities, 1.0),
+                }
+            })
+            .collect();
+        Self {
+            fill: analysis.fill(),
+            transaction_count: analysis.analyzed_transaction_count(),
+            priorities: analysis.priority_distribution(),
+            contended_account_count: analysis.contended_account_count(),
+            hot_accounts,
+        }
+    }
+}
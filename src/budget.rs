@@ -0,0 +1,243 @@
+use {
+    crate::analysis::TransactionSummary,
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        borsh0_10::try_from_slice_unchecked,
+        compute_budget::{self, ComputeBudgetInstruction},
+        transaction::SanitizedVersionedTransaction,
+    },
+};
+
+/// Maximum compute units a single transaction may request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Compute units granted per non-compute-budget instruction when no limit is requested.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+const MIN_HEAP_FRAME_BYTES: u32 = 32 * 1024;
+const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+
+/// Compute-budget usage that makes a transaction's naively parsed priority or limit differ from
+/// what the runtime applies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputeBudgetIssue {
+    /// The instruction at this index repeats an earlier setting; the runtime rejects the
+    /// transaction.
+    DuplicateInstruction(u8),
+    /// The instruction at this index has undecodable data, is the deprecated RequestUnits
+    /// instruction, or requests an invalid heap frame; the runtime rejects the transaction.
+    InvalidInstructionData(u8),
+    /// The requested compute unit limit exceeds the maximum; the runtime clamps it.
+    ComputeUnitLimitClamped(u32),
+}
+
+impl std::fmt::Display for ComputeBudgetIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComputeBudgetIssue::DuplicateInstruction(index) => {
+                write!(f, "duplicate compute-budget instruction at index {}", index)
+            }
+            ComputeBudgetIssue::InvalidInstructionData(index) => {
+                write!(f, "invalid compute-budget instruction at index {}", index)
+            }
+            ComputeBudgetIssue::ComputeUnitLimitClamped(units) => write!(
+                f,
+                "compute unit limit {} clamped to {}",
+                units, MAX_COMPUTE_UNIT_LIMIT
+            ),
+        }
+    }
+}
+
+/// Which compute-budget settings a transaction makes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComputeBudgetUsage {
+    pub sets_price: bool,
+    pub sets_limit: bool,
+    /// Whether it uses the deprecated RequestUnits instruction, which the runtime rejects as
+    /// invalid, so it sets neither.
+    pub requests_units: bool,
+}
+
+impl ComputeBudgetUsage {
+    pub fn is_unset(&self) -> bool {
+        !(self.sets_price || self.sets_limit)
+    }
+}
+
+/// How many transactions make each compute-budget setting, to track adoption of the fee market.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ComputeBudgetAdoption {
+    pub transaction_count: usize,
+    /// Transactions setting a compute unit price.
+    pub price_count: usize,
+    /// Transactions setting a compute unit limit.
+    pub limit_count: usize,
+    /// Transactions using the deprecated RequestUnits instruction, which the runtime rejects.
+    pub request_units_count: usize,
+    /// Transactions making no valid compute-budget setting at all.
+    pub unset_count: usize,
+}
+
+impl ComputeBudgetAdoption {
+    pub fn new<'a>(transactions: impl IntoIterator<Item = &'a TransactionSummary>) -> Self {
+        let mut adoption = Self::default();
+        for transaction in transactions {
+            let usage = transaction.compute_budget_usage;
+            adoption.transaction_count += 1;
+            adoption.price_count += usize::from(usage.sets_price);
+            adoption.limit_count += usize::from(usage.sets_limit);
+            adoption.request_units_count += usize::from(usage.requests_units);
+            adoption.unset_count += usize::from(usage.is_unset());
+        }
+        adoption
+    }
+
+    pub fn add(&mut self, other: ComputeBudgetAdoption) {
+        self.transaction_count += other.transaction_count;
+        self.price_count += other.price_count;
+        self.limit_count += other.limit_count;
+        self.request_units_count += other.request_units_count;
+        self.unset_count += other.unset_count;
+    }
+}
+
+impl std::fmt::Display for ComputeBudgetAdoption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let percent = |count: usize| {
+            if self.transaction_count == 0 {
+                0.0
+            } else {
+                count as f64 * 100.0 / self.transaction_count as f64
+            }
+        };
+        write!(
+            f,
+            "{:.1}% set a CU price, {:.1}% a CU limit, {:.1}% use the rejected RequestUnits, \
+             {:.1}% set nothing",
+            percent(self.price_count),
+            percent(self.limit_count),
+            percent(self.request_units_count),
+            percent(self.unset_count)
+        )
+    }
+}
+
+/// Which compute-budget settings the transaction makes, whether or not the runtime accepts
+/// them.
+pub fn get_compute_budget_usage(transaction: &SanitizedVersionedTransaction) -> ComputeBudgetUsage {
+    let mut usage = ComputeBudgetUsage::default();
+    for (program_id, ix) in transaction.get_message().program_instructions_iter() {
+        if !compute_budget::check_id(program_id) {
+            continue;
+        }
+        match try_from_slice_unchecked(&ix.data) {
+            Ok(ComputeBudgetInstruction::RequestUnitsDeprecated { .. }) => {
+                usage.requests_units = true;
+            }
+            Ok(ComputeBudgetInstruction::SetComputeUnitPrice(_)) => usage.sets_price = true,
+            Ok(ComputeBudgetInstruction::SetComputeUnitLimit(_)) => usage.sets_limit = true,
+            _ => {}
+        }
+    }
+    usage
+}
+
+/// Compute unit price the transaction sets, or 0 without one. The deprecated RequestUnits
+/// instruction sets no price, as the runtime rejects it.
+pub fn get_priority(transaction: &SanitizedVersionedTransaction) -> u64 {
+    for (program_id, ix) in transaction.get_message().program_instructions_iter() {
+        if compute_budget::check_id(program_id) {
+            if let Ok(ComputeBudgetInstruction::SetComputeUnitPrice(price)) =
+                try_from_slice_unchecked(&ix.data)
+            {
+                return price;
+            }
+        }
+    }
+
+    0
+}
+
+/// Compute units the transaction requests, following the runtime's defaults when no limit is
+/// set explicitly.
+pub fn get_compute_unit_limit(transaction: &SanitizedVersionedTransaction) -> u32 {
+    let mut instruction_count: u32 = 0;
+    for (program_id, ix) in transaction.get_message().program_instructions_iter() {
+        if compute_budget::check_id(program_id) {
+            if let Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) =
+                try_from_slice_unchecked(&ix.data)
+            {
+                return units.min(MAX_COMPUTE_UNIT_LIMIT);
+            }
+        } else {
+            instruction_count += 1;
+        }
+    }
+
+    instruction_count
+        .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        .min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// Checks compute-budget instructions the same way the runtime does when loading the
+/// transaction.
+pub fn get_compute_budget_issues(
+    transaction: &SanitizedVersionedTransaction,
+) -> Vec<ComputeBudgetIssue> {
+    let mut issues = Vec::new();
+    let (mut has_limit, mut has_price, mut has_heap_frame, mut has_data_size_limit) =
+        (false, false, false, false);
+    let mut check_duplicate = |seen: &mut bool, index: u8| {
+        if std::mem::replace(seen, true) {
+            issues.push(ComputeBudgetIssue::DuplicateInstruction(index));
+        }
+    };
+    let mut invalid_indexes = Vec::new();
+    let mut clamped_limit = None;
+
+    for (index, (program_id, ix)) in transaction
+        .get_message()
+        .program_instructions_iter()
+        .enumerate()
+    {
+        if !compute_budget::check_id(program_id) {
+            continue;
+        }
+        let index = index as u8;
+        match try_from_slice_unchecked(&ix.data) {
+            Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                check_duplicate(&mut has_limit, index);
+                if units > MAX_COMPUTE_UNIT_LIMIT {
+                    clamped_limit = Some(units);
+                }
+            }
+            Ok(ComputeBudgetInstruction::SetComputeUnitPrice(_)) => {
+                check_duplicate(&mut has_price, index);
+            }
+            Ok(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
+                check_duplicate(&mut has_heap_frame, index);
+                if !(MIN_HEAP_FRAME_BYTES..=MAX_HEAP_FRAME_BYTES).contains(&bytes)
+                    || bytes % 1024 != 0
+                {
+                    invalid_indexes.push(index);
+                }
+            }
+            Ok(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(_)) => {
+                check_duplicate(&mut has_data_size_limit, index);
+            }
+            // Deprecated and rejected by the runtime since its removal feature activated.
+            Ok(ComputeBudgetInstruction::RequestUnitsDeprecated { .. }) | Err(_) => {
+                invalid_indexes.push(index)
+            }
+        }
+    }
+
+    issues.extend(
+        invalid_indexes
+            .into_iter()
+            .map(ComputeBudgetIssue::InvalidInstructionData),
+    );
+    issues.extend(clamped_limit.map(ComputeBudgetIssue::ComputeUnitLimitClamped));
+    issues
+}
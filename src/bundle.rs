@@ -0,0 +1,158 @@
+use {
+    crate::output::write_output,
+    reqwest::Url,
+    serde_json::json,
+    solana_sdk::{clock::Slot, hash::hash},
+    std::{
+        env, mem,
+        path::Path,
+        process::exit,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Everything needed to reproduce and verify the analysis of one slot, written as a
+/// zstd-compressed tar archive for attaching to reports of validator misbehavior: the block as
+/// fetched, the analysis, and a manifest with the tool version, the invocation, and the SHA-256
+/// of every other file.
+pub struct EvidenceBundle {
+    slot: Slot,
+    files: Vec<(&'static str, Vec<u8>)>,
+}
+
+impl EvidenceBundle {
+    const COMPRESSION_LEVEL: i32 = 19;
+
+    pub fn new(slot: Slot) -> Self {
+        Self {
+            slot,
+            files: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: &'static str, contents: Vec<u8>) {
+        self.files.push((name, contents));
+    }
+
+    /// Writes the archive to `path`, with the manifest first.
+    pub fn write(self, path: &Path) {
+        let modified = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let files: Vec<_> = self
+            .files
+            .iter()
+            .map(|(name, contents)| json!({"name": name, "sha256": sha256_hex(contents)}))
+            .collect();
+        let manifest = json!({
+            "tool": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "slot": self.slot,
+            "created": modified,
+            "invocation": redacted_args(),
+            "files": files,
+        });
+        let mut manifest = serde_json::to_vec_pretty(&manifest).unwrap_or_else(|err| {
+            eprintln!("Failed to serialize bundle manifest: {}", err);
+            exit(1);
+        });
+        manifest.push(b'\n');
+
+        let mut archive = Vec::new();
+        append_tar_entry(&mut archive, "manifest.json", &manifest, modified);
+        for (name, contents) in &self.files {
+            append_tar_entry(&mut archive, name, contents, modified);
+        }
+        // Two empty blocks end the archive.
+        archive.resize(archive.len() + 2 * TAR_BLOCK_SIZE, 0);
+        let compressed = zstd::encode_all(archive.as_slice(), Self::COMPRESSION_LEVEL)
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to compress bundle: {}", err);
+                exit(1);
+            });
+        write_output(path, &compressed);
+    }
+}
+
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// Appends a regular file to a ustar archive: a header block, then the contents padded to whole
+/// blocks.
+fn append_tar_entry(archive: &mut Vec<u8>, name: &str, contents: &[u8], modified: u64) {
+    let mut header = [0u8; TAR_BLOCK_SIZE];
+    let mut field = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    field(0, name.as_bytes());
+    field(100, b"0000644\0");
+    field(108, b"0000000\0");
+    field(116, b"0000000\0");
+    field(124, format!("{:011o}\0", contents.len()).as_bytes());
+    field(136, format!("{:011o}\0", modified).as_bytes());
+    // The checksum is computed with its own field filled with spaces.
+    field(148, b"        ");
+    field(156, b"0");
+    field(257, b"ustar\0");
+    field(263, b"00");
+    let checksum: u32 = header.iter().map(|byte| u32::from(*byte)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(contents);
+    let padding = contents.len().next_multiple_of(TAR_BLOCK_SIZE) - contents.len();
+    archive.resize(archive.len() + padding, 0);
+}
+
+/// SHA-256 of `contents` in hex, as `sha256sum` prints it.
+fn sha256_hex(contents: &[u8]) -> String {
+    hash(contents)
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The command line this run was invoked with, with the values of options that may carry
+/// credentials replaced and URLs stripped of anything but their scheme, host and path.
+fn redacted_args() -> Vec<String> {
+    const SECRET_OPTIONS: [&str; 3] = ["--rpc-bearer-token", "--rpc-header", "--proxy"];
+
+    let mut redact_next = false;
+    env::args()
+        .map(|arg| {
+            if mem::take(&mut redact_next) {
+                return "<redacted>".to_string();
+            }
+            for option in SECRET_OPTIONS {
+                if arg == option {
+                    redact_next = true;
+                } else if arg.starts_with(option) && arg[option.len()..].starts_with('=') {
+                    return format!("{}=<redacted>", option);
+                }
+            }
+            redact_url(&arg).unwrap_or(arg)
+        })
+        .collect()
+}
+
+/// `arg`, a URL or `--option=URL`, without its userinfo, query string and fragment, where
+/// providers commonly put API keys, or `None` if it is not a URL.
+fn redact_url(arg: &str) -> Option<String> {
+    let (prefix, value) = match arg.split_once('=') {
+        Some((option, value)) if option.starts_with("--") => (&arg[..option.len() + 1], value),
+        _ => ("", arg),
+    };
+    if !value.contains("://") {
+        return None;
+    }
+    let Ok(mut url) = Url::parse(value) else {
+        return Some(format!("{}<redacted>", prefix));
+    };
+    // Only fails for URLs that cannot have userinfo in the first place.
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.set_query(None);
+    url.set_fragment(None);
+    Some(format!("{}{}", prefix, url))
+}
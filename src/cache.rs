@@ -0,0 +1,184 @@
+use {
+    solana_sdk::clock::Slot,
+    solana_transaction_status::UiConfirmedBlock,
+    std::{
+        env,
+        fs::{self, File, FileTimes},
+        io::BufReader,
+        path::{Path, PathBuf},
+        time::SystemTime,
+    },
+};
+
+/// Fetched blocks kept on disk as zstd-compressed JSON, one `<slot>.json.zst` file per slot, so
+/// repeated analyses of the same slots do not refetch multi-megabyte blocks.
+///
+/// Blocks are fetched at confirmed commitment, so a cached block could in rare cases belong to a
+/// fork that was later abandoned.
+#[derive(Clone)]
+pub struct BlockCache {
+    dir: Option<PathBuf>,
+    refresh: bool,
+    max_size: u64,
+}
+
+/// Settings for the block cache.
+#[derive(Clone, Debug)]
+pub struct CacheOptions {
+    /// Refetch every block, replacing any cached copy.
+    pub refresh: bool,
+    /// Total size of the cached blocks, in bytes, above which the least recently used are
+    /// evicted.
+    pub max_size: u64,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            refresh: false,
+            max_size: BlockCache::DEFAULT_MAX_SIZE_MIB << 20,
+        }
+    }
+}
+
+impl BlockCache {
+    pub const DEFAULT_MAX_SIZE_MIB: u64 = 1024;
+    const COMPRESSION_LEVEL: i32 = 3;
+    const EXTENSION: &'static str = "json.zst";
+
+    /// Cache under `~/.cache/priority-checker`. Disabled if the home directory is unknown.
+    pub fn new(options: &CacheOptions) -> Self {
+        let dir = env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".cache").join("priority-checker"));
+        Self {
+            dir,
+            refresh: options.refresh,
+            max_size: options.max_size,
+        }
+    }
+
+    /// A cache that neither reads nor stores blocks.
+    pub fn disabled() -> Self {
+        Self {
+            dir: None,
+            refresh: false,
+            max_size: 0,
+        }
+    }
+
+    /// A cache for another cluster, in a subdirectory named after it so its slots do not collide
+    /// with these. Its size limit applies separately.
+    pub fn scoped(&self, name: &str) -> Self {
+        Self {
+            dir: self.dir.as_ref().map(|dir| dir.join(name)),
+            ..self.clone()
+        }
+    }
+
+    /// Directory holding the cache, or `None` if caching is disabled.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// The cached block for `slot`, or the result of `fetch`, which is then cached, along with
+    /// whether it came from the cache. Errors from `fetch` are passed on and nothing is cached.
+    pub fn get_or_fetch<E>(
+        &self,
+        slot: Slot,
+        fetch: impl FnOnce() -> Result<UiConfirmedBlock, E>,
+    ) -> Result<(UiConfirmedBlock, bool), E> {
+        if !self.refresh {
+            if let Some(block) = self.read(slot) {
+                return Ok((block, true));
+            }
+        }
+        let block = fetch()?;
+        self.write(slot, &block);
+        Ok((block, false))
+    }
+
+    fn path(&self, slot: Slot) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        Some(dir.join(format!("{}.{}", slot, Self::EXTENSION)))
+    }
+
+    fn read(&self, slot: Slot) -> Option<UiConfirmedBlock> {
+        let path = self.path(slot)?;
+        let file = File::open(&path).ok()?;
+        // The modification time doubles as the last-use time for eviction.
+        let _ = file.set_times(FileTimes::new().set_modified(SystemTime::now()));
+        let block = zstd::Decoder::new(BufReader::new(file))
+            .map_err(|err| err.to_string())
+            .and_then(|decoder| serde_json::from_reader(decoder).map_err(|err| err.to_string()));
+        match block {
+            Ok(block) => Some(block),
+            Err(err) => {
+                eprintln!(
+                    "Warning: ignoring unreadable cached block {}: {}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Failing to cache a block only costs a refetch later, so errors are warnings. The block is
+    /// written to a temporary file first so an interrupted write never leaves a truncated entry.
+    fn write(&self, slot: Slot, block: &UiConfirmedBlock) {
+        let (Some(dir), Some(path)) = (&self.dir, self.path(slot)) else {
+            return;
+        };
+        let temporary_path = path.with_extension("zst.tmp");
+        let result = fs::create_dir_all(dir)
+            .and_then(|()| {
+                let json = serde_json::to_vec(block)?;
+                let compressed = zstd::encode_all(json.as_slice(), Self::COMPRESSION_LEVEL)?;
+                fs::write(&temporary_path, compressed)
+            })
+            .and_then(|()| fs::rename(&temporary_path, &path));
+        if let Err(err) = result {
+            eprintln!(
+                "Warning: failed to cache block {} at {}: {}",
+                slot,
+                path.display(),
+                err
+            );
+            return;
+        }
+        self.evict(dir);
+    }
+
+    /// Removes the least recently used blocks until the cache fits in `max_size`.
+    fn evict(&self, dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut blocks: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let path = entry.path();
+                if !path.to_str()?.ends_with(Self::EXTENSION) {
+                    return None;
+                }
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), path))
+            })
+            .collect();
+        let mut size: u64 = blocks.iter().map(|(_, len, _)| len).sum();
+        blocks.sort_unstable();
+        for (_, len, path) in blocks {
+            if size <= self.max_size {
+                break;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => size -= len,
+                Err(err) => eprintln!(
+                    "Warning: failed to evict cached block {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use {
+    crate::{alerts::AlertRule, provider::Provider},
+    serde::Deserialize,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::BTreeMap, fs, path::Path, process::exit, str::FromStr},
+};
+
+/// Settings read from the TOML file passed with `--config`. Command-line flags add to these.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    /// Accounts excluded from conflict tracking.
+    pub ignore_accounts: Vec<String>,
+    /// Extra `Name: value` headers sent with every RPC request.
+    pub rpc_headers: Vec<String>,
+    /// Bearer token sent in the `Authorization` header of every RPC request.
+    pub rpc_bearer_token: Option<String>,
+    /// Provider serving the RPC endpoint, e.g. `helius`, enabling its APIs beyond standard
+    /// JSON-RPC.
+    pub provider: Option<Provider>,
+    /// Size limit of the block cache, in MiB.
+    pub max_cache_size: Option<u64>,
+    /// Alert rules evaluated by the watch subcommand, e.g. `violation_rate > 5% over 20 slots`.
+    pub alerts: Vec<String>,
+    /// Named clusters for the watch subcommand to follow, e.g. `[clusters.testnet]`.
+    pub clusters: BTreeMap<String, ClusterConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterConfig {
+    /// RPC URL of the cluster.
+    pub url: String,
+}
+
+impl FileConfig {
+    pub fn load(path: &Path) -> Self {
+        let contents = fs::read_to_string(path).unwrap_or_else(|err| {
+            eprintln!("Failed to read config {}: {}", path.display(), err);
+            exit(1);
+        });
+        toml::from_str(&contents).unwrap_or_else(|err| {
+            eprintln!("Failed to parse config {}: {}", path.display(), err);
+            exit(1);
+        })
+    }
+
+    pub fn ignore_accounts(&self) -> Vec<Pubkey> {
+        self.ignore_accounts
+            .iter()
+            .map(|account| {
+                Pubkey::from_str(account).unwrap_or_else(|err| {
+                    eprintln!("Failed to parse ignored account {}: {}", account, err);
+                    exit(1);
+                })
+            })
+            .collect()
+    }
+
+    pub fn alert_rules(&self) -> Vec<AlertRule> {
+        self.alerts
+            .iter()
+            .map(|rule| {
+                rule.parse().unwrap_or_else(|err| {
+                    eprintln!("Invalid alert in config: {}", err);
+                    exit(1);
+                })
+            })
+            .collect()
+    }
+}
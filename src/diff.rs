@@ -0,0 +1,190 @@
+use {
+    crate::{
+        analysis::BlockAnalysis,
+        priority::display_priority,
+        report::{read_records, BlockRecord, Reporter, ViolationRecord},
+        say,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeSet, HashMap},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Differences between two analyses of the same block, as recorded in their NDJSON records.
+#[derive(Debug, Default)]
+pub struct RecordDiff {
+    pub slot: u64,
+    /// Metrics whose values differ, with the old and new values.
+    pub changed_metrics: Vec<(&'static str, String, String)>,
+    pub added_violations: Vec<String>,
+    pub removed_violations: Vec<String>,
+    pub added_violating_transactions: Vec<String>,
+    pub removed_violating_transactions: Vec<String>,
+}
+
+impl RecordDiff {
+    /// Compares `new` against `old`. The leader is not compared, since it is not part of the
+    /// analysis and is only known in range output.
+    pub fn new(old: &BlockRecord, new: &BlockRecord) -> Self {
+        let metrics = |record: &BlockRecord| {
+            [
+                ("transactions", record.transaction_count.to_string()),
+                (
+                    "vote transactions",
+                    record.vote_transaction_count.to_string(),
+                ),
+                ("violations", record.violation_count.to_string()),
+                ("violation rate", format!("{:.4}", record.violation_rate)),
+                ("fill", format!("{:.4}", record.fill)),
+            ]
+        };
+        let changed_metrics = metrics(old)
+            .into_iter()
+            .zip(metrics(new))
+            .filter(|((_, old), (_, new))| old != new)
+            .map(|((name, old), (_, new))| (name, old, new))
+            .collect();
+
+        let violations = |record: &BlockRecord| -> BTreeSet<String> {
+            record.violations.iter().map(describe_violation).collect()
+        };
+        let (old_violations, new_violations) = (violations(old), violations(new));
+        let signatures = |record: &BlockRecord| -> BTreeSet<String> {
+            record
+                .violating_transactions
+                .iter()
+                .map(|transaction| transaction.signature.clone())
+                .collect()
+        };
+        let (old_signatures, new_signatures) = (signatures(old), signatures(new));
+
+        Self {
+            slot: new.slot,
+            changed_metrics,
+            added_violations: new_violations
+                .difference(&old_violations)
+                .cloned()
+                .collect(),
+            removed_violations: old_violations
+                .difference(&new_violations)
+                .cloned()
+                .collect(),
+            added_violating_transactions: new_signatures
+                .difference(&old_signatures)
+                .cloned()
+                .collect(),
+            removed_violating_transactions: old_signatures
+                .difference(&new_signatures)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed_metrics.is_empty()
+            && self.added_violations.is_empty()
+            && self.removed_violations.is_empty()
+            && self.added_violating_transactions.is_empty()
+            && self.removed_violating_transactions.is_empty()
+    }
+}
+
+impl std::fmt::Display for RecordDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Slot {}:", self.slot)?;
+        if self.is_empty() {
+            return write!(f, " unchanged");
+        }
+        for (name, old, new) in &self.changed_metrics {
+            write!(f, "\n  {}: {} -> {}", name, old, new)?;
+        }
+        let sections = [
+            ("+ violation", &self.added_violations),
+            ("- violation", &self.removed_violations),
+            (
+                "+ violating transaction",
+                &self.added_violating_transactions,
+            ),
+            (
+                "- violating transaction",
+                &self.removed_violating_transactions,
+            ),
+        ];
+        for (label, entries) in sections {
+            for entry in entries {
+                write!(f, "\n  {} {}", label, entry)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn describe_violation(violation: &ViolationRecord) -> String {
+    format!(
+        "{} {} {} -> {}",
+        violation.account,
+        violation.kind,
+        display_priority(violation.previous_priority),
+        display_priority(violation.priority)
+    )
+}
+
+/// Compares each analyzed block against its record in a baseline file written with `--ndjson`,
+/// printing what changed. Whether any differed is left to [`BaselineReporter::changed`], so the
+/// caller can exit with an error once every other output is written.
+pub struct BaselineReporter {
+    path: PathBuf,
+    records: HashMap<u64, BlockRecord>,
+    compared_count: usize,
+    changed_count: usize,
+}
+
+impl BaselineReporter {
+    pub fn new(path: &Path) -> Self {
+        let records = read_records(path)
+            .into_iter()
+            .map(|record| (record.slot, record))
+            .collect();
+        Self {
+            path: path.to_path_buf(),
+            records,
+            compared_count: 0,
+            changed_count: 0,
+        }
+    }
+
+    /// Returns whether any compared block differed from its baseline record.
+    pub fn changed(&self) -> bool {
+        self.changed_count > 0
+    }
+}
+
+impl Reporter for BaselineReporter {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        let Some(baseline) = self.records.get(&analysis.slot) else {
+            eprintln!(
+                "Warning: slot {} is not in baseline {}",
+                analysis.slot,
+                self.path.display()
+            );
+            return;
+        };
+        let diff = RecordDiff::new(baseline, &BlockRecord::new(analysis, leader));
+        self.compared_count += 1;
+        if !diff.is_empty() {
+            self.changed_count += 1;
+            say!("{}", diff);
+        }
+    }
+
+    fn finish(&mut self) {
+        say!(
+            "Baseline {}: {} of {} compared blocks changed",
+            self.path.display(),
+            self.changed_count,
+            self.compared_count
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use {
+    crate::analysis::{ratio, BlockAnalysis},
+    solana_sdk::transaction::TransactionError,
+    std::collections::BTreeMap,
+};
+
+/// Failure counts for a group of transactions, broken down by error category.
+#[derive(Default)]
+pub struct FailureCounts {
+    pub transaction_count: usize,
+    pub failed_count: usize,
+    pub categories: BTreeMap<String, usize>,
+}
+
+impl FailureCounts {
+    fn add(&mut self, error: Option<&TransactionError>) {
+        self.transaction_count += 1;
+        if let Some(error) = error {
+            self.failed_count += 1;
+            *self.categories.entry(error_category(error)).or_default() += 1;
+        }
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        ratio(self.failed_count as u64, self.transaction_count as u64)
+    }
+}
+
+/// Failure rates of violating vs non-violating analyzed transactions within a block.
+pub struct FailureCorrelation {
+    pub violating: FailureCounts,
+    pub non_violating: FailureCounts,
+}
+
+impl FailureCorrelation {
+    pub fn new(analysis: &BlockAnalysis) -> Self {
+        let mut violating = FailureCounts::default();
+        let mut non_violating = FailureCounts::default();
+        for transaction in analysis.analyzed_transactions() {
+            let counts = if transaction.is_violation {
+                &mut violating
+            } else {
+                &mut non_violating
+            };
+            counts.add(transaction.error.as_ref());
+        }
+
+        Self {
+            violating,
+            non_violating,
+        }
+    }
+}
+
+/// Coarse error category: the variant name, plus the inner variant for instruction errors.
+pub fn error_category(error: &TransactionError) -> String {
+    match error {
+        TransactionError::InstructionError(_, instruction_error) => {
+            format!("InstructionError::{}", variant_name(instruction_error))
+        }
+        error => variant_name(error),
+    }
+}
+
+fn variant_name(value: &impl std::fmt::Debug) -> String {
+    let debug = format!("{:?}", value);
+    debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
@@ -0,0 +1,207 @@
+use {
+    crate::analysis::{TransactionSummary, MAX_BLOCK_UNITS, MAX_WRITABLE_ACCOUNT_UNITS},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Signature,
+        transaction::SanitizedVersionedTransaction,
+    },
+    std::{cmp::Reverse, collections::HashMap},
+};
+
+/// Fee charged per transaction signature, in lamports.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// Percentage of collected fees that is burned rather than paid to the leader.
+pub const BURN_PERCENT: u64 = 50;
+
+/// Fee charged for the transaction's signatures, before any prioritization fee.
+pub fn base_fee(transaction: &SanitizedVersionedTransaction) -> u64 {
+    let num_signatures = transaction
+        .get_message()
+        .message
+        .header()
+        .num_required_signatures;
+    u64::from(num_signatures) * LAMPORTS_PER_SIGNATURE
+}
+
+/// `lamports` in SOL with thousands separators and without trailing zeros, e.g. `1,234.5 SOL`,
+/// for human-readable output.
+pub fn format_sol(lamports: u64) -> String {
+    let whole = (lamports / LAMPORTS_PER_SOL).to_string();
+    let mut formatted = String::new();
+    for (position, digit) in whole.chars().enumerate() {
+        if position > 0 && (whole.len() - position).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    let fraction = format!("{:09}", lamports % LAMPORTS_PER_SOL);
+    let fraction = fraction.trim_end_matches('0');
+    if !fraction.is_empty() {
+        formatted.push('.');
+        formatted.push_str(fraction);
+    }
+    formatted + " SOL"
+}
+
+/// Like [`format_sol`], with an explicit sign.
+pub fn format_sol_change(lamports: i128) -> String {
+    let sign = if lamports < 0 { '-' } else { '+' };
+    let magnitude = u64::try_from(lamports.unsigned_abs()).unwrap_or(u64::MAX);
+    format!("{}{}", sign, format_sol(magnitude))
+}
+
+/// Fees collected from a set of transactions and how they are split between burn and leader.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeTotals {
+    pub total_fees: u64,
+    pub priority_fees: u64,
+}
+
+impl FeeTotals {
+    pub fn new<'a>(transactions: impl IntoIterator<Item = &'a TransactionSummary>) -> Self {
+        let mut totals = Self::default();
+        for transaction in transactions {
+            totals.total_fees += transaction.fee;
+            totals.priority_fees += transaction.priority_fee;
+        }
+        totals
+    }
+
+    pub fn add(&mut self, other: FeeTotals) {
+        self.total_fees += other.total_fees;
+        self.priority_fees += other.priority_fees;
+    }
+
+    pub fn burned(&self) -> u64 {
+        self.total_fees * BURN_PERCENT / 100
+    }
+
+    pub fn leader_revenue(&self) -> u64 {
+        self.total_fees - self.burned()
+    }
+
+    /// Leader revenue attributable to prioritization fees.
+    pub fn leader_priority_revenue(&self) -> u64 {
+        self.priority_fees - self.priority_fees * BURN_PERCENT / 100
+    }
+}
+
+/// Prioritization revenue a leader would have earned by packing the same transactions strictly
+/// in priority order, admitting each only while its requested compute units fit under the block
+/// and per-account limits.
+pub struct PackingEstimate {
+    /// Leader prioritization revenue from the transactions as they landed.
+    pub actual_revenue: u64,
+    /// Leader prioritization revenue from the priority-ordered packing.
+    pub packed_revenue: u64,
+    /// Transactions that did not fit in the priority-ordered packing.
+    pub cut_count: usize,
+    /// Signatures and priorities of the transactions that did not fit, highest priority first.
+    pub cut_transactions: Vec<(Signature, u64)>,
+    /// Packed transactions whose position among the packed transactions differs from the
+    /// position they actually landed in.
+    pub moved_count: usize,
+    /// Largest such change of position.
+    pub max_move: usize,
+}
+
+impl PackingEstimate {
+    pub fn new<'a>(transactions: impl IntoIterator<Item = &'a TransactionSummary>) -> Self {
+        let mut transactions: Vec<_> = transactions.into_iter().collect();
+        transactions.sort_by_key(|transaction| transaction.index);
+        let actual_revenue = FeeTotals::new(transactions.iter().copied()).leader_priority_revenue();
+
+        // Stable sort so equal priorities keep their block order.
+        transactions.sort_by_key(|transaction| Reverse(transaction.priority));
+        let mut block_units = 0;
+        let mut account_units: HashMap<Pubkey, u64> = HashMap::new();
+        let mut packed = Vec::with_capacity(transactions.len());
+        let mut cut_transactions = Vec::new();
+        for transaction in transactions {
+            let units = u64::from(transaction.compute_unit_limit);
+            let fits = block_units + units <= MAX_BLOCK_UNITS
+                && transaction.writable_accounts.iter().all(|account| {
+                    account_units.get(account).copied().unwrap_or_default() + units
+                        <= MAX_WRITABLE_ACCOUNT_UNITS
+                });
+            if !fits {
+                cut_transactions.push((transaction.signature, transaction.priority));
+                continue;
+            }
+
+            block_units += units;
+            for account in &transaction.writable_accounts {
+                *account_units.entry(*account).or_default() += units;
+            }
+            packed.push(transaction);
+        }
+
+        // Compare each packed transaction's packed rank with its rank in block order among the
+        // same transactions.
+        let mut landed_order: Vec<_> = packed.iter().map(|transaction| transaction.index).collect();
+        landed_order.sort_unstable();
+        let moves: Vec<_> = packed
+            .iter()
+            .enumerate()
+            .map(|(packed_rank, transaction)| {
+                let landed_rank = landed_order
+                    .binary_search(&transaction.index)
+                    .unwrap_or_default();
+                packed_rank.abs_diff(landed_rank)
+            })
+            .collect();
+
+        Self {
+            actual_revenue,
+            packed_revenue: FeeTotals::new(packed).leader_priority_revenue(),
+            cut_count: cut_transactions.len(),
+            cut_transactions,
+            moved_count: moves.iter().filter(|distance| **distance > 0).count(),
+            max_move: moves.into_iter().max().unwrap_or_default(),
+        }
+    }
+
+    /// Revenue the priority-ordered packing would have gained (positive) or lost (negative)
+    /// relative to the observed block.
+    pub fn revenue_delta(&self) -> i128 {
+        i128::from(self.packed_revenue) - i128::from(self.actual_revenue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_sol_cases() {
+        let cases = [
+            (0, "0 SOL"),
+            (1, "0.000000001 SOL"),
+            (LAMPORTS_PER_SOL, "1 SOL"),
+            (1_500_000_000, "1.5 SOL"),
+            (999 * LAMPORTS_PER_SOL, "999 SOL"),
+            (1_000 * LAMPORTS_PER_SOL, "1,000 SOL"),
+            (1_234_500_000_000, "1,234.5 SOL"),
+            (1_234_567 * LAMPORTS_PER_SOL + 10, "1,234,567.00000001 SOL"),
+            (u64::MAX, "18,446,744,073.709551615 SOL"),
+        ];
+        for (lamports, expected) in cases {
+            assert_eq!(format_sol(lamports), expected, "{}", lamports);
+        }
+    }
+
+    #[test]
+    fn format_sol_change_cases() {
+        let cases = [
+            (0, "+0 SOL"),
+            (2_500_000_000, "+2.5 SOL"),
+            (-1, "-0.000000001 SOL"),
+            (-(1_000 * LAMPORTS_PER_SOL as i128), "-1,000 SOL"),
+            (i128::MIN, "-18,446,744,073.709551615 SOL"),
+        ];
+        for (lamports, expected) in cases {
+            assert_eq!(format_sol_change(lamports), expected, "{}", lamports);
+        }
+    }
+}
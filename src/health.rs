@@ -0,0 +1,142 @@
+use {
+    solana_sdk::clock::Slot,
+    std::{
+        io::{BufRead, BufReader, Write},
+        net::{TcpListener, TcpStream},
+        process::exit,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// Progress of a long-running watcher, served over HTTP for orchestrators:
+///
+/// - `/healthz` fails once the watcher has made no progress, neither polling the tip nor
+///   finishing a block, for `stale_after`, meaning it is stuck and should be restarted.
+/// - `/readyz` fails while the last tip poll failed or processing lags the tip by more than
+///   `max_lag` slots.
+pub struct Health {
+    stale_after: Duration,
+    max_lag: u64,
+    last_progress: Mutex<Instant>,
+    rpc_connected: AtomicBool,
+    tip_slot: AtomicU64,
+    processed_slot: AtomicU64,
+}
+
+impl Health {
+    pub fn new(stale_after: Duration, max_lag: u64) -> Arc<Self> {
+        Arc::new(Self {
+            stale_after,
+            max_lag,
+            last_progress: Mutex::new(Instant::now()),
+            rpc_connected: AtomicBool::new(false),
+            tip_slot: AtomicU64::new(0),
+            processed_slot: AtomicU64::new(0),
+        })
+    }
+
+    /// Records a poll of the tip, `None` if it failed.
+    pub fn record_poll(&self, tip: Option<Slot>) {
+        self.rpc_connected.store(tip.is_some(), Ordering::Relaxed);
+        if let Some(tip) = tip {
+            self.tip_slot.fetch_max(tip, Ordering::Relaxed);
+        }
+        self.record_progress();
+    }
+
+    /// Records that every slot up to and including `slot` has been processed.
+    pub fn record_processed(&self, slot: Slot) {
+        self.processed_slot.fetch_max(slot, Ordering::Relaxed);
+        self.record_progress();
+    }
+
+    fn record_progress(&self) {
+        *self.last_progress.lock().unwrap() = Instant::now();
+    }
+
+    fn liveness(&self) -> Result<String, String> {
+        let idle = self.last_progress.lock().unwrap().elapsed();
+        if idle > self.stale_after {
+            return Err(format!("no progress for {}s", idle.as_secs()));
+        }
+        Ok(format!("last progress {}s ago", idle.as_secs()))
+    }
+
+    fn readiness(&self) -> Result<String, String> {
+        if !self.rpc_connected.load(Ordering::Relaxed) {
+            return Err("RPC unreachable".to_string());
+        }
+        let tip = self.tip_slot.load(Ordering::Relaxed);
+        let lag = tip.saturating_sub(self.processed_slot.load(Ordering::Relaxed));
+        if lag > self.max_lag {
+            return Err(format!("{} slots behind tip {}", lag, tip));
+        }
+        Ok(format!("{} slots behind tip {}", lag, tip))
+    }
+}
+
+/// Serves `/healthz` and `/readyz` on `address` from a background thread, answering for every
+/// named watcher in `checks` and failing if any of them fails.
+pub fn serve_health(address: &str, checks: Vec<(String, Arc<Health>)>) {
+    let listener = TcpListener::bind(address).unwrap_or_else(|err| {
+        eprintln!("Failed to listen on {}: {}", address, err);
+        exit(1);
+    });
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(err) = respond(stream, &checks) {
+                eprintln!("Warning: failed to answer health check: {}", err);
+            }
+        }
+    });
+}
+
+fn respond(mut stream: TcpStream, checks: &[(String, Arc<Health>)]) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let check: fn(&Health) -> Result<String, String> =
+        match request_line.split_whitespace().nth(1).unwrap_or_default() {
+            "/healthz" => Health::liveness,
+            "/readyz" => Health::readiness,
+            _ => {
+                return write_response(&mut stream, "404 Not Found", "not found\n");
+            }
+        };
+
+    let mut healthy = true;
+    let mut body = String::new();
+    for (name, health) in checks {
+        let line = check(health).unwrap_or_else(|err| {
+            healthy = false;
+            err
+        });
+        if name.is_empty() {
+            body.push_str(&format!("{}\n", line));
+        } else {
+            body.push_str(&format!("{}: {}\n", name, line));
+        }
+    }
+    let status = if healthy {
+        "200 OK"
+    } else {
+        "503 Service Unavailable"
+    };
+    write_response(&mut stream, status, &body)
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\
+         \r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
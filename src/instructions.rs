@@ -0,0 +1,82 @@
+use {
+    solana_sdk::{
+        bs58,
+        instruction::CompiledInstruction,
+        message::{v0::LoadedAddresses, AccountKeys},
+        pubkey::Pubkey,
+    },
+    solana_transaction_status::{
+        parse_instruction::parse, UiInnerInstructions, UiInstruction, UiParsedInstruction,
+    },
+};
+
+/// An instruction invoked through CPI by one of a transaction's top-level instructions.
+pub struct InnerInstruction {
+    /// Index of the top-level instruction that invoked it.
+    pub index: u8,
+    pub stack_height: Option<u32>,
+    pub program_id: Pubkey,
+    /// Program name and parsed instruction, for programs the status crate knows how to parse.
+    pub parsed: Option<(String, String)>,
+    /// Base-58 instruction data, for programs it does not.
+    pub data: String,
+}
+
+impl std::fmt::Display for InnerInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.index)?;
+        if let Some(stack_height) = self.stack_height {
+            write!(f, " (depth {})", stack_height)?;
+        }
+        match &self.parsed {
+            Some((program, parsed)) => write!(f, " {} {}", program, parsed),
+            None => write!(f, " {} data {}", self.program_id, self.data),
+        }
+    }
+}
+
+/// Resolves the program ids of the compiled inner instructions in a transaction's meta against
+/// its account keys, and parses those belonging to well-known programs.
+pub fn resolve_inner_instructions(
+    inner_instructions: Vec<UiInnerInstructions>,
+    static_keys: &[Pubkey],
+    loaded_addresses: &LoadedAddresses,
+) -> Vec<InnerInstruction> {
+    let account_keys = AccountKeys::new(static_keys, Some(loaded_addresses));
+    inner_instructions
+        .into_iter()
+        .flat_map(|inner| {
+            let index = inner.index;
+            inner
+                .instructions
+                .into_iter()
+                .map(move |instruction| (index, instruction))
+        })
+        .filter_map(|(index, instruction)| match instruction {
+            UiInstruction::Compiled(compiled) => {
+                let program_id = *account_keys.get(compiled.program_id_index as usize)?;
+                let parsed = bs58::decode(&compiled.data)
+                    .into_vec()
+                    .ok()
+                    .and_then(|data| {
+                        let instruction = CompiledInstruction {
+                            program_id_index: compiled.program_id_index,
+                            accounts: compiled.accounts.clone(),
+                            data,
+                        };
+                        parse(&program_id, &instruction, &account_keys, None).ok()
+                    });
+                Some(InnerInstruction {
+                    index,
+                    stack_height: compiled.stack_height,
+                    program_id,
+                    parsed: parsed.map(|parsed| (parsed.program, parsed.parsed.to_string())),
+                    data: compiled.data,
+                })
+            }
+            // Only returned for jsonParsed encoding, which the block fetch does not request.
+            UiInstruction::Parsed(UiParsedInstruction::Parsed(_))
+            | UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(_)) => None,
+        })
+        .collect()
+}
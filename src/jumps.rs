@@ -0,0 +1,133 @@
+use {
+    crate::analysis::{percentile, BlockAnalysis},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::collections::{HashMap, HashSet},
+};
+
+/// How many higher-priority conflicting transactions landed after each analyzed transaction,
+/// its jump count, as `(block position, jump count)` in block order.
+///
+/// A violation only says that a transaction jumped at least one higher-priority one; the jump
+/// count says how far up the queue it got. Transactions conflicting on several accounts are
+/// counted once.
+pub fn jump_counts(analysis: &BlockAnalysis) -> Vec<(usize, usize)> {
+    struct Access {
+        index: usize,
+        is_write: bool,
+        priority: u64,
+    }
+
+    let mut timelines: HashMap<Pubkey, Vec<Access>> = HashMap::new();
+    // Each transaction's tracked accounts with its position in their timelines.
+    let mut positions = Vec::new();
+    for transaction in analysis.analyzed_transactions() {
+        let accounts = transaction
+            .writable_accounts
+            .iter()
+            .map(|account| (account, true))
+            .chain(
+                transaction
+                    .readonly_accounts
+                    .iter()
+                    .map(|account| (account, false)),
+            )
+            .filter(|(account, _)| analysis.write_lock_counts.contains_key(account));
+        let mut transaction_positions = Vec::new();
+        for (account, is_write) in accounts {
+            let timeline = timelines.entry(*account).or_default();
+            transaction_positions.push((*account, timeline.len()));
+            timeline.push(Access {
+                index: transaction.index,
+                is_write,
+                priority: transaction.priority,
+            });
+        }
+        positions.push((
+            transaction.index,
+            transaction.priority,
+            transaction_positions,
+        ));
+    }
+
+    let mut jumped = HashSet::new();
+    positions
+        .into_iter()
+        .map(|(index, priority, transaction_positions)| {
+            jumped.clear();
+            for (account, position) in transaction_positions {
+                let timeline = &timelines[&account];
+                let is_write = timeline[position].is_write;
+                jumped.extend(
+                    timeline[position + 1..]
+                        .iter()
+                        .filter(|later| (is_write || later.is_write) && later.priority > priority)
+                        .map(|later| later.index),
+                );
+            }
+            (index, jumped.len())
+        })
+        .collect()
+}
+
+/// Distribution of a block's jump counts, over every analyzed transaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct QueueJumps {
+    pub transaction_count: usize,
+    /// Transactions with at least one higher-priority conflicting transaction landing after
+    /// them.
+    pub jumping_transaction_count: usize,
+    /// Jump counts summed over transactions.
+    pub jump_count: usize,
+    pub median: usize,
+    pub p90: usize,
+    pub p99: usize,
+    pub max: usize,
+    /// Block position of the transaction with the most jumps.
+    pub max_index: Option<usize>,
+}
+
+impl QueueJumps {
+    pub fn new(analysis: &BlockAnalysis) -> Self {
+        let jump_counts = jump_counts(analysis);
+        let mut sorted: Vec<_> = jump_counts.iter().map(|(_, jumps)| *jumps as u64).collect();
+        sorted.sort_unstable();
+        let max = jump_counts
+            .iter()
+            .filter(|(_, jumps)| *jumps > 0)
+            .max_by_key(|(index, jumps)| (*jumps, std::cmp::Reverse(*index)));
+        Self {
+            transaction_count: jump_counts.len(),
+            jumping_transaction_count: jump_counts.iter().filter(|(_, jumps)| *jumps > 0).count(),
+            jump_count: jump_counts.iter().map(|(_, jumps)| jumps).sum(),
+            median: percentile(&sorted, 0.5) as usize,
+            p90: percentile(&sorted, 0.9) as usize,
+            p99: percentile(&sorted, 0.99) as usize,
+            max: max.map_or(0, |(_, jumps)| *jumps),
+            max_index: max.map(|(index, _)| *index),
+        }
+    }
+}
+
+impl std::fmt::Display for QueueJumps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} transactions landed ahead of higher-priority conflicting ones, {} jumps in \
+             all; per transaction median {}, p90 {}, p99 {}, max {}",
+            self.jumping_transaction_count,
+            self.transaction_count,
+            self.jump_count,
+            self.median,
+            self.p90,
+            self.p99,
+            self.max
+        )?;
+        if let Some(index) = self.max_index {
+            write!(f, " (#{})", index)?;
+        }
+        Ok(())
+    }
+}
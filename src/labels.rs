@@ -0,0 +1,148 @@
+use {
+    serde::{Deserialize, Serialize},
+    solana_client::{rpc_client::RpcClient, rpc_request::MAX_MULTIPLE_ACCOUNTS},
+    solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey},
+    std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+};
+
+const TOKEN_PROGRAMS: [&str; 2] = [
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+    "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",
+];
+const ASSOCIATED_TOKEN_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+/// Symbols for widely held mints. Other mints are labeled by address.
+const KNOWN_MINTS: [(&str, &str); 7] = [
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC"),
+    ("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BVwNYB", "USDT"),
+    ("So11111111111111111111111111111111111111112", "wSOL"),
+    ("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So", "mSOL"),
+    ("J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn", "JitoSOL"),
+    ("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "BONK"),
+    ("JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN", "JUP"),
+];
+
+/// Mint and owner of a token account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TokenAccount {
+    mint: String,
+    owner: String,
+    /// Whether the account is the owner's associated token account for the mint.
+    is_associated: bool,
+}
+
+/// Resolves token accounts to human-readable labels such as "USDC ATA of <owner>", remembering
+/// results, including accounts that are not token accounts, in `token-accounts.json` in the
+/// cache directory.
+///
+/// Token account owners can be reassigned, so a cached label may in rare cases be stale.
+pub struct TokenLabels {
+    path: Option<PathBuf>,
+    accounts: HashMap<String, Option<TokenAccount>>,
+}
+
+impl TokenLabels {
+    pub fn new(cache_dir: Option<&Path>) -> Self {
+        let path = cache_dir.map(|dir| dir.join("token-accounts.json"));
+        let accounts = path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default();
+        Self { path, accounts }
+    }
+
+    /// Labels for those of `accounts` that are token accounts, fetching any not yet known.
+    pub fn resolve(&mut self, client: &RpcClient, accounts: &[Pubkey]) -> HashMap<Pubkey, String> {
+        let missing: Vec<_> = accounts
+            .iter()
+            .filter(|account| !self.accounts.contains_key(&account.to_string()))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            self.fetch(client, &missing);
+            self.save();
+        }
+
+        accounts
+            .iter()
+            .filter_map(|account| {
+                let token_account = self.accounts.get(&account.to_string())?.as_ref()?;
+                let symbol = KNOWN_MINTS
+                    .iter()
+                    .find(|(mint, _)| *mint == token_account.mint)
+                    .map_or(token_account.mint.as_str(), |(_, symbol)| symbol);
+                let kind = if token_account.is_associated {
+                    "ATA"
+                } else {
+                    "token account"
+                };
+                Some((
+                    *account,
+                    format!("{} {} of {}", symbol, kind, token_account.owner),
+                ))
+            })
+            .collect()
+    }
+
+    fn fetch(&mut self, client: &RpcClient, accounts: &[Pubkey]) {
+        let token_programs: Vec<_> = TOKEN_PROGRAMS.iter().map(|id| parse(id)).collect();
+        for chunk in accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let fetched = match client
+                .get_multiple_accounts_with_commitment(chunk, CommitmentConfig::confirmed())
+            {
+                Ok(response) => response.value,
+                Err(err) => {
+                    eprintln!("Warning: failed to fetch token accounts: {}", err);
+                    continue;
+                }
+            };
+            for (address, account) in chunk.iter().zip(fetched) {
+                let token_account = account.filter(|account| {
+                    token_programs.contains(&account.owner) && account.data.len() >= 64
+                });
+                let token_account = token_account.map(|account| {
+                    let mint = Pubkey::try_from(&account.data[..32]).unwrap_or_default();
+                    let owner = Pubkey::try_from(&account.data[32..64]).unwrap_or_default();
+                    let (associated, _) = Pubkey::find_program_address(
+                        &[owner.as_ref(), account.owner.as_ref(), mint.as_ref()],
+                        &parse(ASSOCIATED_TOKEN_PROGRAM),
+                    );
+                    TokenAccount {
+                        mint: mint.to_string(),
+                        owner: owner.to_string(),
+                        is_associated: associated == *address,
+                    }
+                });
+                self.accounts.insert(address.to_string(), token_account);
+            }
+        }
+    }
+
+    /// Failing to save only costs a refetch later, so errors are warnings.
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(path, serde_json::to_vec(&self.accounts)?));
+        if let Err(err) = result {
+            eprintln!(
+                "Warning: failed to save token account labels to {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+}
+
+fn parse(pubkey: &str) -> Pubkey {
+    Pubkey::from_str(pubkey).expect("valid program id")
+}
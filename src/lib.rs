@@ -0,0 +1,38 @@
+//! Checks whether the transactions in a block landed in priority order, flagging conflicting
+//! transactions that were included ahead of higher-priority ones.
+
+pub mod alerts;
+pub mod analysis;
+pub mod anonymize;
+pub mod arrow;
+pub mod auction;
+pub mod budget;
+pub mod bundle;
+pub mod cache;
+pub mod config;
+pub mod diff;
+pub mod failures;
+pub mod fees;
+pub mod health;
+pub mod instructions;
+pub mod jumps;
+pub mod labels;
+pub mod ordering;
+pub mod output;
+pub mod presets;
+pub mod priority;
+pub mod profile;
+pub mod provider;
+pub mod query;
+pub mod reorder;
+pub mod report;
+pub mod rpc;
+pub mod rule;
+pub mod sandwich;
+pub mod shutdown;
+pub mod starvation;
+pub mod stats;
+pub mod steal;
+pub mod svg;
+pub mod threads;
+pub mod tiebreak;
@@ -1,233 +1,2844 @@
 use {
-    clap::Parser,
-    solana_client::{rpc_client::RpcClient, rpc_config::RpcBlockConfig},
+    clap::{Args, Parser, Subcommand},
+    indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle},
+    priority_checker::{
+        alerts::{AlertEvaluator, AlertEvent, AlertRule},
+        analysis::{
+            percentile, ratio, AccountSort, AnalysisConfig, Analyzer, BlockAnalysis, BlockDecoder,
+            FetchMetadata, TiePolicy, Violation, ViolationKind,
+        },
+        anonymize::Anonymizer,
+        auction::AuctionReport,
+        bundle::EvidenceBundle,
+        cache::{BlockCache, CacheOptions},
+        config::{ClusterConfig, FileConfig},
+        diff::{BaselineReporter, RecordDiff},
+        failures::FailureCorrelation,
+        fees::{format_sol, format_sol_change},
+        health::{serve_health, Health},
+        jumps::QueueJumps,
+        labels::TokenLabels,
+        ordering::account_rank_correlations,
+        output::{quiet, set_quiet},
+        presets::Preset,
+        priority::{
+            display_priority, set_display_unit, BuiltinPriorityModel, PriorityUnit, Unprioritized,
+        },
+        profile::{elapsed, Profiler},
+        provider::{EstimateEvaluation, Provider, ProviderAdapter},
+        query::{account_violations, daily_trend, worst_leaders},
+        reorder::{ReorderBuffer, ReorderWindow},
+        report::{
+            parse_output_sink, read_records, verify_identical, BlockRecord, FanOut, NdjsonReporter,
+            OutputSink, PrometheusReporter, Reporter,
+        },
+        rpc::{
+            bearer_header, describe_batch_stats, describe_transport_stats, fetch_block,
+            fetch_block_slots, fetch_epoch_schedule, fetch_finalized_block, fetch_finalized_slot,
+            fetch_leader, fetch_node_versions, fetch_recent_prioritization_fees,
+            fetch_slot_leaders, fetch_tip_slot, parse_header, try_fetch_block,
+            try_fetch_block_slots, verify_block_consistency, BatchClient, RpcConnector, RpcOptions,
+            MAINNET_URL,
+        },
+        rule::{ViolationFilter, ViolationRule},
+        sandwich::find_sandwiches,
+        say, shutdown,
+        starvation::find_read_starvation,
+        stats::{HotAccountPersistence, RangeStats, RollingWindow, SlotCsvWriter, SlotSummary},
+        steal::{FailedAttempt, InFlight},
+        svg::write_timeline_svg,
+        threads::{ThreadInference, DEFAULT_BANKING_THREADS},
+    },
+    reqwest::header::{HeaderMap, HeaderName, HeaderValue},
+    solana_client::rpc_client::RpcClient,
     solana_sdk::{
-        borsh0_10::try_from_slice_unchecked,
-        clock::Slot,
-        commitment_config::{CommitmentConfig, CommitmentLevel},
-        compute_budget::{self, ComputeBudgetInstruction},
+        clock::{Epoch, Slot},
         pubkey::Pubkey,
         signature::Signature,
-        transaction::SanitizedVersionedTransaction,
     },
-    solana_transaction_status::{TransactionDetails, UiLoadedAddresses, UiTransactionEncoding},
+    solana_transaction_status::UiConfirmedBlock,
     std::{
-        collections::{hash_map::Entry, HashMap, HashSet},
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+        env, fs,
+        path::{Path, PathBuf},
         process::exit,
         str::FromStr,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            mpsc, Arc, Mutex,
+        },
+        thread,
+        time::{Duration, Instant},
     },
 };
 
 #[derive(Debug, Parser)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(flatten)]
+    check: CheckArgs,
+    /// Include vote transactions in conflict tracking and reported statistics.
+    #[clap(long, global = true, default_value_t = false)]
+    include_votes: bool,
+    /// Only analyze transactions invoking this program, directly or through CPI. May be repeated.
+    #[clap(long = "program", value_name = "PUBKEY", global = true)]
+    programs: Vec<Pubkey>,
+    /// Only track conflicts on this account. May be repeated.
+    #[clap(long = "account", value_name = "PUBKEY", global = true)]
+    accounts: Vec<Pubkey>,
+    /// Only track conflicts on the accounts of a built-in watchlist, in addition to any given
+    /// with --account. May be repeated.
+    #[clap(long = "preset", value_enum, value_name = "PRESET", global = true)]
+    presets: Vec<Preset>,
+    /// Only report violations where this fee payer paid for either conflicting transaction.
+    #[clap(long, value_name = "PUBKEY", global = true)]
+    fee_payer: Option<Pubkey>,
+    /// Only report violations involving a transaction listed in this file, one signature per
+    /// line. The whole block is still analyzed for context.
+    #[clap(long, value_name = "FILE", global = true)]
+    signatures: Option<PathBuf>,
+    /// Exclude this account from conflict tracking, e.g. a popular oracle. May be repeated, and
+    /// adds to `ignore_accounts` in the config file.
+    #[clap(long = "ignore-account", value_name = "PUBKEY", global = true)]
+    ignore_accounts: Vec<Pubkey>,
+    /// TOML config file with defaults for the analysis options.
+    #[clap(long, value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
+    /// rhai script deciding whether a conflicting access is a violation, in place of the
+    /// built-in priority comparison.
+    #[clap(long, value_name = "FILE", global = true)]
+    violation_rule: Option<PathBuf>,
+    /// rhai expression selecting the violations reported, stored and counted, e.g.
+    /// `gap > 1e6 && program == "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"`. It sees `slot`,
+    /// `account`, `kind`, `prev_priority`, `priority`, `gap`, `prev_index`, `index`,
+    /// `prev_fee_payer`, `fee_payer`, `program` (the later transaction's main program) and
+    /// `programs`.
+    #[clap(long, value_name = "EXPR", global = true)]
+    filter: Option<String>,
+    /// How transaction priority is computed.
+    #[clap(long, value_enum, global = true, default_value_t = BuiltinPriorityModel::CuPrice)]
+    priority_model: BuiltinPriorityModel,
+    /// Units priorities are printed in, for the per-compute-unit priority models. Structured
+    /// outputs always hold micro-lamports per compute unit.
+    #[clap(long, value_enum, global = true, default_value_t = PriorityUnit::MicroLamports)]
+    units: PriorityUnit,
+    /// How non-vote transactions that set no compute unit price are treated: ranked at priority
+    /// 0, excluded from conflict checks and statistics, or ranked at their base fee per
+    /// requested compute unit. Without it they are ranked by the priority model like any other.
+    #[clap(long, value_enum, global = true)]
+    unprioritized: Option<Unprioritized>,
+    /// Whether a conflicting transaction landing after one of equal priority is a violation:
+    /// never, or always, for studies holding the scheduler to a strict order.
+    #[clap(long, value_enum, global = true, default_value_t = TiePolicy::Never)]
+    ties: TiePolicy,
+    /// Timeout for each RPC request, in seconds.
+    #[clap(long, value_name = "SECONDS", global = true, default_value_t = 30)]
+    rpc_timeout: u64,
+    /// Initial timeout when the RPC client confirms transactions, in seconds.
+    #[clap(long, value_name = "SECONDS", global = true)]
+    rpc_confirm_timeout: Option<u64>,
+    /// Extra `Name: value` header to send with every RPC request, e.g. an API key. May be
+    /// repeated, and adds to `rpc_headers` in the config file.
+    #[clap(long = "rpc-header", value_name = "HEADER", value_parser = parse_header, global = true)]
+    rpc_headers: Vec<(HeaderName, HeaderValue)>,
+    /// Bearer token to authenticate RPC requests with. Overrides `rpc_bearer_token` in the
+    /// config file.
+    #[clap(long, value_name = "TOKEN", global = true)]
+    rpc_bearer_token: Option<String>,
+    /// Proxy URL for RPC requests. HTTPS_PROXY and related environment variables are honored
+    /// without it.
+    #[clap(long, value_name = "URL", global = true)]
+    proxy: Option<String>,
+    /// Provider serving the RPC endpoint, enabling its APIs beyond standard JSON-RPC, such as
+    /// priority fee estimates. Overrides `provider` in the config file.
+    #[clap(long, value_enum, global = true)]
+    provider: Option<Provider>,
+    /// Analyze every block twice and exit with an error if the structured results differ. Range
+    /// scans compare the parallel pipeline against a serial run over the same fetched block.
+    #[clap(long, global = true, default_value_t = false)]
+    verify_determinism: bool,
+    /// Print the wall time spent fetching, decoding, computing priorities, checking conflicts,
+    /// and reporting for each slot, and totals on exit, to tell RPC from CPU bottlenecks.
+    #[clap(long, global = true, default_value_t = false)]
+    profile: bool,
+    /// Replace account keys and transaction signatures in all output with stable hashes salted
+    /// with the secret in PRIORITY_CHECKER_SALT, so reports can be shared without exposing the
+    /// accounts involved. Builtin programs and sysvars are kept.
+    #[clap(long, global = true, default_value_t = false)]
+    anonymize: bool,
+    /// Fetch every block from RPC without reading or writing the block cache.
+    #[clap(
+        long,
+        global = true,
+        default_value_t = false,
+        conflicts_with = "refresh"
+    )]
+    no_cache: bool,
+    /// Refetch every block from RPC, replacing cached copies.
+    #[clap(long, global = true, default_value_t = false)]
+    refresh: bool,
+    /// Size limit of the block cache, in MiB, past which the least recently used blocks are
+    /// evicted. Overrides `max_cache_size` in the config file.
+    #[clap(long, value_name = "MIB", global = true)]
+    max_cache_size: Option<u64>,
+    /// Print nothing but warnings and errors, leaving the exit code and any structured outputs
+    /// such as --output, --ndjson or --csv as the only results, e.g. for cron jobs.
+    #[clap(short = 'q', long, global = true, default_value_t = false)]
+    quiet: bool,
+    /// Print an access timeline for each violated account and the inner instructions of each
+    /// violating transaction, and on exit the RPC requests made to each endpoint: counts, mean
+    /// latency, time rate limited and, for block fetches, bytes transferred.
+    #[clap(short = 'v', long, global = true, default_value_t = false)]
+    verbose: bool,
+}
+
+#[derive(Debug, Args)]
+struct CheckArgs {
     /// Slot to fetch block and perform priority checks for.
-    slot: Slot,
+    #[clap(required = true)]
+    slot: Option<Slot>,
     /// Display number of violations only.
     #[clap(short = 'c', long, default_value_t = false)]
     display_count_only: bool,
+    /// Second RPC URL to fetch the same block from and compare against before analysis.
+    #[clap(long)]
+    verify_with: Option<String>,
+    /// Fetch the block again at finalized commitment and exit with an error if its transactions
+    /// differ from the confirmed block analyzed, which can happen if the slot was still
+    /// unsettled when fetched or cached.
+    #[clap(long, default_value_t = false)]
+    check_finality: bool,
+    /// Compare landed priorities on the block's hot accounts against the provider's
+    /// getRecentPrioritizationFees. Only useful for slots near the tip.
+    #[clap(long, default_value_t = false, conflicts_with = "anonymize")]
+    compare_recent_fees: bool,
+    /// Score the --provider's priority fee estimates for the block's hot accounts against the
+    /// clearing prices observed on them, as an evaluation of the provider as a fee oracle.
+    /// Estimates reflect the provider's current window, so only useful for slots near the tip.
+    #[clap(long, default_value_t = false, conflicts_with = "anonymize")]
+    evaluate_fee_estimates: bool,
+    /// Write a Gantt-style SVG timeline of the block's hot accounts to this path.
+    #[clap(long, value_name = "FILE")]
+    svg: Option<PathBuf>,
+    /// Package the block as fetched, its analysis, the tool version and this invocation into a
+    /// zstd-compressed tar archive at this path, e.g. evidence.tar.zst, for attaching to reports
+    /// of validator misbehavior.
+    #[clap(long, value_name = "FILE", conflicts_with = "anonymize")]
+    bundle: Option<PathBuf>,
+    /// Write the block's analysis as FORMAT (json, ndjson, csv, prometheus or arrow) to PATH, or
+    /// to stdout if PATH is `-`. arrow writes transactions.arrow and violations.arrow into the
+    /// directory PATH. May be repeated to write several at once.
+    #[clap(long = "output", value_name = "FORMAT=PATH", value_parser = parse_output_sink)]
+    outputs: Vec<OutputSink>,
+    /// Compare the analysis against the slot's record in this file, written earlier with
+    /// --ndjson, and print what changed. Exits with an error if it differs.
+    #[clap(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+    #[clap(flatten)]
+    report: ReportArgs,
+}
+
+/// What the full report printed for a block includes.
+#[derive(Debug, Args)]
+struct ReportArgs {
+    /// Print the minimum, median, and maximum landed priority on this many of the block's most
+    /// write-locked accounts.
+    #[clap(long, value_name = "ACCOUNTS")]
+    fee_table: Option<usize>,
+    /// Number of banking threads to partition the block into when attributing violations to
+    /// threads racing each other or to a single thread running out of priority order.
+    #[clap(long, value_name = "THREADS", default_value_t = DEFAULT_BANKING_THREADS)]
+    banking_threads: usize,
+    /// For each violation, print both transactions' signatures, positions, priorities, compute
+    /// unit limits, fee payers and programs, and every account they conflict on.
+    #[clap(long, default_value_t = false)]
+    explain: bool,
+    /// Print an auction report: how full the block got, the distribution of landed priorities,
+    /// and the clearing price, the lowest priority that still won a write lock, on each of the
+    /// block's most contended accounts.
+    #[clap(long, default_value_t = false)]
+    auction_report: bool,
+    /// List this many of the block's highest-priority transactions with their positions.
+    #[clap(long, value_name = "K")]
+    show_top_txs: Option<usize>,
+    /// Label violated token accounts with their mint and owner, e.g. "USDC ATA of <owner>",
+    /// fetched over RPC and cached.
+    #[clap(long, default_value_t = false, conflicts_with = "anonymize")]
+    resolve_token_accounts: bool,
+    /// Flag likely sandwiches (same-payer buy and sell around another trader) on known AMM
+    /// pool accounts near violations.
+    #[clap(long, default_value_t = false, conflicts_with = "anonymize")]
+    flag_sandwiches: bool,
+    /// List accounts whose reads landed behind runs of lower-priority writes, counting every
+    /// write in the run rather than only the one immediately before the read.
+    #[clap(long, default_value_t = false)]
+    read_starvation: bool,
+    /// Print the rank correlation between priority and block position of the writes to each
+    /// account write-locked at least MIN_WRITES times, flagging accounts whose order looks
+    /// unrelated to priority.
+    #[clap(long, value_name = "MIN_WRITES")]
+    rank_correlation: Option<usize>,
+    /// Order of the violated accounts listed.
+    #[clap(long, value_enum, default_value_t = AccountSort::Severity)]
+    sort: AccountSort,
+    /// List at most this many entries of each violation and account listing.
+    #[clap(long, value_name = "N")]
+    limit: Option<usize>,
+    /// Skip this many entries of each violation and account listing, to page through them
+    /// with --limit.
+    #[clap(long, value_name = "N", default_value_t = 0)]
+    offset: usize,
+}
+
+/// The entries of each long listing printed for a block that are shown, set with --offset and
+/// --limit.
+#[derive(Clone, Copy)]
+struct Page {
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl Page {
+    /// The entries of `items` on this page, and how many follow it.
+    fn select<T>(self, items: impl IntoIterator<Item = T>) -> (Vec<T>, usize) {
+        let mut items = items.into_iter().skip(self.offset);
+        let selected: Vec<_> = items
+            .by_ref()
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        (selected, items.count())
+    }
+}
+
+/// Notes entries left out of a listing by --limit.
+fn print_remaining(remaining: usize, noun: &str) {
+    if remaining > 0 {
+        say!("... and {} more {}", remaining, noun);
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Contrast the analyses of two blocks side by side.
+    Compare {
+        /// First slot to compare.
+        slot_a: Slot,
+        /// Second slot to compare.
+        slot_b: Slot,
+    },
+    /// Analyze every block in a slot range, printing rolling statistics as it goes.
+    Range {
+        #[clap(flatten)]
+        range: RangeArgs,
+        /// Number of most recent blocks included in the rolling statistics.
+        #[clap(long, default_value_t = 100)]
+        window: usize,
+        /// Number of each block's most write-locked accounts counted as hot in it, when tracking
+        /// which accounts stay hot across the range.
+        #[clap(long, value_name = "ACCOUNTS", default_value_t = 10)]
+        hot_accounts: usize,
+        /// Report accounts hot in at least this many consecutive blocks at the end of the scan,
+        /// with their clearing prices over the blocks they were hot in.
+        #[clap(long, value_name = "BLOCKS", default_value_t = 4)]
+        min_streak: usize,
+        /// Also print each block's full report, as for a single slot, shaped by the listing
+        /// options such as --explain, --sort and --limit.
+        #[clap(long, default_value_t = false)]
+        details: bool,
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+    /// Print aggregate statistics over every block in a slot range.
+    Stats {
+        #[clap(flatten)]
+        range: RangeArgs,
+        /// Also save the aggregate statistics to this file, to combine with others later using
+        /// merge-stats.
+        #[clap(long, value_name = "FILE")]
+        save: Option<PathBuf>,
+    },
+    /// Print aggregate statistics combined from files saved with `stats --save`, each covering
+    /// different blocks, e.g. parts of a range scanned on separate machines.
+    MergeStats {
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Fill a records file, in the format written with --ndjson, with every block in a slot
+    /// range, skipping slots it already has, so an interrupted backfill resumes where it stopped.
+    Backfill {
+        #[clap(flatten)]
+        backfill: BackfillArgs,
+    },
+    /// Print the JSON Schema of the per-block records written with --ndjson.
+    Schema,
+    /// Compare two files of block records written with --ndjson, e.g. by different versions or
+    /// priority models, reporting added and removed violations and changed metrics. Exits with
+    /// an error if they differ.
+    Diff {
+        /// Records to compare against.
+        old: PathBuf,
+        /// Records to compare.
+        new: PathBuf,
+    },
+    /// Follow the chain, analyzing each new confirmed block and alerting on violations.
+    Watch {
+        #[clap(flatten)]
+        watch: WatchArgs,
+    },
+    /// Answer common questions from a records file, written with --ndjson or backfill, without
+    /// writing any code.
+    Query {
+        /// Records file to query.
+        #[clap(long, value_name = "FILE")]
+        db: PathBuf,
+        #[clap(subcommand)]
+        query: Query,
+    },
+}
+
+/// Canned queries over a records file.
+#[derive(Debug, Subcommand)]
+enum Query {
+    /// Leaders with the highest violation rates over the recorded blocks of an epoch.
+    WorstLeaders {
+        /// Epoch to rank leaders in. Defaults to the epoch of the most recent record.
+        #[clap(long)]
+        epoch: Option<Epoch>,
+        /// Leave out leaders with fewer recorded blocks in the epoch.
+        #[clap(long, value_name = "BLOCKS", default_value_t = 4)]
+        min_blocks: usize,
+        /// Number of leaders listed.
+        #[clap(long, value_name = "N", default_value_t = 10)]
+        limit: usize,
+    },
+    /// Every recorded violation on an account.
+    Account { account: Pubkey },
+    /// Violation rate per UTC day.
+    Trend,
+}
+
+#[derive(Debug, Args)]
+struct RangeArgs {
+    /// First slot of the range (inclusive).
+    start: Slot,
+    /// Last slot of the range (inclusive).
+    end: Slot,
+    /// Also write a per-slot summary CSV to this path.
+    #[clap(long)]
+    csv: Option<PathBuf>,
+    /// Also write one JSON object per block, including violation details, to this path.
+    #[clap(long)]
+    ndjson: Option<PathBuf>,
+    /// Also keep a Prometheus text-format metrics file at this path up to date.
+    #[clap(long)]
+    prometheus: Option<PathBuf>,
+    /// Also write FORMAT (json, ndjson, csv, prometheus or arrow) to PATH, or to stdout if PATH
+    /// is `-`. arrow writes transactions.arrow and violations.arrow into the directory PATH. May
+    /// be repeated to write several at once. Unlike with --ndjson, an existing file
+    /// is replaced rather than resumed.
+    #[clap(long = "output", value_name = "FORMAT=PATH", value_parser = parse_output_sink)]
+    outputs: Vec<OutputSink>,
+    /// Compare each block against its record in this file, written earlier with --ndjson, and
+    /// print what changed. Exits with an error if any block differs.
+    #[clap(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+    /// Analyze slots already recorded in the --ndjson file again, rewriting it, instead of
+    /// skipping them and appending the rest.
+    #[clap(long, default_value_t = false)]
+    force: bool,
+    /// Number of blocks fetched in parallel, each worker with its own connection.
+    #[clap(long, value_name = "WORKERS", default_value_t = 4)]
+    fetch_workers: usize,
+    /// Number of fetched blocks decoded in parallel ahead of the conflict pass.
+    #[clap(long, value_name = "WORKERS", default_value_t = 2)]
+    decode_workers: usize,
+    /// How many blocks fetching may run ahead of the next block to report. Blocks that finish
+    /// early wait for the ones before them, so output stays in slot order.
+    #[clap(long, value_name = "BLOCKS", default_value_t = 16)]
+    reorder_window: usize,
+    /// Seconds after which a block still being fetched counts as stalled, and an idle fetch
+    /// worker fetches it again. Should be below --rpc-timeout: a fetch that times out or
+    /// otherwise fails only fails the scan if the block's other fetch fails too.
+    #[clap(long, value_name = "SECONDS", default_value_t = 10)]
+    stall_timeout: u64,
+    /// RPC URL to refetch stalled blocks from. Without one, stalled blocks are fetched again
+    /// from the main endpoint over another worker's connection, which only helps when a
+    /// connection is stuck rather than the endpoint itself.
+    #[clap(long, value_name = "URL")]
+    fallback_url: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct BackfillArgs {
+    /// First slot of the range (inclusive).
+    start: Slot,
+    /// Last slot of the range (inclusive).
+    end: Slot,
+    /// Records file to fill. Created if missing and appended to otherwise.
+    #[clap(long, value_name = "FILE")]
+    db: PathBuf,
+    /// Number of blocks fetched and analyzed in parallel, each worker with its own connection.
+    #[clap(long, value_name = "WORKERS", default_value_t = 4)]
+    workers: usize,
+    /// Analyze slots already in the records file again, replacing their records.
+    #[clap(long, default_value_t = false)]
+    force: bool,
+    /// How many blocks workers may run ahead of the next block to record. Blocks that finish
+    /// early wait for the ones before them, so records are appended in slot order.
+    #[clap(long, value_name = "BLOCKS", default_value_t = 16)]
+    reorder_window: usize,
+}
+
+#[derive(Debug, Args)]
+struct WatchArgs {
+    /// Only track and alert on violations touching accounts listed in this file, one pubkey per
+    /// line. Adds to any accounts given with --account or --preset.
+    #[clap(long, value_name = "FILE")]
+    watchlist: Option<PathBuf>,
+    /// Alert on blocks with at least this many violations. Adds to the `alerts` rules in the
+    /// config file; without either, any violation raises an alert.
+    #[clap(long, value_name = "VIOLATIONS")]
+    alert_threshold: Option<usize>,
+    /// Seconds to wait between polls for new blocks.
+    #[clap(long, value_name = "SECONDS", default_value_t = 2)]
+    poll_interval: u64,
+    /// Number of most recent blocks included in the rolling statistics.
+    #[clap(long, default_value_t = 100)]
+    window: usize,
+    /// Only watch this cluster from `clusters` in the config file. May be repeated. Every
+    /// configured cluster is watched by default, and mainnet if none are.
+    #[clap(long = "cluster", value_name = "NAME")]
+    clusters: Vec<String>,
+    /// Address to serve `/healthz` and `/readyz` on, e.g. 0.0.0.0:8080. Liveness fails after
+    /// a minute, or ten poll intervals, without progress; readiness fails while RPC is
+    /// unreachable or processing falls more than --max-lag slots behind the tip.
+    #[clap(long, value_name = "ADDRESS")]
+    health_listen: Option<String>,
+    /// Slots processing may lag the tip before the watcher reports itself not ready.
+    #[clap(long, value_name = "SLOTS", default_value_t = 150)]
+    max_lag: u64,
+    /// Order of the violated accounts listed after an alert.
+    #[clap(long, value_enum, default_value_t = AccountSort::Count)]
+    sort: AccountSort,
+    /// Append each block's record to this NDJSON records file, as backfill does. Only
+    /// supported when watching a single cluster.
+    #[clap(long, value_name = "FILE")]
+    db: Option<PathBuf>,
+    /// Analyze each block again once it is finalized, printing a correction if the results
+    /// differ from the confirmed analysis, and recording both with --db.
+    #[clap(long, default_value_t = false)]
+    reanalyze_finalized: bool,
 }
 
 fn main() {
     let Cli {
-        slot,
-        display_count_only,
+        command,
+        check,
+        include_votes,
+        programs,
+        mut accounts,
+        presets,
+        fee_payer,
+        signatures,
+        mut ignore_accounts,
+        config,
+        violation_rule,
+        filter,
+        priority_model,
+        units,
+        unprioritized,
+        ties,
+        rpc_timeout,
+        rpc_confirm_timeout,
+        rpc_headers,
+        rpc_bearer_token,
+        proxy,
+        provider,
+        no_cache,
+        refresh,
+        max_cache_size,
+        verify_determinism,
+        profile,
+        anonymize,
+        quiet,
+        verbose,
     } = Cli::parse();
+    set_quiet(quiet);
+    if priority_model.is_per_compute_unit() {
+        set_display_unit(units);
+    } else if units != PriorityUnit::default() {
+        eprintln!(
+            "Warning: --units only applies to per-compute-unit priority models, printing \
+             priorities as computed"
+        );
+    }
+    if unprioritized == Some(Unprioritized::BaseFee) && !priority_model.is_per_compute_unit() {
+        eprintln!(
+            "--unprioritized base-fee requires a per-compute-unit priority model, as the imputed \
+             priority is a price per compute unit"
+        );
+        exit(1);
+    }
+    let file_config = config.as_deref().map(FileConfig::load).unwrap_or_default();
+    ignore_accounts.extend(file_config.ignore_accounts());
+    let mut alert_rules = file_config.alert_rules();
+    let clusters = file_config.clusters.clone();
+    for preset in presets {
+        accounts.extend(preset.accounts());
+    }
 
-    let client = RpcClient::new("https://api.mainnet-beta.solana.com");
-    let block = client
-        .get_block_with_config(
-            slot,
-            RpcBlockConfig {
-                encoding: Some(UiTransactionEncoding::Binary),
-                transaction_details: Some(TransactionDetails::Full),
-                rewards: None,
-                commitment: Some(CommitmentConfig {
-                    commitment: CommitmentLevel::Confirmed,
-                }),
-                max_supported_transaction_version: Some(0),
-            },
-        )
-        .unwrap_or_else(|err| {
-            eprintln!("Failed to fetch block at slot {}: {}", slot, err);
+    let mut headers: HeaderMap = file_config
+        .rpc_headers
+        .iter()
+        .map(|header| {
+            parse_header(header).unwrap_or_else(|err| {
+                eprintln!("Invalid RPC header in config: {}", err);
+                exit(1);
+            })
+        })
+        .collect();
+    headers.extend(rpc_headers);
+    if let Some(token) = rpc_bearer_token.or(file_config.rpc_bearer_token) {
+        headers.extend([bearer_header(&token)]);
+    }
+    let rpc_options = RpcOptions {
+        timeout: Duration::from_secs(rpc_timeout),
+        confirm_transaction_initial_timeout: rpc_confirm_timeout.map(Duration::from_secs),
+        headers,
+        proxy,
+    };
+    let provider = provider.or(file_config.provider).map(Provider::adapter);
+    if check.evaluate_fee_estimates {
+        if provider.is_none() {
+            eprintln!("--evaluate-fee-estimates requires --provider");
             exit(1);
-        });
+        }
+        if !priority_model.is_per_compute_unit() {
+            eprintln!(
+                "--evaluate-fee-estimates requires a per-compute-unit priority model, as fee \
+                 estimates are prices per compute unit"
+            );
+            exit(1);
+        }
+    }
+    let connector = RpcConnector::new(&rpc_options);
+    let client = connector.connect(MAINNET_URL);
+    let batch_client = connector.connect_batch(MAINNET_URL);
+    let cache = if no_cache {
+        BlockCache::disabled()
+    } else {
+        let max_cache_size = max_cache_size
+            .or(file_config.max_cache_size)
+            .unwrap_or(BlockCache::DEFAULT_MAX_SIZE_MIB);
+        BlockCache::new(&CacheOptions {
+            refresh,
+            max_size: max_cache_size.saturating_mul(1 << 20),
+        })
+    };
+    let config = AnalysisConfig {
+        priority_model: priority_model.model(),
+        include_votes,
+        programs,
+        accounts,
+        fee_payer,
+        signatures: signatures.as_deref().map(read_signatures),
+        ignore_accounts,
+        violation_rule: violation_rule
+            .as_deref()
+            .map(|path| Arc::new(ViolationRule::load(path))),
+        violation_filter: filter
+            .as_deref()
+            .map(|expression| Arc::new(ViolationFilter::parse(expression))),
+        verify_determinism,
+        profile,
+        anonymizer: anonymize.then(|| {
+            let salt = env::var("PRIORITY_CHECKER_SALT").unwrap_or_else(|_| {
+                eprintln!(
+                    "--anonymize requires a secret salt in PRIORITY_CHECKER_SALT, kept the same \
+                     across runs for hashes to stay stable"
+                );
+                exit(1);
+            });
+            Arc::new(Anonymizer::new(salt.as_bytes()))
+        }),
+        unprioritized,
+        tie_policy: ties,
+    };
+    match command {
+        Some(Command::Compare { slot_a, slot_b }) => {
+            compare_slots(&client, &batch_client, &cache, &config, slot_a, slot_b)
+        }
+        Some(Command::Range {
+            range,
+            window,
+            hot_accounts,
+            min_streak,
+            details,
+            report,
+        }) => {
+            let details = details.then_some(BlockTerminalReporter {
+                client: &client,
+                batch_client: &batch_client,
+                cache: &cache,
+                provider: None,
+                include_votes: config.include_votes,
+                report: &report,
+                count_only: false,
+                compare_recent_fees: false,
+                evaluate_fee_estimates: false,
+                verbose,
+            });
+            scan_range(
+                &client,
+                &batch_client,
+                &cache,
+                &rpc_options,
+                &config,
+                &range,
+                window,
+                hot_accounts,
+                min_streak,
+                details,
+            )
+        }
+        Some(Command::Stats { range, save }) => range_stats(
+            &client,
+            &batch_client,
+            &cache,
+            &rpc_options,
+            &config,
+            &range,
+            save.as_deref(),
+        ),
+        Some(Command::MergeStats { files }) => {
+            let mut stats = RangeStats::new();
+            for path in &files {
+                stats.merge(RangeStats::load(path));
+            }
+            print_range_stats(&client, &stats, include_votes);
+        }
+        Some(Command::Backfill { backfill }) => backfill_records(
+            &client,
+            &batch_client,
+            &cache,
+            &rpc_options,
+            &config,
+            &backfill,
+        ),
+        Some(Command::Schema) => print_schema(),
+        Some(Command::Diff { old, new }) => diff_records(&old, &new),
+        Some(Command::Query { db, query }) => run_query(&client, &db, query),
+        Some(Command::Watch { watch }) => {
+            alert_rules.extend(watch.alert_threshold.map(AlertRule::violation_count));
+            if alert_rules.is_empty() {
+                alert_rules.push(AlertRule::violation_count(1));
+            }
+            let clusters = watched_clusters(&clusters, &watch.clusters, &rpc_options, &cache);
+            watch_clusters(&clusters, &config, &watch, &alert_rules)
+        }
+        None => check_slot(
+            &client,
+            &batch_client,
+            &cache,
+            &connector,
+            provider.as_deref(),
+            &config,
+            check,
+            verbose,
+        ),
+    }
+    if verbose {
+        eprintln!("RPC: {}", describe_transport_stats(&client));
+        for stats in describe_batch_stats() {
+            eprintln!("RPC: {}", stats);
+        }
+    }
+}
 
-    let mut last_access_map: HashMap<Pubkey, LastAccessPriority> = HashMap::default();
-    let mut violated_accounts: HashMap<Pubkey, Vec<[u64; 2]>> = HashMap::new();
-    let mut violating_transaction_signatures: Vec<(Vec<Signature>, Signature)> = Vec::new();
+/// Analyzes `block`, a second time on a copy if `config.verify_determinism` is set.
+fn analyze(
+    analyzer: &mut Analyzer,
+    slot: Slot,
+    block: UiConfirmedBlock,
+    config: &AnalysisConfig,
+) -> BlockAnalysis {
+    if !config.verify_determinism {
+        return analyzer.analyze(slot, block, config);
+    }
+    let repeat = analyzer.analyze(slot, block.clone(), config);
+    let analysis = analyzer.analyze(slot, block, config);
+    verify_identical(&analysis, &repeat);
+    analysis
+}
 
-    let transactions = block.transactions.unwrap_or_else(|| {
-        eprintln!("Block does not have transactions, something is misconfigured");
+/// The block at `slot` from `cache`, fetched from RPC on a miss, and how it was obtained.
+/// Exits if it cannot be fetched.
+fn fetch_cached(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    slot: Slot,
+) -> (UiConfirmedBlock, FetchMetadata) {
+    try_fetch_cached(client, batch_client, cache, slot).unwrap_or_else(|err| {
+        eprintln!("{}", err);
         exit(1);
-    });
-    for transaction in transactions {
-        let mut is_violation = false;
-        let Some(addresses) = Option::<UiLoadedAddresses>::from(
-            transaction
-                .meta
-                .unwrap_or_else(|| {
-                    eprintln!("Transactions do not have metadata, something is misconfigured");
-                    exit(1);
-                })
-                .loaded_addresses,
-        ) else {
-            eprintln!("Transactions do not have loaded addresses, something is misconfigured");
+    })
+}
+
+/// Like [`fetch_cached`], but returns why the block could not be fetched.
+fn try_fetch_cached(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    slot: Slot,
+) -> Result<(UiConfirmedBlock, FetchMetadata), String> {
+    let start = Instant::now();
+    let retry_count = batch_client.retry_count();
+    let (block, cache_hit) =
+        cache.get_or_fetch(slot, || try_fetch_block(client, batch_client, slot))?;
+    let fetch = FetchMetadata {
+        duration: start.elapsed(),
+        rpc_retries: batch_client.retry_count() - retry_count,
+        cache_hit,
+    };
+    Ok((block, fetch))
+}
+
+/// Fetches the block at `slot` through `cache` and analyzes it, noting how it was obtained.
+fn fetch_and_analyze(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    analyzer: &mut Analyzer,
+    slot: Slot,
+    config: &AnalysisConfig,
+) -> BlockAnalysis {
+    try_fetch_and_analyze(client, batch_client, cache, analyzer, slot, config).unwrap_or_else(
+        |err| {
+            eprintln!("{}", err);
             exit(1);
-        };
+        },
+    )
+}
+
+/// Like [`fetch_and_analyze`], but returns why the block could not be fetched.
+fn try_fetch_and_analyze(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    analyzer: &mut Analyzer,
+    slot: Slot,
+    config: &AnalysisConfig,
+) -> Result<BlockAnalysis, String> {
+    let (block, fetch) = try_fetch_cached(client, batch_client, cache, slot)?;
+    let mut analysis = analyze(analyzer, slot, block, config);
+    analysis.fetch = fetch;
+    Ok(analysis)
+}
 
-        let versioned_transaction = transaction.transaction.decode().unwrap_or_else(|| {
-            eprintln!("Failed to decode transaction");
+#[allow(clippy::too_many_arguments)]
+fn check_slot(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    connector: &RpcConnector,
+    provider: Option<&dyn ProviderAdapter>,
+    config: &AnalysisConfig,
+    check: CheckArgs,
+    verbose: bool,
+) {
+    let slot = check.slot.expect("slot is required without a subcommand");
+    let mut baseline = check.baseline.as_deref().map(BaselineReporter::new);
+    let mut profiler = Profiler::new(config.profile);
+
+    let (block, fetch) = fetch_cached(client, batch_client, cache, slot);
+    if let Some(verify_url) = &check.verify_with {
+        let verify_client = connector.connect(verify_url);
+        let verify_block = fetch_block(&verify_client, &connector.connect_batch(verify_url), slot);
+        if verbose {
+            eprintln!("RPC: {}", describe_transport_stats(&verify_client));
+        }
+        verify_block_consistency(&block, &verify_block, verify_url);
+    }
+    let changed_on_finalization = check.check_finality
+        && fetch_finalized_block(client, batch_client, slot).is_some_and(|finalized| {
+            !verify_block_consistency(&block, &finalized, "the finalized block")
+        });
+
+    let block_json = check.bundle.as_ref().map(|_| {
+        serde_json::to_vec(&block).unwrap_or_else(|err| {
+            eprintln!("Failed to serialize block for bundle: {}", err);
+            exit(1);
+        })
+    });
+
+    let mut analysis = analyze(&mut Analyzer::default(), slot, block, config);
+    analysis.fetch = fetch;
+    let report_start = profiler.start();
+    if let Some(path) = &check.svg {
+        write_timeline_svg(path, &analysis, &timeline_accounts(&analysis));
+    }
+    let mut reporters = FanOut::default();
+    reporters.push(BlockTerminalReporter {
+        client,
+        batch_client,
+        cache,
+        provider,
+        include_votes: config.include_votes,
+        report: &check.report,
+        count_only: check.display_count_only,
+        compare_recent_fees: check.compare_recent_fees,
+        evaluate_fee_estimates: check.evaluate_fee_estimates,
+        verbose,
+    });
+    for sink in &check.outputs {
+        reporters.push_sink(sink);
+    }
+    if let Some(baseline) = &mut baseline {
+        reporters.push(baseline);
+    }
+    reporters.report(&analysis, None);
+    reporters.finish();
+    drop(reporters);
+    if let (Some(path), Some(block_json)) = (&check.bundle, block_json) {
+        let record = BlockRecord::new(&analysis, fetch_leader(client, slot));
+        let mut record_json = serde_json::to_vec_pretty(&record).unwrap_or_else(|err| {
+            eprintln!("Failed to serialize analysis for bundle: {}", err);
             exit(1);
         });
-        let signature = versioned_transaction.signatures[0];
-        let sanitized_transaction = SanitizedVersionedTransaction::try_new(versioned_transaction)
-            .unwrap_or_else(|err| {
-                eprintln!("Failed to sanitize transaction: {err}");
-                exit(1);
-            });
-        let priority = get_priority(&sanitized_transaction);
-
-        let mut violating_signatures = HashSet::new();
-        for write_account in addresses.writable.iter().map(parse_pubkey) {
-            match last_access_map.entry(write_account) {
-                Entry::Occupied(mut entry) => {
-                    if entry.get().priority < priority {
-                        is_violation = true;
-                        violated_accounts
-                            .entry(write_account)
-                            .or_default()
-                            .push([entry.get().priority, priority]);
-                        violating_signatures.insert(entry.get().signature);
-                    }
+        record_json.push(b'\n');
+        let mut bundle = EvidenceBundle::new(slot);
+        bundle.add("block.json", block_json);
+        bundle.add("analysis.json", record_json);
+        bundle.write(path);
+    }
+    profiler.record(&analysis, elapsed(report_start));
+    profiler.finish();
+    if changed_on_finalization {
+        eprintln!(
+            "Slot {} changed between confirmed and finalized commitment, so this analysis of the \
+             confirmed block may not hold; rerun with --refresh to analyze the finalized block",
+            slot
+        );
+        exit(1);
+    }
+    if baseline.is_some_and(|baseline| baseline.changed()) {
+        exit(1);
+    }
+}
 
-                    entry.insert(LastAccessPriority {
-                        last_access: LastAccess::Write,
-                        priority,
-                        signature,
-                    });
-                }
-                Entry::Vacant(entry) => {
-                    entry.insert(LastAccessPriority {
-                        last_access: LastAccess::Write,
-                        priority,
-                        signature,
-                    });
-                }
+/// Prints the full report for each block: the violations found, with the listings selected in
+/// [`ReportArgs`], and the block's summary statistics.
+struct BlockTerminalReporter<'a> {
+    client: &'a RpcClient,
+    batch_client: &'a BatchClient,
+    cache: &'a BlockCache,
+    provider: Option<&'a dyn ProviderAdapter>,
+    include_votes: bool,
+    report: &'a ReportArgs,
+    /// Print only the number of violating transactions.
+    count_only: bool,
+    /// Compare landed priorities against the endpoint's recent prioritization fees, which only
+    /// makes sense for blocks near the tip.
+    compare_recent_fees: bool,
+    /// Score the provider's fee estimates, which also only makes sense near the tip.
+    evaluate_fee_estimates: bool,
+    verbose: bool,
+}
+
+impl Reporter for BlockTerminalReporter<'_> {
+    fn report(&mut self, analysis: &BlockAnalysis, _leader: Option<Pubkey>) {
+        if self.count_only {
+            say!("{}", analysis.violating_transaction_signatures.len());
+            return;
+        }
+        let report = self.report;
+        let labels = if report.resolve_token_accounts {
+            let accounts: Vec<_> = analysis.violated_accounts.keys().copied().collect();
+            TokenLabels::new(self.cache.dir()).resolve(self.client, &accounts)
+        } else {
+            HashMap::new()
+        };
+        if self.compare_recent_fees {
+            print_recent_fee_comparison(self.batch_client, self.provider, analysis);
+        }
+        if let Some(account_count) = report.fee_table {
+            print_fee_table(analysis, account_count);
+        }
+        if report.auction_report {
+            print_auction_report(analysis);
+        }
+        if let Some(provider) = self.provider.filter(|_| self.evaluate_fee_estimates) {
+            print_fee_estimate_evaluation(self.batch_client, provider, analysis);
+        }
+        if let Some(count) = report.show_top_txs {
+            print_top_transactions(analysis, count);
+        }
+        say!(
+            "Analyzed {}",
+            describe_transactions(
+                analysis.analyzed_transaction_count(),
+                analysis.vote_transaction_count(),
+                self.include_votes,
+            )
+        );
+        let durable_nonce_count = analysis.durable_nonce_signatures().len();
+        if durable_nonce_count > 0 {
+            say!("{} durable-nonce transactions", durable_nonce_count);
+        }
+        let thread_inference = ThreadInference::new(analysis, report.banking_threads);
+        let page = Page {
+            offset: report.offset,
+            limit: report.limit,
+        };
+        print_violations(
+            analysis,
+            &thread_inference,
+            &labels,
+            report.sort,
+            page,
+            self.verbose,
+        );
+        print_zero_priority_jumps(analysis);
+        if report.flag_sandwiches {
+            print_sandwiches(analysis);
+        }
+        if report.read_starvation {
+            print_read_starvation(analysis, page);
+        }
+        if let Some(min_writes) = report.rank_correlation {
+            print_rank_correlations(analysis, min_writes, page);
+        }
+        if report.explain {
+            print_explanations(analysis, page);
+        }
+        print_thread_attribution(&thread_inference);
+        print_block_summary(analysis, self.verbose);
+        print_compute_budget_issues(analysis);
+        print_failure_correlation(&FailureCorrelation::new(analysis));
+    }
+}
+
+fn print_violations(
+    analysis: &BlockAnalysis,
+    thread_inference: &ThreadInference,
+    labels: &HashMap<Pubkey, String>,
+    sort: AccountSort,
+    page: Page,
+    verbose: bool,
+) {
+    if analysis.violated_accounts.is_empty() {
+        say!("No priority violations found");
+        return;
+    }
+
+    say!(
+        "{} priority violations found on {} accounts:",
+        analysis.violating_transaction_signatures.len(),
+        analysis.violated_accounts.len()
+    );
+    let (accounts, remaining) = page.select(analysis.sorted_violated_accounts(sort));
+    for (account, violations) in accounts {
+        match labels.get(&account) {
+            Some(label) => say!("Account: {} ({})", account, label),
+            None => say!("Account: {}", account),
+        }
+        for violation in violations {
+            let attribution = match thread_inference.same_thread(violation) {
+                Some(thread) => format!("same thread {}", thread),
+                None => "cross-thread".to_string(),
+            };
+            say!(
+                "  {} (#{}) -> {} (#{}), {} apart, {}",
+                display_priority(violation.previous_priority),
+                violation.previous_index,
+                display_priority(violation.priority),
+                violation.index,
+                violation.distance(),
+                attribution
+            );
+        }
+        if verbose {
+            print_account_timeline(analysis, &account, violations);
+        }
+    }
+    print_remaining(remaining, "accounts");
+    say!("Violating transactions:");
+    let transactions: HashMap<_, _> = analysis
+        .transactions
+        .iter()
+        .map(|transaction| (transaction.signature, transaction))
+        .collect();
+    let describe = |signature: &Signature| match transactions.get(signature) {
+        Some(transaction) => format!("{} (#{})", signature, transaction.index),
+        None => signature.to_string(),
+    };
+    let (violating_transactions, remaining) =
+        page.select(&analysis.violating_transaction_signatures);
+    for (previous_signatures, signature) in violating_transactions {
+        let Some(transaction) = transactions.get(signature) else {
+            continue;
+        };
+        let marker = if transaction.is_durable_nonce {
+            " (durable nonce)"
+        } else {
+            ""
+        };
+        let previous: Vec<_> = previous_signatures.iter().map(describe).collect();
+        say!(
+            "[{}] -> {}{}",
+            previous.join(", "),
+            describe(signature),
+            marker
+        );
+        if verbose {
+            for inner_instruction in &transaction.inner_instructions {
+                say!("    {}", inner_instruction);
             }
         }
+    }
+    print_remaining(remaining, "violating transactions");
+}
+
+/// Prints every access to `account` on one line, marking accesses that violated ordering with
+/// `!`.
+fn print_account_timeline(analysis: &BlockAnalysis, account: &Pubkey, violations: &[Violation]) {
+    let violating_indexes: HashSet<_> =
+        violations.iter().map(|violation| violation.index).collect();
+    let timeline: Vec<_> = analysis
+        .account_timeline(account)
+        .into_iter()
+        .map(|access| {
+            let marker = if violating_indexes.contains(&access.index) {
+                "!"
+            } else {
+                ""
+            };
+            format!("{}{}", access, marker)
+        })
+        .collect();
+    say!("  timeline: {}", timeline.join("  "));
+}
 
-        for read_account in addresses.readonly.iter().map(parse_pubkey) {
-            match last_access_map.entry(read_account) {
-                Entry::Occupied(mut entry) => {
-                    if entry.get().last_access == LastAccess::Write
-                        && entry.get().priority < priority
+fn print_sandwiches(analysis: &BlockAnalysis) {
+    let sandwiches = find_sandwiches(analysis);
+    if sandwiches.is_empty() {
+        say!("No likely sandwiches near violations");
+        return;
+    }
+    say!("{} likely sandwiches near violations:", sandwiches.len());
+    for sandwich in sandwiches {
+        say!(
+            "  pool {}: #{} front-run and #{} back-run by {} around victim #{} {}",
+            sandwich.pool,
+            sandwich.front_index,
+            sandwich.back_index,
+            sandwich.attacker,
+            sandwich.victim_index,
+            analysis.transactions[sandwich.victim_index].signature
+        );
+    }
+}
+
+fn print_read_starvation(analysis: &BlockAnalysis, page: Page) {
+    let starvation = find_read_starvation(analysis);
+    if starvation.is_empty() {
+        say!("No reads landed behind lower-priority writes");
+        return;
+    }
+    say!(
+        "{} accounts with reads behind lower-priority writes:",
+        starvation.len()
+    );
+    let (starvation, remaining) = page.select(starvation);
+    for account in starvation {
+        say!(
+            "  {}: {} of {} reads starved by {} lower-priority writes, up to {} ahead of #{} {}",
+            account.account,
+            account.starved_read_count,
+            account.read_count,
+            account.jumping_write_count,
+            account.longest_streak,
+            account.longest_streak_index,
+            analysis.transactions[account.longest_streak_index].signature
+        );
+    }
+    print_remaining(remaining, "accounts");
+}
+
+fn print_rank_correlations(analysis: &BlockAnalysis, min_writes: usize, page: Page) {
+    let correlations = account_rank_correlations(analysis, min_writes);
+    if correlations.is_empty() {
+        say!(
+            "No accounts with at least {} writes at differing priorities",
+            min_writes
+        );
+        return;
+    }
+    let unrelated_count = correlations
+        .iter()
+        .filter(|correlation| correlation.is_unrelated())
+        .count();
+    say!(
+        "Priority rank correlation of {} accounts, {} ordered unrelated to priority:",
+        correlations.len(),
+        unrelated_count
+    );
+    let (correlations, remaining) = page.select(correlations);
+    for correlation in correlations {
+        let marker = if correlation.is_unrelated() {
+            " (unrelated to priority)"
+        } else {
+            ""
+        };
+        say!(
+            "  {}: {:+.3} over {} writes{}",
+            correlation.account,
+            correlation.correlation,
+            correlation.write_count,
+            marker
+        );
+    }
+    print_remaining(remaining, "accounts");
+}
+
+fn print_zero_priority_jumps(analysis: &BlockAnalysis) {
+    let jumps = analysis.zero_priority_jumps();
+    if jumps.is_empty() {
+        return;
+    }
+    say!(
+        "{} zero-priority transactions landed ahead of prioritized ones:",
+        jumps.len()
+    );
+    for (account, violation) in jumps {
+        say!(
+            "  #{} {} (priority 0) ahead of #{} {} (priority {}) on {}",
+            violation.previous_index,
+            analysis.transactions[violation.previous_index].signature,
+            violation.index,
+            analysis.transactions[violation.index].signature,
+            display_priority(violation.priority),
+            account
+        );
+    }
+}
+
+fn print_explanations(analysis: &BlockAnalysis, page: Page) {
+    let mut violations: Vec<_> = analysis
+        .violated_accounts
+        .iter()
+        .flat_map(|(account, violations)| {
+            violations.iter().map(move |violation| (account, violation))
+        })
+        .collect();
+    violations.sort_by_key(|(account, violation)| (violation.index, **account));
+
+    say!("Violation details:");
+    let (violations, remaining) = page.select(violations);
+    for (account, violation) in violations {
+        let earlier = &analysis.transactions[violation.previous_index];
+        let later = &analysis.transactions[violation.index];
+        say!(
+            "{} violation on {}, priority {} -> {}:",
+            violation.kind,
+            account,
+            display_priority(violation.previous_priority),
+            display_priority(violation.priority)
+        );
+        for (label, transaction) in [("earlier", earlier), ("later", later)] {
+            let programs: Vec<_> = transaction
+                .program_ids
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            say!(
+                "  {} #{} {}: priority {}, CU limit {}, fee payer {}, programs {}",
+                label,
+                transaction.index,
+                transaction.signature,
+                display_priority(transaction.priority),
+                transaction.compute_unit_limit,
+                transaction.fee_payer,
+                programs.join(", ")
+            );
+        }
+        let shared: Vec<_> = earlier
+            .conflicting_accounts(later)
+            .into_iter()
+            .map(|(account, kind)| format!("{} ({})", account, kind))
+            .collect();
+        say!("  shared accounts: {}", shared.join(", "));
+    }
+    print_remaining(remaining, "violations");
+}
+
+fn print_thread_attribution(thread_inference: &ThreadInference) {
+    say!(
+        "Inferred banking threads: {}, violations {} cross-thread / {} same-thread",
+        thread_inference.thread_stats.len(),
+        thread_inference.cross_thread_violation_count,
+        thread_inference.same_thread_violation_count
+    );
+    for (thread, stats) in thread_inference.thread_stats.iter().enumerate() {
+        say!(
+            "  thread {}: {} transactions, priority {} -> {}, {} same-thread violations",
+            thread,
+            stats.transaction_count,
+            display_priority(stats.first_priority),
+            display_priority(stats.last_priority),
+            stats.same_thread_violation_count
+        );
+    }
+}
+
+fn print_block_summary(analysis: &BlockAnalysis, verbose: bool) {
+    say!("Normalized violations: {}", analysis.normalized_rates());
+    say!("Compute budget: {}", analysis.compute_budget_adoption());
+    say!("Ties: {}", analysis.tie_stats());
+    say!("Queue jumps: {}", QueueJumps::new(analysis));
+    let disorder = analysis.ordering_disorder();
+    say!(
+        "Ordering disorder: {:.4} inversion ratio, {:.4} mean displacement, {} max displacement",
+        disorder.inversion_ratio,
+        disorder.mean_displacement,
+        disorder.max_displacement
+    );
+    let fee_totals = analysis.fee_totals();
+    say!(
+        "Fees: {} ({} from prioritization), {} burned, leader revenue {} ({} from \
+         prioritization)",
+        format_sol(fee_totals.total_fees),
+        format_sol(fee_totals.priority_fees),
+        format_sol(fee_totals.burned()),
+        format_sol(fee_totals.leader_revenue()),
+        format_sol(fee_totals.leader_priority_revenue())
+    );
+    let packing_estimate = analysis.packing_estimate();
+    say!(
+        "Priority-ordered packing: {} prioritization revenue vs {} actual ({}), {} \
+         transactions moved (up to {} positions), {} transactions cut",
+        format_sol(packing_estimate.packed_revenue),
+        format_sol(packing_estimate.actual_revenue),
+        format_sol_change(packing_estimate.revenue_delta()),
+        packing_estimate.moved_count,
+        packing_estimate.max_move,
+        packing_estimate.cut_count
+    );
+    if verbose {
+        for (signature, priority) in &packing_estimate.cut_transactions {
+            say!(
+                "  cut: {} (priority {})",
+                signature,
+                display_priority(*priority)
+            );
+        }
+    }
+}
+
+fn print_compute_budget_issues(analysis: &BlockAnalysis) {
+    let transactions: Vec<_> = analysis
+        .transactions
+        .iter()
+        .filter(|transaction| !transaction.compute_budget_issues.is_empty())
+        .collect();
+    if transactions.is_empty() {
+        return;
+    }
+
+    say!(
+        "{} transactions with invalid compute-budget usage (priority may differ from the \
+         runtime's):",
+        transactions.len()
+    );
+    for transaction in transactions {
+        let issues: Vec<_> = transaction
+            .compute_budget_issues
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        say!("  {}: {}", transaction.signature, issues.join(", "));
+    }
+}
+
+fn print_failure_correlation(failure_correlation: &FailureCorrelation) {
+    say!("Failure rates:");
+    for (label, counts) in [
+        ("violating", &failure_correlation.violating),
+        ("non-violating", &failure_correlation.non_violating),
+    ] {
+        say!(
+            "  {}: {}/{} failed ({:.2}%)",
+            label,
+            counts.failed_count,
+            counts.transaction_count,
+            counts.failure_rate() * 100.0
+        );
+        for (category, count) in &counts.categories {
+            say!("    {}: {}", category, count);
+        }
+    }
+}
+
+/// Accounts shown in the SVG timeline: violated accounts, most violations first, then the most
+/// write-locked accounts.
+fn timeline_accounts(analysis: &BlockAnalysis) -> Vec<Pubkey> {
+    const TIMELINE_ACCOUNTS: usize = 20;
+
+    let mut violated: Vec<_> = analysis
+        .violated_accounts
+        .iter()
+        .map(|(account, violations)| (*account, violations.len()))
+        .collect();
+    violated.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut accounts: Vec<_> = violated.into_iter().map(|(account, _)| account).collect();
+    for (account, _) in analysis.top_contended_accounts(TIMELINE_ACCOUNTS) {
+        if !accounts.contains(&account) {
+            accounts.push(account);
+        }
+    }
+    accounts.truncate(TIMELINE_ACCOUNTS);
+    accounts
+}
+
+fn print_top_transactions(analysis: &BlockAnalysis, count: usize) {
+    let block_length = analysis.transactions.len();
+    say!("Highest-priority transactions:");
+    for transaction in analysis.top_priority_transactions(count) {
+        say!(
+            "  #{} of {} ({:.0}% into the block) {}: priority {}, CU limit {}{}",
+            transaction.index,
+            block_length,
+            transaction.index as f64 * 100.0 / block_length as f64,
+            transaction.signature,
+            display_priority(transaction.priority),
+            transaction.compute_unit_limit,
+            if transaction.is_violation {
+                ", violation"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+fn print_fee_table(analysis: &BlockAnalysis, account_count: usize) {
+    say!(
+        "{:<46}{:>8}{:>16}{:>16}{:>16}",
+        "Account",
+        "Writes",
+        "Min",
+        "Median",
+        "Max"
+    );
+    for (account, write_count) in analysis.top_contended_accounts(account_count) {
+        let mut priorities = analysis.write_lock_priorities(&account);
+        priorities.sort_unstable();
+        say!(
+            "{:<46}{:>8}{:>16}{:>16}{:>16}",
+            account,
+            write_count,
+            display_priority(percentile(&priorities, 0.0)),
+            display_priority(percentile(&priorities, 0.5)),
+            display_priority(percentile(&priorities, 1.0)),
+        );
+    }
+}
+
+fn print_auction_report(analysis: &BlockAnalysis) {
+    const HOT_ACCOUNTS: usize = 5;
+
+    let report = AuctionReport::new(analysis, HOT_ACCOUNTS);
+    let priorities = &report.priorities;
+    say!("Auction report:");
+    say!(
+        "  fill {:.1}%, {} of {} transactions prioritized, {} contended accounts",
+        report.fill * 100.0,
+        priorities.nonzero_count,
+        report.transaction_count,
+        report.contended_account_count
+    );
+    say!(
+        "  priorities: min {} / p25 {} / median {} / p75 {} / p90 {} / max {}",
+        display_priority(priorities.min),
+        display_priority(priorities.p25),
+        display_priority(priorities.median),
+        display_priority(priorities.p75),
+        display_priority(priorities.p90),
+        display_priority(priorities.max)
+    );
+    for hot_account in &report.hot_accounts {
+        say!(
+            "  {}: {} writes, clearing price {}, median {}, max {}",
+            hot_account.account,
+            hot_account.write_count,
+            display_priority(hot_account.clearing_price),
+            display_priority(hot_account.median_priority),
+            display_priority(hot_account.max_priority)
+        );
+    }
+}
+
+/// Scores `provider`'s fee estimates for the block's hot accounts against their clearing prices.
+fn print_fee_estimate_evaluation(
+    client: &BatchClient,
+    provider: &dyn ProviderAdapter,
+    analysis: &BlockAnalysis,
+) {
+    const HOT_ACCOUNTS: usize = 5;
+
+    let format_error = |error: i128| {
+        let sign = if error < 0 { "-" } else { "+" };
+        let magnitude = u64::try_from(error.unsigned_abs()).unwrap_or(u64::MAX);
+        format!("{}{}", sign, display_priority(magnitude))
+    };
+    let report = AuctionReport::new(analysis, HOT_ACCOUNTS);
+    let accounts: Vec<_> = report
+        .hot_accounts
+        .iter()
+        .map(|hot_account| hot_account.account)
+        .collect();
+    let estimates = provider.priority_fee_estimates(client, &accounts);
+    let mut evaluations = Vec::with_capacity(accounts.len());
+    for (hot_account, estimate) in report.hot_accounts.iter().zip(estimates) {
+        match estimate {
+            Ok(estimate) => evaluations.push(EstimateEvaluation {
+                account: hot_account.account,
+                clearing_price: hot_account.clearing_price,
+                estimate,
+            }),
+            Err(err) => eprintln!(
+                "Warning: failed to fetch {} fee estimate for {}: {}",
+                provider.name(),
+                hot_account.account,
+                err
+            ),
+        }
+    }
+    if evaluations.is_empty() {
+        say!(
+            "No hot accounts with {} fee estimates to evaluate",
+            provider.name()
+        );
+        return;
+    }
+
+    say!("Fee estimates ({}) vs clearing prices:", provider.name());
+    for evaluation in &evaluations {
+        let cleared: Vec<_> = evaluation
+            .estimate
+            .levels()
+            .into_iter()
+            .filter(|(_, priority)| evaluation.clears(*priority))
+            .map(|(level, _)| level)
+            .collect();
+        say!(
+            "  {}: clearing price {}, estimate medium {} ({}), levels pricing in: {}",
+            evaluation.account,
+            display_priority(evaluation.clearing_price),
+            display_priority(evaluation.estimate.medium),
+            format_error(evaluation.error()),
+            if cleared.is_empty() {
+                "none".to_string()
+            } else {
+                cleared.join(", ")
+            }
+        );
+    }
+    let count = evaluations.len() as i128;
+    let mean_error = evaluations
+        .iter()
+        .map(EstimateEvaluation::error)
+        .sum::<i128>()
+        / count;
+    let mean_absolute_error = evaluations
+        .iter()
+        .map(|evaluation| evaluation.error().abs())
+        .sum::<i128>()
+        / count;
+    say!(
+        "  medium priced in on {} of {} hot accounts, mean error {}, mean absolute error {}",
+        evaluations
+            .iter()
+            .filter(|evaluation| evaluation.clears(evaluation.estimate.medium))
+            .count(),
+        evaluations.len(),
+        format_error(mean_error),
+        format_error(mean_absolute_error).trim_start_matches('+'),
+    );
+}
+
+/// Compares what the provider's getRecentPrioritizationFees reports for the block's hot accounts,
+/// and its own fee estimate if it has an adapter, against the priorities that actually landed on
+/// them.
+fn print_recent_fee_comparison(
+    client: &BatchClient,
+    provider: Option<&dyn ProviderAdapter>,
+    analysis: &BlockAnalysis,
+) {
+    const HOT_ACCOUNTS: usize = 5;
+
+    let accounts: Vec<_> = analysis
+        .top_contended_accounts(HOT_ACCOUNTS)
+        .into_iter()
+        .map(|(account, _)| account)
+        .collect();
+    let recent_fees = fetch_recent_prioritization_fees(client, &accounts);
+    let estimates = provider.map(|provider| provider.priority_fee_estimates(client, &accounts));
+    say!("Recent prioritization fees (RPC) vs landed priorities:");
+    for (index, (account, recent_fees)) in accounts.into_iter().zip(recent_fees).enumerate() {
+        let mut landed = analysis.write_lock_priorities(&account);
+        landed.sort_unstable();
+        let mut recent: Vec<_> = recent_fees.iter().map(|(_, fee)| *fee).collect();
+        recent.sort_unstable();
+        let at_slot = recent_fees
+            .iter()
+            .find(|(slot, _)| *slot == analysis.slot)
+            .map_or_else(
+                || "outside window".to_string(),
+                |(_, fee)| display_priority(*fee),
+            );
+        say!(
+            "  {}: rpc at slot {}, rpc recent median {} ({} slots), landed min {} / median {} \
+             / max {}",
+            account,
+            at_slot,
+            display_priority(percentile(&recent, 0.5)),
+            recent.len(),
+            display_priority(percentile(&landed, 0.0)),
+            display_priority(percentile(&landed, 0.5)),
+            display_priority(percentile(&landed, 1.0)),
+        );
+        let (Some(provider), Some(estimates)) = (provider, &estimates) else {
+            continue;
+        };
+        match &estimates[index] {
+            Ok(estimate) => say!(
+                "    {} estimate: {}",
+                provider.name(),
+                estimate
+                    .levels()
+                    .map(|(level, priority)| format!("{} {}", level, display_priority(priority)))
+                    .join(" / ")
+            ),
+            Err(err) => eprintln!(
+                "Warning: failed to fetch {} fee estimate for {}: {}",
+                provider.name(),
+                account,
+                err
+            ),
+        }
+    }
+}
+
+fn compare_slots(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    config: &AnalysisConfig,
+    slot_a: Slot,
+    slot_b: Slot,
+) {
+    const TOP_ACCOUNTS: usize = 5;
+
+    let format_leader = |leader: Option<_>| {
+        leader.map_or_else(
+            || "unknown".to_string(),
+            |leader: Pubkey| leader.to_string(),
+        )
+    };
+    let leader_a = format_leader(fetch_leader(client, slot_a));
+    let leader_b = format_leader(fetch_leader(client, slot_b));
+    let mut analyzer = Analyzer::default();
+    let analysis_a = fetch_and_analyze(client, batch_client, cache, &mut analyzer, slot_a, config);
+    let analysis_b = fetch_and_analyze(client, batch_client, cache, &mut analyzer, slot_b, config);
+    let distribution_a = analysis_a.priority_distribution();
+    let distribution_b = analysis_b.priority_distribution();
+
+    let row = |label: &str, a: &dyn std::fmt::Display, b: &dyn std::fmt::Display| {
+        say!("{:<24}{:<48}{}", label, a, b);
+    };
+    row("Slot", &analysis_a.slot, &analysis_b.slot);
+    row("Leader", &leader_a, &leader_b);
+    row(
+        "Transactions",
+        &analysis_a.analyzed_transaction_count(),
+        &analysis_b.analyzed_transaction_count(),
+    );
+    row(
+        "Vote transactions",
+        &analysis_a.vote_transaction_count(),
+        &analysis_b.vote_transaction_count(),
+    );
+    row(
+        "Violations",
+        &analysis_a.violating_transaction_signatures.len(),
+        &analysis_b.violating_transaction_signatures.len(),
+    );
+    row(
+        "Violated accounts",
+        &analysis_a.violated_accounts.len(),
+        &analysis_b.violated_accounts.len(),
+    );
+    row(
+        "Prioritized txs",
+        &distribution_a.nonzero_count,
+        &distribution_b.nonzero_count,
+    );
+    row(
+        "Priority min",
+        &display_priority(distribution_a.min),
+        &display_priority(distribution_b.min),
+    );
+    row(
+        "Priority p25",
+        &display_priority(distribution_a.p25),
+        &display_priority(distribution_b.p25),
+    );
+    row(
+        "Priority median",
+        &display_priority(distribution_a.median),
+        &display_priority(distribution_b.median),
+    );
+    row(
+        "Priority p75",
+        &display_priority(distribution_a.p75),
+        &display_priority(distribution_b.p75),
+    );
+    row(
+        "Priority p90",
+        &display_priority(distribution_a.p90),
+        &display_priority(distribution_b.p90),
+    );
+    row(
+        "Priority max",
+        &display_priority(distribution_a.max),
+        &display_priority(distribution_b.max),
+    );
+    let disorder_a = analysis_a.ordering_disorder();
+    let disorder_b = analysis_b.ordering_disorder();
+    row(
+        "Inversion ratio",
+        &format!("{:.4}", disorder_a.inversion_ratio),
+        &format!("{:.4}", disorder_b.inversion_ratio),
+    );
+    row(
+        "Mean displacement",
+        &format!("{:.4}", disorder_a.mean_displacement),
+        &format!("{:.4}", disorder_b.mean_displacement),
+    );
+    row(
+        "Max displacement",
+        &disorder_a.max_displacement,
+        &disorder_b.max_displacement,
+    );
+
+    say!("Top contended accounts (write locks):");
+    let top_a = analysis_a.top_contended_accounts(TOP_ACCOUNTS);
+    let top_b = analysis_b.top_contended_accounts(TOP_ACCOUNTS);
+    for index in 0..top_a.len().max(top_b.len()) {
+        let format_entry = |entry: Option<&(_, usize)>| {
+            entry
+                .map(|(account, count)| format!("{} ({})", account, count))
+                .unwrap_or_default()
+        };
+        row(
+            "",
+            &format_entry(top_a.get(index)),
+            &format_entry(top_b.get(index)),
+        );
+    }
+}
+
+/// Analyzes every block in the range, handing each to `reporter` and to the file outputs
+/// requested in `range`.
+///
+/// Blocks flow through a pipeline of bounded channels: fetch workers, each with its own
+/// connection, then decode workers, then this thread, which runs the conflict pass and reports
+/// blocks in slot order. Fetching stays at most `--reorder-window` blocks ahead of reporting, so
+/// one slow block cannot make the rest pile up in memory, and fetch workers with nothing else to
+/// do refetch blocks stalled past `--stall-timeout`, so it cannot hold up the scan for long.
+/// A failed fetch only stops the scan once the block's refetch, if any, has failed too; the
+/// blocks before it are still reported and the outputs finished before exiting.
+///
+/// Returns whether any block differed from `--baseline`, leaving it to the caller to exit with
+/// an error once its own output is written.
+fn for_each_block(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    rpc_options: &RpcOptions,
+    config: &AnalysisConfig,
+    range: &RangeArgs,
+    reporter: impl Reporter,
+) -> bool {
+    let mut slots = fetch_block_slots(client, range.start, range.end);
+    let leaders = fetch_slot_leaders(batch_client, range.start, range.end);
+    let mut baseline = range.baseline.as_deref().map(BaselineReporter::new);
+    let mut reporters = FanOut::default();
+    if let Some(path) = &range.csv {
+        reporters.push(SlotCsvWriter::new(path));
+    }
+    if let Some(path) = &range.ndjson {
+        if range.force || !path.exists() {
+            reporters.push(NdjsonReporter::new(path));
+        } else {
+            let recorded = recorded_slots(path);
+            let slot_count = slots.len();
+            slots.retain(|slot| !recorded.contains(slot));
+            if slots.len() < slot_count {
+                say!(
+                    "Skipping {} slots already in {}, pass --force to analyze them again",
+                    slot_count - slots.len(),
+                    path.display()
+                );
+            }
+            reporters.push(NdjsonReporter::append(path));
+        }
+    }
+    if let Some(path) = &range.prometheus {
+        reporters.push(PrometheusReporter::new(path));
+    }
+    for sink in &range.outputs {
+        reporters.push_sink(sink);
+    }
+    if let Some(baseline) = &mut baseline {
+        reporters.push(baseline);
+    }
+    reporters.push(reporter);
+    shutdown::install();
+
+    const STEAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+    let fetch_workers = range.fetch_workers.max(1);
+    let decode_workers = range.decode_workers.max(1);
+    let window = ReorderWindow::new(range.reorder_window);
+    let in_flight = InFlight::new(Duration::from_secs(range.stall_timeout));
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let (fetched_sender, fetched_receiver) = mpsc::sync_channel(fetch_workers);
+    let fetched_receiver = Mutex::new(fetched_receiver);
+    let (decoded_sender, decoded_receiver) = mpsc::sync_channel(decode_workers);
+    let mut analyzer = Analyzer::default();
+    let mut serial_analyzer = Analyzer::default();
+    let mut profiler = Profiler::new(config.profile);
+    let mut pending = ReorderBuffer::default();
+    thread::scope(|scope| {
+        for _ in 0..fetch_workers {
+            let (slots, next, window, in_flight, failed) =
+                (&slots, &next, &window, &in_flight, &failed);
+            let sender = fetched_sender.clone();
+            scope.spawn(move || {
+                let connector = RpcConnector::new(rpc_options);
+                let client = connector.connect(MAINNET_URL);
+                let batch_client = connector.connect_batch(MAINNET_URL);
+                let fallback = range
+                    .fallback_url
+                    .as_ref()
+                    .map(|url| (connector.connect(url), connector.connect_batch(url)));
+                let attempt_failed = |position, slot, err: String| match in_flight.fail(position) {
+                    FailedAttempt::Superseded => {}
+                    FailedAttempt::Pending => {
+                        eprintln!("Warning: leaving slot {} to its other fetch. {}", slot, err)
+                    }
+                    FailedAttempt::Failed => {
+                        eprintln!("{}", err);
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                };
+                // Refetches the longest stalled block, if any, returning whether there was one.
+                let steal = || {
+                    let Some((position, slot, stalled_for)) = in_flight.steal() else {
+                        return false;
+                    };
+                    let (client, batch_client) = fallback
+                        .as_ref()
+                        .map_or((&client, &batch_client), |(client, batch_client)| {
+                            (client, batch_client)
+                        });
+                    eprintln!(
+                        "Warning: fetching slot {} has stalled for {}s, fetching it again from {}",
+                        slot,
+                        stalled_for.as_secs(),
+                        batch_client.url()
+                    );
+                    // Left uncached: the stalled fetch still writes the block when it finishes.
+                    match try_fetch_cached(client, batch_client, &BlockCache::disabled(), slot) {
+                        Ok((block, fetch)) => {
+                            if in_flight.finish(position) {
+                                let _ = sender.send((position, slot, block, fetch));
+                            }
+                        }
+                        Err(err) => attempt_failed(position, slot, err),
+                    }
+                    true
+                };
+                'claim: while !shutdown::requested() && !failed.load(Ordering::Relaxed) {
+                    let position = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&slot) = slots.get(position) else {
+                        break;
+                    };
+                    // Every claimed position is fetched, even after an interrupt, so the
+                    // ordered stage never waits on a gap. Waiting on the window usually means
+                    // an earlier block is slow, so take it over meanwhile. Once a block has
+                    // failed, the window never moves past it and nothing later is reported.
+                    while !window.wait_for_timeout(position, STEAL_POLL_INTERVAL) {
+                        if failed.load(Ordering::Relaxed) {
+                            continue 'claim;
+                        }
+                        steal();
+                    }
+                    in_flight.start(position, slot);
+                    let (block, fetch) = match try_fetch_cached(&client, &batch_client, cache, slot)
                     {
-                        is_violation = true;
-                        violated_accounts
-                            .entry(read_account)
-                            .or_default()
-                            .push([entry.get().priority, priority]);
-                        violating_signatures.insert(entry.get().signature);
+                        Ok(fetched) => fetched,
+                        Err(err) => {
+                            attempt_failed(position, slot, err);
+                            continue;
+                        }
+                    };
+                    if in_flight.finish(position)
+                        && sender.send((position, slot, block, fetch)).is_err()
+                    {
+                        break;
                     }
-
-                    entry.insert(LastAccessPriority {
-                        last_access: LastAccess::Read,
-                        priority,
-                        signature,
-                    });
                 }
-                Entry::Vacant(entry) => {
-                    entry.insert(LastAccessPriority {
-                        last_access: LastAccess::Read,
-                        priority,
-                        signature,
-                    });
+                // Out of slots to claim: help with the last ones still being fetched.
+                while !in_flight.is_empty()
+                    && !shutdown::requested()
+                    && !failed.load(Ordering::Relaxed)
+                {
+                    if !steal() {
+                        thread::sleep(STEAL_POLL_INTERVAL);
+                    }
                 }
+            });
+        }
+        drop(fetched_sender);
+        for _ in 0..decode_workers {
+            let (receiver, sender) = (&fetched_receiver, decoded_sender.clone());
+            scope.spawn(move || {
+                let mut decoder = BlockDecoder::default();
+                loop {
+                    // Bound separately so the lock is released before decoding.
+                    let fetched = receiver.lock().unwrap().recv();
+                    let Ok((position, slot, block, fetch)) = fetched else {
+                        break;
+                    };
+                    let serial = config.verify_determinism.then(|| block.clone());
+                    let mut decoded = decoder.decode(slot, block, config);
+                    decoded.fetch = fetch;
+                    if sender.send((position, decoded, serial)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(decoded_sender);
+        for (position, decoded, serial) in decoded_receiver {
+            pending.insert(position, (decoded, serial));
+            while let Some((decoded, serial)) = pending.pop() {
+                let analysis = analyzer.analyze_decoded(decoded, config);
+                // Checks the pipeline against a serial run, decoding the same block again on
+                // this thread with scratch state of its own.
+                if let Some(block) = serial {
+                    let serial = serial_analyzer.analyze(analysis.slot, block, config);
+                    verify_identical(&analysis, &serial);
+                }
+                let report_start = profiler.start();
+                reporters.report(&analysis, leaders.get(&analysis.slot).copied());
+                profiler.record(&analysis, elapsed(report_start));
+                window.advance(pending.released());
             }
         }
+    });
+    if let Some(slot) = slots.get(pending.released()) {
+        eprintln!(
+            "Interrupted before slot {}, rerun from there to resume",
+            slot
+        );
+    }
+    reporters.finish();
+    profiler.finish();
+    if failed.into_inner() {
+        exit(1);
+    }
+    drop(reporters);
+    baseline.is_some_and(|baseline| baseline.changed())
+}
 
-        if is_violation {
-            let mut violating_signatures: Vec<_> = violating_signatures.into_iter().collect();
-            violating_signatures.sort();
+/// Prints a line per block plus rolling statistics over the most recent blocks.
+struct RangeTerminalReporter {
+    include_votes: bool,
+    rolling_window: RollingWindow,
+    hot_accounts: HotAccountPersistence,
+}
 
-            violating_transaction_signatures.push((violating_signatures, signature));
-        }
+impl Reporter for RangeTerminalReporter {
+    fn report(&mut self, analysis: &BlockAnalysis, _leader: Option<Pubkey>) {
+        let slot = analysis.slot;
+        let disorder = analysis.ordering_disorder();
+        say!(
+            "Slot {}: {}, {} violations ({:.2}%, {:.3} per contended account), fill {:.1}%, \
+             inversion ratio {:.4}, mean displacement {:.4}",
+            slot,
+            describe_transactions(
+                analysis.analyzed_transaction_count(),
+                analysis.vote_transaction_count(),
+                self.include_votes,
+            ),
+            analysis.violating_transaction_signatures.len(),
+            analysis.violation_rate() * 100.0,
+            analysis.normalized_rates().per_contended_account,
+            analysis.fill() * 100.0,
+            disorder.inversion_ratio,
+            disorder.mean_displacement,
+        );
+
+        self.rolling_window.push(SlotSummary::new(analysis));
+        say!("  {}", self.rolling_window);
+        self.hot_accounts.add(analysis);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn scan_range(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    rpc_options: &RpcOptions,
+    config: &AnalysisConfig,
+    range: &RangeArgs,
+    window: usize,
+    hot_accounts: usize,
+    min_streak: usize,
+    details: Option<BlockTerminalReporter>,
+) {
+    let mut reporter = RangeTerminalReporter {
+        include_votes: config.include_votes,
+        rolling_window: RollingWindow::new(window.max(1)),
+        hot_accounts: HotAccountPersistence::new(hot_accounts),
+    };
+    let mut reporters = FanOut::default();
+    reporters.push(&mut reporter);
+    if let Some(details) = details {
+        reporters.push(details);
+    }
+    let changed = for_each_block(
+        client,
+        batch_client,
+        cache,
+        rpc_options,
+        config,
+        range,
+        reporters,
+    );
+    print_persistent_hot_accounts(&reporter.hot_accounts, min_streak);
+    if changed {
+        exit(1);
+    }
+}
+
+fn print_persistent_hot_accounts(hot_accounts: &HotAccountPersistence, min_streak: usize) {
+    let persistent = hot_accounts.persistent(min_streak);
+    if persistent.is_empty() {
+        say!(
+            "No accounts stayed hot for {} consecutive blocks",
+            min_streak
+        );
+        return;
     }
+    say!(
+        "{} accounts stayed hot for at least {} consecutive blocks:",
+        persistent.len(),
+        min_streak
+    );
+    for hot_account in persistent {
+        say!(
+            "  {}: {} consecutive blocks from slot {}, hot in {} blocks, clearing price median \
+             {} max {}",
+            hot_account.account,
+            hot_account.longest_streak,
+            hot_account.longest_streak_start,
+            hot_account.block_count,
+            display_priority(hot_account.median_clearing_price()),
+            display_priority(hot_account.max_clearing_price()),
+        );
+    }
+}
 
-    if display_count_only {
-        println!("{}", violating_transaction_signatures.len());
+/// Analyzes the range's blocks missing from the records file on `workers` threads, appending
+/// their records in slot order, with a progress bar overall and per worker.
+fn backfill_records(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    rpc_options: &RpcOptions,
+    config: &AnalysisConfig,
+    backfill: &BackfillArgs,
+) {
+    let range = backfill.start..=backfill.end;
+    if backfill.force && backfill.db.exists() {
+        let kept: Vec<_> = read_records(&backfill.db)
+            .into_iter()
+            .filter(|record| !range.contains(&record.slot))
+            .collect();
+        let mut records = NdjsonReporter::new(&backfill.db);
+        for record in &kept {
+            records.write_record(record);
+        }
+        records.finish();
+    }
+    let present = if backfill.db.exists() {
+        recorded_slots(&backfill.db)
+    } else {
+        HashSet::new()
+    };
+    let all_slots = fetch_block_slots(client, backfill.start, backfill.end);
+    let slots: Vec<_> = all_slots
+        .iter()
+        .filter(|slot| !present.contains(slot))
+        .copied()
+        .collect();
+    say!(
+        "{} blocks in range, {} already in {}",
+        all_slots.len(),
+        all_slots.len() - slots.len(),
+        backfill.db.display()
+    );
+    if slots.is_empty() {
         return;
     }
+    let leaders = fetch_slot_leaders(batch_client, backfill.start, backfill.end);
+    let mut records = NdjsonReporter::append(&backfill.db);
 
-    if violated_accounts.is_empty() {
-        println!("No priority violations found");
+    let progress = if quiet() {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
     } else {
-        println!(
-            "{} priority violations found on {} accounts:",
-            violating_transaction_signatures.len(),
-            violated_accounts.len()
-        );
-        for (account, violations) in violated_accounts {
-            println!("Account: {}", account);
-            for violation in violations {
-                println!("  {} -> {}", violation[0], violation[1]);
+        MultiProgress::new()
+    };
+    let overall = progress.add(ProgressBar::new(slots.len() as u64));
+    overall.set_style(
+        ProgressStyle::with_template(
+            "{wide_bar} {pos}/{len} blocks, {per_sec}, elapsed {elapsed}, ETA {eta}",
+        )
+        .expect("valid progress template"),
+    );
+    let worker_style =
+        ProgressStyle::with_template("  worker {prefix}: {pos} blocks, {per_sec} {msg}")
+            .expect("valid progress template");
+
+    shutdown::install();
+    let mut profiler = Profiler::new(config.profile);
+    let window = ReorderWindow::new(backfill.reorder_window);
+    let mut pending = ReorderBuffer::default();
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for worker in 0..backfill.workers.max(1) {
+            let bar = progress.add(ProgressBar::new_spinner().with_style(worker_style.clone()));
+            bar.set_prefix(worker.to_string());
+            let (slots, next, window, sender) = (&slots, &next, &window, sender.clone());
+            scope.spawn(move || {
+                let connector = RpcConnector::new(rpc_options);
+                let client = connector.connect(MAINNET_URL);
+                let batch_client = connector.connect_batch(MAINNET_URL);
+                let mut analyzer = Analyzer::default();
+                while !shutdown::requested() {
+                    let position = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&slot) = slots.get(position) else {
+                        break;
+                    };
+                    window.wait_for(position);
+                    bar.set_message(format!("slot {}", slot));
+                    let analysis = fetch_and_analyze(
+                        &client,
+                        &batch_client,
+                        cache,
+                        &mut analyzer,
+                        slot,
+                        config,
+                    );
+                    if sender.send((position, analysis)).is_err() {
+                        break;
+                    }
+                    bar.inc(1);
+                }
+                bar.finish_with_message("done");
+            });
+        }
+        drop(sender);
+        for (position, analysis) in receiver {
+            pending.insert(position, analysis);
+            while let Some(analysis) = pending.pop() {
+                let report_start = profiler.start();
+                records.report(&analysis, leaders.get(&analysis.slot).copied());
+                let report = elapsed(report_start);
+                progress.suspend(|| profiler.record(&analysis, report));
+                overall.inc(1);
+                window.advance(pending.released());
             }
         }
-        println!("Violating transactions:");
-        for (previous_signatures, signature) in violating_transaction_signatures {
-            println!("{:?} -> {}", previous_signatures, signature);
+    });
+    records.finish();
+    overall.finish();
+    profiler.finish();
+    say!(
+        "Wrote {} of {} missing blocks to {}",
+        overall.position(),
+        slots.len(),
+        backfill.db.display()
+    );
+}
+
+/// A cluster followed by the watch subcommand. Each has its own connections, since pooled batch
+/// connections are bound to the runtime that opened them, and its own block cache.
+struct WatchedCluster {
+    name: Option<String>,
+    client: RpcClient,
+    batch_client: BatchClient,
+    cache: BlockCache,
+}
+
+impl WatchedCluster {
+    fn new(name: Option<&str>, url: &str, rpc_options: &RpcOptions, cache: &BlockCache) -> Self {
+        let connector = RpcConnector::new(rpc_options);
+        Self {
+            name: name.map(str::to_string),
+            client: connector.connect(url),
+            batch_client: connector.connect_batch(url),
+            cache: name.map_or_else(|| cache.clone(), |name| cache.scoped(name)),
         }
     }
+
+    /// Prefix for every line of output about this cluster.
+    fn tag(&self) -> String {
+        self.name
+            .as_ref()
+            .map(|name| format!("[{}] ", name))
+            .unwrap_or_default()
+    }
 }
 
-fn parse_pubkey(s: impl AsRef<str>) -> Pubkey {
-    Pubkey::from_str(s.as_ref()).unwrap_or_else(|err| {
-        eprintln!("Failed to parse pubkey {}: {}", s.as_ref(), err);
+/// The clusters from the config file selected with `--cluster`, all of them by default, or
+/// mainnet if none are configured.
+fn watched_clusters(
+    configured: &BTreeMap<String, ClusterConfig>,
+    selected: &[String],
+    rpc_options: &RpcOptions,
+    cache: &BlockCache,
+) -> Vec<WatchedCluster> {
+    if configured.is_empty() {
+        if !selected.is_empty() {
+            eprintln!("No clusters are configured, add them under [clusters] in the config file");
+            exit(1);
+        }
+        return vec![WatchedCluster::new(None, MAINNET_URL, rpc_options, cache)];
+    }
+    for name in selected {
+        if !configured.contains_key(name) {
+            eprintln!("Cluster {} is not configured", name);
+            exit(1);
+        }
+    }
+    configured
+        .iter()
+        .filter(|(name, _)| selected.is_empty() || selected.contains(name))
+        .map(|(name, cluster)| WatchedCluster::new(Some(name), &cluster.url, rpc_options, cache))
+        .collect()
+}
+
+/// Follows every cluster from its own thread until SIGINT or SIGTERM.
+fn watch_clusters(
+    clusters: &[WatchedCluster],
+    config: &AnalysisConfig,
+    watch: &WatchArgs,
+    rules: &[AlertRule],
+) {
+    if watch.db.is_some() && clusters.len() > 1 {
+        eprintln!("--db only supports watching a single cluster, select one with --cluster");
         exit(1);
-    })
+    }
+    let mut config = config.clone();
+    if let Some(path) = &watch.watchlist {
+        config.accounts.extend(read_accounts(path));
+        say!(
+            "Watching {} accounts from {}",
+            config.accounts.len(),
+            path.display()
+        );
+    }
+    for rule in rules {
+        say!("Alerting on {}", rule);
+    }
+
+    let poll_interval = Duration::from_secs(watch.poll_interval);
+    let checks: Vec<_> = clusters
+        .iter()
+        .map(|cluster| {
+            let health = Health::new(
+                (poll_interval * 10).max(Duration::from_secs(60)),
+                watch.max_lag,
+            );
+            (cluster.name.clone().unwrap_or_default(), health)
+        })
+        .collect();
+    if let Some(address) = &watch.health_listen {
+        serve_health(address, checks.clone());
+    }
+
+    shutdown::install();
+    let config = &config;
+    thread::scope(|scope| {
+        for (cluster, (_, health)) in clusters.iter().zip(&checks) {
+            scope.spawn(move || watch_tip(cluster, config, watch, rules, health));
+        }
+    });
 }
 
-fn get_priority(transaction: &SanitizedVersionedTransaction) -> u64 {
-    for (program_id, ix) in transaction.get_message().program_instructions_iter() {
-        if compute_budget::check_id(program_id) {
-            match try_from_slice_unchecked(&ix.data) {
-                Ok(ComputeBudgetInstruction::RequestUnitsDeprecated {
-                    units,
-                    additional_fee,
-                }) => {
-                    const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
-                    return (additional_fee as u128)
-                        .saturating_mul(MICRO_LAMPORTS_PER_LAMPORT)
-                        .checked_div(units as u128)
-                        .unwrap_or_else(|| {
-                            eprintln!("Failed to calculate priority");
-                            exit(1);
-                        })
-                        .try_into()
-                        .unwrap_or_else(|err| {
-                            eprintln!("Failed to calculate priority: {err}");
-                            exit(1);
-                        });
+/// Polls `cluster` for new confirmed blocks from the current tip onward, printing a line per
+/// block and an alert whenever one of `rules` holds. Runs until SIGINT or SIGTERM.
+fn watch_tip(
+    cluster: &WatchedCluster,
+    config: &AnalysisConfig,
+    watch: &WatchArgs,
+    rules: &[AlertRule],
+    health: &Health,
+) {
+    let WatchedCluster {
+        client,
+        batch_client,
+        cache,
+        ..
+    } = cluster;
+    let mut reporter = WatchReporter {
+        tag: cluster.tag(),
+        include_votes: config.include_votes,
+        rolling_window: RollingWindow::new(watch.window.max(1)),
+        alerts: AlertEvaluator::new(rules.to_vec()),
+        sort: watch.sort,
+    };
+    let mut records = watch.db.as_deref().map(NdjsonReporter::append);
+    // Records of the blocks analyzed at confirmed commitment, in slot order, awaiting
+    // finalization to be analyzed again.
+    let mut unfinalized = VecDeque::new();
+    let mut analyzer = Analyzer::default();
+    let mut profiler = Profiler::new(config.profile).tagged(cluster.tag());
+    let poll_interval = Duration::from_secs(watch.poll_interval);
+
+    let mut next_slot = fetch_tip_slot(client).unwrap_or_else(|| exit(1));
+    health.record_poll(Some(next_slot));
+    health.record_processed(next_slot.saturating_sub(1));
+    // A failed fetch is retried from the same slot on the next poll rather than skipped.
+    let tag = cluster.tag();
+    let fetch_failed = |slot: Slot, err: String| {
+        health.record_poll(None);
+        eprintln!(
+            "{}Warning: retrying from slot {} on the next poll. {}",
+            tag, slot, err
+        );
+    };
+    'poll: while !shutdown::requested() {
+        let tip = fetch_tip_slot(client);
+        health.record_poll(tip);
+        if let Some(tip) = tip.filter(|tip| *tip >= next_slot) {
+            'blocks: {
+                let slots = match try_fetch_block_slots(client, next_slot, tip) {
+                    Ok(slots) => slots,
+                    Err(err) => {
+                        fetch_failed(next_slot, err);
+                        break 'blocks;
+                    }
+                };
+                let leaders = fetch_slot_leaders(batch_client, next_slot, tip);
+                for slot in slots {
+                    if shutdown::requested() {
+                        break 'poll;
+                    }
+                    let analysis = match try_fetch_and_analyze(
+                        client,
+                        batch_client,
+                        cache,
+                        &mut analyzer,
+                        slot,
+                        config,
+                    ) {
+                        Ok(analysis) => analysis,
+                        Err(err) => {
+                            fetch_failed(slot, err);
+                            break 'blocks;
+                        }
+                    };
+                    let leader = leaders.get(&slot).copied();
+                    let report_start = profiler.start();
+                    reporter.report(&analysis, leader);
+                    if records.is_some() || watch.reanalyze_finalized {
+                        let record = BlockRecord::new(&analysis, leader);
+                        if let Some(records) = &mut records {
+                            records.write_record(&record);
+                        }
+                        if watch.reanalyze_finalized {
+                            unfinalized.push_back((leader, record));
+                        }
+                    }
+                    profiler.record(&analysis, elapsed(report_start));
+                    health.record_processed(slot);
+                    next_slot = slot + 1;
                 }
-                Ok(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => {
-                    return price;
+                if shutdown::requested() {
+                    break 'poll;
+                }
+                health.record_processed(tip);
+                next_slot = tip + 1;
+            }
+        }
+        if watch.reanalyze_finalized {
+            reanalyze_finalized(
+                cluster,
+                &mut analyzer,
+                config,
+                &mut unfinalized,
+                records.as_mut(),
+            );
+        }
+        thread::sleep(poll_interval);
+    }
+    reporter.finish();
+    if let Some(records) = &mut records {
+        records.finish();
+    }
+    profiler.finish();
+    eprintln!("{}Stopped before slot {}", reporter.tag, next_slot);
+}
+
+/// Analyzes the blocks in `unfinalized` that have been finalized since their confirmed analysis
+/// again, from the finalized block, printing a correction for each whose results changed and
+/// recording the finalized analysis.
+fn reanalyze_finalized(
+    cluster: &WatchedCluster,
+    analyzer: &mut Analyzer,
+    config: &AnalysisConfig,
+    unfinalized: &mut VecDeque<(Option<Pubkey>, BlockRecord)>,
+    mut records: Option<&mut NdjsonReporter>,
+) {
+    let Some(finalized_slot) = fetch_finalized_slot(&cluster.client) else {
+        return;
+    };
+    let tag = cluster.tag();
+    while let Some((leader, confirmed)) =
+        unfinalized.pop_front_if(|(_, record)| record.slot <= finalized_slot)
+    {
+        let slot = confirmed.slot;
+        let Some(block) = fetch_finalized_block(&cluster.client, &cluster.batch_client, slot)
+        else {
+            say!(
+                "{}Correction for slot {}: the confirmed block was not finalized",
+                tag,
+                slot
+            );
+            continue;
+        };
+        let analysis = analyze(analyzer, slot, block, config);
+        let mut record = BlockRecord::new(&analysis, leader);
+        record.finalized = true;
+        let diff = RecordDiff::new(&confirmed, &record);
+        if !diff.is_empty() {
+            say!("{}Correction after finalization: {}", tag, diff);
+        }
+        if let Some(records) = &mut records {
+            records.write_record(&record);
+        }
+    }
+}
+
+/// Prints a line per block, followed by any alerts it raised and, if so, its violated accounts.
+struct WatchReporter {
+    tag: String,
+    include_votes: bool,
+    rolling_window: RollingWindow,
+    alerts: AlertEvaluator,
+    sort: AccountSort,
+}
+
+impl Reporter for WatchReporter {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        let violation_count = analysis.violating_transaction_signatures.len();
+        say!(
+            "{}Slot {}: {}, {} violations",
+            self.tag,
+            analysis.slot,
+            describe_transactions(
+                analysis.analyzed_transaction_count(),
+                analysis.vote_transaction_count(),
+                self.include_votes,
+            ),
+            violation_count,
+        );
+        self.rolling_window.push(SlotSummary::new(analysis));
+        say!("{}  {}", self.tag, self.rolling_window);
+        let events = self.alerts.evaluate(analysis, leader);
+        let fired = events
+            .iter()
+            .any(|event| matches!(event, AlertEvent::Fired(_)));
+        for event in events {
+            say!("{}{}", self.tag, event);
+        }
+        if !fired {
+            return;
+        }
+        for (account, violations) in analysis.sorted_violated_accounts(self.sort) {
+            say!("{}  {}: {} violations", self.tag, account, violations.len());
+        }
+    }
+}
+
+fn range_stats(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    cache: &BlockCache,
+    rpc_options: &RpcOptions,
+    config: &AnalysisConfig,
+    range: &RangeArgs,
+    save: Option<&Path>,
+) {
+    let mut stats = RangeStats::new();
+    let changed = for_each_block(
+        client,
+        batch_client,
+        cache,
+        rpc_options,
+        config,
+        range,
+        &mut stats,
+    );
+    if let Some(path) = save {
+        stats.save(path);
+    }
+    print_range_stats(client, &stats, config.include_votes);
+    if changed {
+        exit(1);
+    }
+}
+
+fn print_range_stats(client: &RpcClient, stats: &RangeStats, include_votes: bool) {
+    const GAP_PERCENTILES: [f64; 5] = [0.5, 0.75, 0.9, 0.99, 1.0];
+    const TOP_PROGRAMS: usize = 10;
+
+    let node_versions = fetch_node_versions(client);
+
+    say!("Blocks: {}", stats.block_count);
+    say!(
+        "Transactions: {}",
+        describe_transactions(
+            stats.transaction_count,
+            stats.vote_transaction_count,
+            include_votes,
+        )
+    );
+    say!(
+        "Violations: {} ({:.2}%)",
+        stats.violation_count,
+        stats.violation_rate() * 100.0
+    );
+    say!("Normalized violations: {}", stats.normalized_rates());
+    say!("Compute budget: {}", stats.compute_budget);
+    say!("Ties: {}", stats.ties);
+    say!("Tie breaking: {}", stats.tie_breaking);
+    say!(
+        "Zero-priority jumps: {} account violations where a zero-priority transaction landed \
+         first",
+        stats.zero_priority_jump_count
+    );
+    say!(
+        "Fees: {} ({} from prioritization), {} burned, leader revenue {} ({} from \
+         prioritization)",
+        format_sol(stats.fee_totals.total_fees),
+        format_sol(stats.fee_totals.priority_fees),
+        format_sol(stats.fee_totals.burned()),
+        format_sol(stats.fee_totals.leader_revenue()),
+        format_sol(stats.fee_totals.leader_priority_revenue())
+    );
+    say!("Account violations by type:");
+    for kind in ViolationKind::ALL {
+        say!(
+            "  {}: {}",
+            kind,
+            stats
+                .violations_by_kind
+                .get(&kind)
+                .copied()
+                .unwrap_or_default()
+        );
+    }
+    say!("Priority gaps:");
+    for (fraction, gap) in GAP_PERCENTILES
+        .iter()
+        .zip(stats.gap_percentiles(&GAP_PERCENTILES))
+    {
+        say!("  p{}: {}", fraction * 100.0, display_priority(gap));
+    }
+    say!("Priority gap histogram:");
+    let histogram = stats.gap_histogram();
+    let largest_bucket = histogram.values().copied().max().unwrap_or_default();
+    for (decade, count) in histogram {
+        const BAR_WIDTH: usize = 40;
+        say!(
+            "  [1e{}, 1e{}): {:>8} {}",
+            decade,
+            decade + 1,
+            count,
+            "#".repeat((count * BAR_WIDTH).div_ceil(largest_bucket))
+        );
+    }
+
+    let total_compute_units: u64 = stats
+        .programs
+        .values()
+        .map(|program_stats| program_stats.compute_units_consumed)
+        .sum();
+    say!("Compute by top-level program:");
+    for (program_id, program_stats) in stats.top_programs(TOP_PROGRAMS) {
+        say!(
+            "  {}: {} transactions, {} CUs ({:.1}%)",
+            program_id,
+            program_stats.transaction_count,
+            program_stats.compute_units_consumed,
+            ratio(program_stats.compute_units_consumed, total_compute_units) * 100.0
+        );
+    }
+
+    say!("Per leader:");
+    let mut leaders: Vec<_> = stats.leaders.iter().collect();
+    leaders.sort_by(|a, b| b.1.block_count.cmp(&a.1.block_count).then(a.0.cmp(b.0)));
+    for (leader, leader_stats) in leaders {
+        let (scheduler, confidence) = leader_stats.classify_scheduler();
+        say!(
+            "  {} ({}): {} blocks, {} transactions, {} violations ({}), {:.1}% monotonic, \
+             {:.1}% of violations cross-thread, scheduler {} ({:.0}% confidence), priority fee \
+             revenue {}",
+            leader,
+            node_versions
+                .get(leader)
+                .map(String::as_str)
+                .unwrap_or("unknown version"),
+            leader_stats.block_count,
+            leader_stats.transaction_count,
+            leader_stats.violation_count,
+            leader_stats.normalized_rates(),
+            leader_stats.priority_monotonicity() * 100.0,
+            leader_stats.cross_thread_share().unwrap_or_default() * 100.0,
+            scheduler,
+            confidence * 100.0,
+            format_sol(leader_stats.fee_totals.leader_priority_revenue()),
+        );
+        if leader_stats.tie_breaking.pair_count > 0 {
+            say!("    Tie breaking: {}", leader_stats.tie_breaking);
+        }
+    }
+}
+
+fn print_schema() {
+    let schema = schemars::schema_for!(BlockRecord);
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema).expect("schema serializes")
+    );
+}
+
+fn run_query(client: &RpcClient, db: &Path, query: Query) {
+    let records = read_records(db);
+    match query {
+        Query::WorstLeaders {
+            epoch,
+            min_blocks,
+            limit,
+        } => {
+            let epoch_schedule = fetch_epoch_schedule(client);
+            let Some(epoch) = epoch.or_else(|| {
+                let slot = records.iter().map(|record| record.slot).max()?;
+                Some(epoch_schedule.get_epoch(slot))
+            }) else {
+                say!("No records in {}", db.display());
+                return;
+            };
+            let slots = epoch_schedule.get_first_slot_in_epoch(epoch)
+                ..=epoch_schedule.get_last_slot_in_epoch(epoch);
+            let leaders = worst_leaders(&records, slots, min_blocks);
+            if leaders.is_empty() {
+                say!(
+                    "No leaders with at least {} recorded blocks in epoch {}",
+                    min_blocks,
+                    epoch
+                );
+                return;
+            }
+            say!("Worst leaders in epoch {}:", epoch);
+            for (leader, totals) in leaders.into_iter().take(limit) {
+                say!(
+                    "  {}: {:.2}% violation rate, {} violations in {} transactions over {} blocks",
+                    leader,
+                    totals.violation_rate() * 100.0,
+                    totals.violation_count,
+                    totals.transaction_count,
+                    totals.block_count
+                );
+            }
+        }
+        Query::Account { account } => {
+            let violations = account_violations(&records, &account.to_string());
+            if violations.is_empty() {
+                say!("No recorded violations on {}", account);
+                return;
+            }
+            say!("{} recorded violations on {}:", violations.len(), account);
+            for (record, violation) in violations {
+                say!(
+                    "  slot {} (leader {}): {} #{} ({}) ahead of #{} ({})",
+                    record.slot,
+                    record.leader.as_deref().unwrap_or("unknown"),
+                    violation.kind,
+                    violation.previous_index,
+                    display_priority(violation.previous_priority),
+                    violation.index,
+                    display_priority(violation.priority)
+                );
+            }
+        }
+        Query::Trend => {
+            let (days, untimed_count) = daily_trend(&records);
+            if untimed_count > 0 {
+                eprintln!(
+                    "Warning: left out {} records without a block time, written by an older \
+                     version",
+                    untimed_count
+                );
+            }
+            say!("Violation rate by day (UTC):");
+            for (day, totals) in days {
+                say!(
+                    "  {}: {:.2}%, {} violations in {} transactions over {} blocks",
+                    day,
+                    totals.violation_rate() * 100.0,
+                    totals.violation_count,
+                    totals.transaction_count,
+                    totals.block_count
+                );
+            }
+        }
+    }
+}
+
+fn diff_records(old_path: &Path, new_path: &Path) {
+    let old_records: BTreeMap<_, _> = read_records(old_path)
+        .into_iter()
+        .map(|record| (record.slot, record))
+        .collect();
+    let new_records: BTreeMap<_, _> = read_records(new_path)
+        .into_iter()
+        .map(|record| (record.slot, record))
+        .collect();
+
+    let mut changed_count = 0;
+    let slots: BTreeSet<_> = old_records.keys().chain(new_records.keys()).collect();
+    for slot in slots {
+        match (old_records.get(slot), new_records.get(slot)) {
+            (Some(old), Some(new)) => {
+                let diff = RecordDiff::new(old, new);
+                if !diff.is_empty() {
+                    changed_count += 1;
+                    say!("{}", diff);
                 }
-                _ => {}
             }
+            (Some(_), None) => {
+                changed_count += 1;
+                say!("Slot {}: only in {}", slot, old_path.display());
+            }
+            (None, Some(_)) => {
+                changed_count += 1;
+                say!("Slot {}: only in {}", slot, new_path.display());
+            }
+            (None, None) => unreachable!(),
         }
     }
 
-    0
+    let totals = |records: &BTreeMap<_, BlockRecord>| {
+        let transaction_count: usize = records
+            .values()
+            .map(|record| record.transaction_count)
+            .sum();
+        let violation_count: usize = records.values().map(|record| record.violation_count).sum();
+        let rate = violation_count as f64 / transaction_count.max(1) as f64;
+        (records.len(), transaction_count, violation_count, rate)
+    };
+    let (old_blocks, old_transactions, old_violations, old_rate) = totals(&old_records);
+    let (new_blocks, new_transactions, new_violations, new_rate) = totals(&new_records);
+    say!("Blocks: {} -> {}", old_blocks, new_blocks);
+    say!("Transactions: {} -> {}", old_transactions, new_transactions);
+    say!(
+        "Violations: {} ({:.2}%) -> {} ({:.2}%)",
+        old_violations,
+        old_rate * 100.0,
+        new_violations,
+        new_rate * 100.0
+    );
+    say!("{} slots differ", changed_count);
+    if changed_count > 0 {
+        exit(1);
+    }
 }
 
-#[derive(PartialEq, Eq)]
-enum LastAccess {
-    Read,
-    Write,
+fn describe_transactions(analyzed_count: usize, vote_count: usize, include_votes: bool) -> String {
+    if include_votes {
+        format!("{} transactions ({} votes)", analyzed_count, vote_count)
+    } else {
+        format!(
+            "{} non-vote transactions ({} votes excluded)",
+            analyzed_count, vote_count
+        )
+    }
+}
+
+/// Slots with a record in a file written with --ndjson.
+fn recorded_slots(path: &Path) -> HashSet<Slot> {
+    read_records(path)
+        .into_iter()
+        .map(|record| record.slot)
+        .collect()
+}
+
+fn read_accounts(path: &Path) -> Vec<Pubkey> {
+    let contents = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Failed to read {}: {}", path.display(), err);
+        exit(1);
+    });
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            Pubkey::from_str(line).unwrap_or_else(|err| {
+                eprintln!("Failed to parse account {}: {}", line, err);
+                exit(1);
+            })
+        })
+        .collect()
 }
 
-struct LastAccessPriority {
-    last_access: LastAccess,
-    priority: u64,
-    signature: Signature,
+fn read_signatures(path: &Path) -> HashSet<Signature> {
+    let contents = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Failed to read {}: {}", path.display(), err);
+        exit(1);
+    });
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            Signature::from_str(line).unwrap_or_else(|err| {
+                eprintln!("Failed to parse signature {}: {}", line, err);
+                exit(1);
+            })
+        })
+        .collect()
 }
@@ -0,0 +1,245 @@
+use {
+    crate::analysis::{ratio, BlockAnalysis},
+    solana_sdk::pubkey::Pubkey,
+};
+
+/// How far a priority sequence is from being ordered by descending priority.
+///
+/// The inversion ratio alone cannot tell pervasive small-scale shuffling from a handful of
+/// transactions that jumped far out of place; the displacement figures can.
+pub struct OrderingDisorder {
+    /// Fraction of transaction pairs where the later transaction has strictly higher priority.
+    pub inversion_ratio: f64,
+    /// Mean distance, as a fraction of the sequence length, between each transaction's position
+    /// and its position in a priority-sorted ordering.
+    pub mean_displacement: f64,
+    /// Largest such distance, in positions.
+    pub max_displacement: usize,
+}
+
+impl OrderingDisorder {
+    pub fn new(priorities: &[u64]) -> Self {
+        let length = priorities.len();
+        let pairs = (length as u64) * (length.saturating_sub(1) as u64) / 2;
+        let inversions = count_inversions(&mut priorities.to_vec());
+
+        // Stable sort so equal priorities keep their block order and are not displaced.
+        let mut sorted_positions: Vec<_> = (0..length).collect();
+        sorted_positions.sort_by(|a, b| priorities[*b].cmp(&priorities[*a]));
+        let displacements = sorted_positions
+            .iter()
+            .enumerate()
+            .map(|(rank, position)| rank.abs_diff(*position));
+        let (total_displacement, max_displacement) = displacements
+            .fold((0, 0), |(total, max), displacement| {
+                (total + displacement as u64, max.max(displacement))
+            });
+
+        Self {
+            inversion_ratio: ratio(inversions, pairs),
+            mean_displacement: ratio(total_displacement, length as u64) / length.max(1) as f64,
+            max_displacement,
+        }
+    }
+}
+
+/// Counts pairs `i < j` with `priorities[i] < priorities[j]`, sorting the slice descending.
+fn count_inversions(priorities: &mut [u64]) -> u64 {
+    let length = priorities.len();
+    if length < 2 {
+        return 0;
+    }
+
+    let (left, right) = priorities.split_at_mut(length / 2);
+    let mut inversions = count_inversions(left) + count_inversions(right);
+
+    let mut merged = Vec::with_capacity(length);
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] >= right[j] {
+            merged.push(left[i]);
+            i += 1;
+        } else {
+            // Every remaining element on the left precedes and is lower than right[j].
+            inversions += (left.len() - i) as u64;
+            merged.push(right[j]);
+            j += 1;
+        }
+    }
+    merged.extend_from_slice(&left[i..]);
+    merged.extend_from_slice(&right[j..]);
+    priorities.copy_from_slice(&merged);
+
+    inversions
+}
+
+/// How closely the order of the writes to one contended account followed priority.
+pub struct AccountRankCorrelation {
+    pub account: Pubkey,
+    pub write_count: usize,
+    /// Spearman correlation between block position and descending priority: 1 when the writes
+    /// landed highest priority first, -1 when lowest first, and near 0 when their order had
+    /// nothing to do with priority.
+    pub correlation: f64,
+}
+
+impl AccountRankCorrelation {
+    /// Whether the order looks unrelated to priority, suggesting something other than fees,
+    /// such as arrival or a side deal, decided which writes landed first.
+    pub fn is_unrelated(&self) -> bool {
+        const MAX_UNRELATED_CORRELATION: f64 = 0.2;
+
+        self.correlation.abs() < MAX_UNRELATED_CORRELATION
+    }
+}
+
+/// Rank correlation of every account write-locked by at least `min_writes` analyzed
+/// transactions, least correlated first. Accounts whose writes all had the same priority are
+/// left out, as their order says nothing either way.
+pub fn account_rank_correlations(
+    analysis: &BlockAnalysis,
+    min_writes: usize,
+) -> Vec<AccountRankCorrelation> {
+    let mut correlations: Vec<_> = analysis
+        .write_lock_counts
+        .iter()
+        .filter(|(_, count)| **count >= min_writes.max(2))
+        .filter_map(|(account, count)| {
+            let priorities = analysis.write_lock_priorities(account);
+            Some(AccountRankCorrelation {
+                account: *account,
+                write_count: *count,
+                correlation: priority_rank_correlation(&priorities)?,
+            })
+        })
+        .collect();
+    correlations.sort_by(|a, b| {
+        a.correlation
+            .total_cmp(&b.correlation)
+            .then(a.account.cmp(&b.account))
+    });
+    correlations
+}
+
+/// Spearman correlation between the positions in `priorities` and descending priority, with
+/// tied priorities sharing their average rank. `None` if every priority is the same.
+pub fn priority_rank_correlation(priorities: &[u64]) -> Option<f64> {
+    let length = priorities.len();
+    let mut order: Vec<_> = (0..length).collect();
+    order.sort_by(|a, b| priorities[*b].cmp(&priorities[*a]));
+    let mut ranks = vec![0.0; length];
+    let mut start = 0;
+    while start < length {
+        let end = start
+            + order[start..]
+                .iter()
+                .take_while(|position| priorities[**position] == priorities[order[start]])
+                .count();
+        let average_rank = (start + end - 1) as f64 / 2.0;
+        for position in &order[start..end] {
+            ranks[*position] = average_rank;
+        }
+        start = end;
+    }
+
+    let mean = (length as f64 - 1.0) / 2.0;
+    let (mut covariance, mut position_variance, mut rank_variance) = (0.0, 0.0, 0.0);
+    for (position, rank) in ranks.iter().enumerate() {
+        let position = position as f64 - mean;
+        let rank = rank - mean;
+        covariance += position * rank;
+        position_variance += position * position;
+        rank_variance += rank * rank;
+    }
+    if rank_variance == 0.0 {
+        return None;
+    }
+    Some(covariance / (position_variance * rank_variance).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_inversions(priorities: &[u64]) -> u64 {
+        let mut inversions = 0;
+        for (i, earlier) in priorities.iter().enumerate() {
+            for later in &priorities[i + 1..] {
+                inversions += u64::from(earlier < later);
+            }
+        }
+        inversions
+    }
+
+    #[test]
+    fn count_inversions_cases() {
+        let cases: [(&[u64], u64); 7] = [
+            (&[], 0),
+            (&[7], 0),
+            (&[5, 4, 3, 2, 1], 0),
+            (&[1, 2, 3, 4, 5], 10),
+            (&[3, 3, 3], 0),
+            (&[2, 2, 1, 1, 3], 4),
+            (&[1, 3, 2, 3], 4),
+        ];
+        for (priorities, expected) in cases {
+            let mut sorted = priorities.to_vec();
+            assert_eq!(count_inversions(&mut sorted), expected, "{:?}", priorities);
+            assert!(
+                sorted.windows(2).all(|pair| pair[0] >= pair[1]),
+                "{:?} left unsorted as {:?}",
+                priorities,
+                sorted
+            );
+        }
+    }
+
+    #[test]
+    fn count_inversions_matches_brute_force() {
+        let priorities: Vec<u64> = (0..257).map(|i| i * 7919 % 61).collect();
+        let expected = brute_force_inversions(&priorities);
+        assert_eq!(count_inversions(&mut priorities.clone()), expected);
+    }
+
+    #[test]
+    fn priority_rank_correlation_cases() {
+        let assert_close = |priorities: &[u64], expected: f64| {
+            let correlation = priority_rank_correlation(priorities).unwrap();
+            assert!(
+                (correlation - expected).abs() < 1e-9,
+                "{:?}: {} != {}",
+                priorities,
+                correlation,
+                expected
+            );
+        };
+        assert_close(&[5, 4, 3, 2, 1], 1.0);
+        assert_close(&[1, 2, 3, 4, 5], -1.0);
+        // The tied pair shares rank 0.5.
+        assert_close(&[3, 3, 1], 1.5 / 3f64.sqrt());
+        assert_close(&[1, 3, 3], -1.5 / 3f64.sqrt());
+
+        assert_eq!(priority_rank_correlation(&[]), None);
+        assert_eq!(priority_rank_correlation(&[4]), None);
+        assert_eq!(priority_rank_correlation(&[2, 2, 2]), None);
+    }
+
+    #[test]
+    fn ordering_disorder_cases() {
+        let sorted = OrderingDisorder::new(&[5, 4, 4, 1]);
+        assert_eq!(sorted.inversion_ratio, 0.0);
+        assert_eq!(sorted.mean_displacement, 0.0);
+        assert_eq!(sorted.max_displacement, 0);
+
+        let reversed = OrderingDisorder::new(&[1, 2, 3, 4]);
+        assert_eq!(reversed.inversion_ratio, 1.0);
+        assert_eq!(reversed.max_displacement, 3);
+        // Displacements 3, 1, 1, 3 over 4 positions, as a fraction of the length.
+        assert_eq!(reversed.mean_displacement, 0.5);
+
+        let empty = OrderingDisorder::new(&[]);
+        assert_eq!(empty.inversion_ratio, 0.0);
+        assert_eq!(empty.mean_displacement, 0.0);
+        assert_eq!(empty.max_displacement, 0);
+    }
+}
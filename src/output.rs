@@ -0,0 +1,273 @@
+use {
+    serde::de::IgnoredAny,
+    std::{
+        ffi::OsString,
+        fs::{self, File, OpenOptions},
+        io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+        process::exit,
+        sync::atomic::{AtomicBool, Ordering},
+    },
+};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Suppresses the human-readable output printed with [`say!`](crate::say), for runs that only
+/// rely on the exit code and structured outputs. Warnings and errors still go to stderr.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether human-readable output is suppressed.
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// `println!` for human-readable output, which `--quiet` suppresses.
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !$crate::output::quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// A report written under a temporary name next to `path` and renamed into place by
+/// [`OutputFile::commit`], so scripts never see it half-written, or stdout if `path` is `-`.
+/// Missing parent directories are created.
+pub struct OutputFile {
+    writer: BufWriter<Box<dyn Write>>,
+    /// Temporary and final paths, or `None` for stdout.
+    paths: Option<(PathBuf, PathBuf)>,
+}
+
+impl OutputFile {
+    pub fn create(path: &Path) -> Self {
+        if path == Path::new("-") {
+            return Self {
+                writer: BufWriter::new(Box::new(io::stdout())),
+                paths: None,
+            };
+        }
+        let temporary_path = temporary_path(path);
+        let file = create_parent_dir(path)
+            .and_then(|()| File::create(&temporary_path))
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to create {}: {}", path.display(), err);
+                exit(1);
+            });
+        Self {
+            writer: BufWriter::new(Box::new(file)),
+            paths: Some((temporary_path, path.to_path_buf())),
+        }
+    }
+
+    /// Appends to `path` in place, creating it if missing, for files of JSON lines that grow
+    /// across runs. A truncated last line, left by a run killed mid-write, is cut off first so
+    /// that appended lines stay intact.
+    pub fn append(path: &Path) -> Self {
+        if path == Path::new("-") {
+            return Self::create(path);
+        }
+        let file = create_parent_dir(path)
+            .and_then(|()| {
+                OpenOptions::new()
+                    .create(true)
+                    .read(true)
+                    .append(true)
+                    .open(path)
+            })
+            .and_then(|mut file| {
+                if truncate_partial_line(&mut file)? {
+                    eprintln!(
+                        "Warning: cut the truncated last line off {} before appending",
+                        path.display()
+                    );
+                }
+                Ok(file)
+            })
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to open {}: {}", path.display(), err);
+                exit(1);
+            });
+        Self {
+            writer: BufWriter::new(Box::new(file)),
+            paths: None,
+        }
+    }
+
+    /// Flushes the report and moves it into place.
+    pub fn commit(&mut self) {
+        let result = self.writer.flush().and_then(|()| match self.paths.take() {
+            Some((temporary_path, path)) => fs::rename(temporary_path, path),
+            None => Ok(()),
+        });
+        if let Err(err) = result {
+            eprintln!("Failed to write report: {}", err);
+            exit(1);
+        }
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Cuts a last line that is not valid JSON off `file`, returning whether there was one. A
+/// valid last line missing its newline gets one instead.
+fn truncate_partial_line(file: &mut File) -> io::Result<bool> {
+    const CHUNK_SIZE: u64 = 64 * 1024;
+
+    let len = file.metadata()?.len();
+    let mut end = len;
+    let mut chunk = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK_SIZE);
+        chunk.resize((end - start) as usize, 0);
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        if let Some(newline) = chunk.iter().rposition(|byte| *byte == b'\n') {
+            end = start + newline as u64 + 1;
+            break;
+        }
+        end = start;
+    }
+    if end == len {
+        return Ok(false);
+    }
+    let mut last_line = Vec::new();
+    file.seek(SeekFrom::Start(end))?;
+    file.read_to_end(&mut last_line)?;
+    if serde_json::from_slice::<IgnoredAny>(&last_line).is_ok() {
+        file.write_all(b"\n")?;
+        return Ok(false);
+    }
+    file.set_len(end)?;
+    Ok(true)
+}
+
+/// Writes `contents` to `path` the way [`OutputFile`] does, in one go.
+pub fn write_output(path: &Path, contents: &[u8]) {
+    let mut output = OutputFile::create(path);
+    output.write_all(contents).unwrap_or_else(|err| {
+        eprintln!("Failed to write {}: {}", path.display(), err);
+        exit(1);
+    });
+    output.commit();
+}
+
+/// Creates the directory `path` goes in, if missing.
+pub fn create_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{env, process},
+    };
+
+    /// Runs [`truncate_partial_line`] on a file holding `contents`, as [`OutputFile::append`]
+    /// opens it, returning whether it cut a line and what the file holds after.
+    fn truncate(name: &str, contents: &[u8]) -> (bool, Vec<u8>) {
+        let path = env::temp_dir().join(format!("priority-checker-{}-{}", process::id(), name));
+        fs::write(&path, contents).unwrap();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .unwrap();
+        let cut = truncate_partial_line(&mut file).unwrap();
+        drop(file);
+        let contents = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        (cut, contents)
+    }
+
+    #[test]
+    fn truncate_partial_line_cases() {
+        let cases: [(&str, &str, bool, &str); 7] = [
+            ("empty", "", false, ""),
+            (
+                "complete",
+                "{\"a\":1}\n{\"b\":2}\n",
+                false,
+                "{\"a\":1}\n{\"b\":2}\n",
+            ),
+            ("truncated", "{\"a\":1}\n{\"b\":", true, "{\"a\":1}\n"),
+            (
+                "unterminated",
+                "{\"a\":1}\n{\"b\":2}",
+                false,
+                "{\"a\":1}\n{\"b\":2}\n",
+            ),
+            ("only-truncated", "{\"a\":", true, ""),
+            ("only-unterminated", "{\"a\":1}", false, "{\"a\":1}\n"),
+            ("blank-tail", "{\"a\":1}\n  ", true, "{\"a\":1}\n"),
+        ];
+        for (name, contents, expected_cut, expected_contents) in cases {
+            let (cut, contents) = truncate(name, contents.as_bytes());
+            assert_eq!(cut, expected_cut, "{}", name);
+            assert_eq!(
+                String::from_utf8(contents).unwrap(),
+                expected_contents,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_partial_line_across_chunks() {
+        const CHUNK_SIZE: usize = 64 * 1024;
+        let line = |length: usize| format!("\"{}\"", "x".repeat(length - 2));
+        let complete = format!("{}\n", line(CHUNK_SIZE + 10));
+
+        // The truncated line spans more than a chunk, so the newline is found in the next one.
+        let truncated = format!("{}{}", complete, &line(2 * CHUNK_SIZE)[..CHUNK_SIZE + 5]);
+        assert_eq!(
+            truncate("long-truncated", truncated.as_bytes()),
+            (true, complete.clone().into_bytes())
+        );
+
+        // The truncated line fills exactly one chunk, leaving the newline as the last byte of
+        // the chunk before.
+        let truncated = format!("{}{}", complete, &line(2 * CHUNK_SIZE)[..CHUNK_SIZE]);
+        assert_eq!(
+            truncate("chunk-truncated", truncated.as_bytes()),
+            (true, complete.clone().into_bytes())
+        );
+
+        // A valid line over a chunk long only gets its newline.
+        let unterminated = format!("{}{}", complete, line(CHUNK_SIZE + 1));
+        assert_eq!(
+            truncate("long-unterminated", unterminated.as_bytes()),
+            (false, format!("{}\n", unterminated).into_bytes())
+        );
+
+        // No newline anywhere in several chunks.
+        let truncated = &line(3 * CHUNK_SIZE)[..3 * CHUNK_SIZE - 1];
+        assert_eq!(
+            truncate("no-newline", truncated.as_bytes()),
+            (true, Vec::new())
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use {clap::ValueEnum, solana_sdk::pubkey::Pubkey, std::str::FromStr};
+
+/// Pool and market accounts of the most traded SOL and stablecoin pairs, as of early 2024.
+const DEX_HOT: [(&str, &str); 8] = [
+    (
+        "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2",
+        "Raydium AMM v4 SOL-USDC",
+    ),
+    (
+        "6UmmUiYoBjSrhakAobJw8BvkmJtDVxaeBtbt7rxWo1mg",
+        "Raydium AMM v4 RAY-USDC",
+    ),
+    (
+        "2QdhepnKRTLjjSqPL1PtKNwqrUkoLee5Gqs8bvZhRdMv",
+        "Raydium CLMM SOL-USDC",
+    ),
+    (
+        "HJPjoWUrhoZzkNfRpHuieeFk9WcZWjwy6PBjZ81ngndJ",
+        "Orca Whirlpool SOL-USDC (64)",
+    ),
+    (
+        "7qbRF6YsyGuLUVs6Y1q64bdVrfe4ZcUUz1JRdoVNUJnm",
+        "Orca Whirlpool SOL-USDC (8)",
+    ),
+    (
+        "5rCf1DM8LjKTw4YqhnoLcngyZYeNnQqztScTogYHAS6",
+        "Meteora DLMM SOL-USDC",
+    ),
+    (
+        "4DoNfFBfF7UokCC2FQzriy7yHK6DY6NVdYpuekQ5pRgg",
+        "Phoenix SOL-USDC",
+    ),
+    (
+        "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6",
+        "OpenBook SOL-USDC",
+    ),
+];
+
+/// Curated account watchlists selectable from the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// Pools and order books of the busiest DEX markets.
+    DexHot,
+}
+
+impl Preset {
+    /// The preset's accounts with a short description of each.
+    pub fn entries(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Preset::DexHot => &DEX_HOT,
+        }
+    }
+
+    pub fn accounts(self) -> Vec<Pubkey> {
+        self.entries()
+            .iter()
+            .map(|(account, _)| Pubkey::from_str(account).expect("valid preset account"))
+            .collect()
+    }
+}
@@ -0,0 +1,177 @@
+use {
+    crate::{
+        budget::{get_compute_unit_limit, get_priority},
+        fees::{base_fee, BURN_PERCENT},
+    },
+    clap::ValueEnum,
+    solana_sdk::transaction::SanitizedVersionedTransaction,
+    solana_transaction_status::UiTransactionStatusMeta,
+    std::sync::{Arc, OnceLock},
+};
+
+static DISPLAY_UNIT: OnceLock<PriorityUnit> = OnceLock::new();
+
+/// Decides how transactions are ranked when checking whether conflicting transactions landed in
+/// priority order. Higher values are expected to be scheduled first.
+///
+/// Embedders can supply their own model through [`crate::analysis::AnalysisConfig`].
+pub trait PriorityModel: std::fmt::Debug + Send + Sync {
+    fn priority(
+        &self,
+        transaction: &SanitizedVersionedTransaction,
+        meta: &UiTransactionStatusMeta,
+    ) -> u64;
+}
+
+/// Compute-unit price requested through the compute-budget program, in micro-lamports.
+#[derive(Debug, Default)]
+pub struct CuPrice;
+
+impl PriorityModel for CuPrice {
+    fn priority(
+        &self,
+        transaction: &SanitizedVersionedTransaction,
+        _meta: &UiTransactionStatusMeta,
+    ) -> u64 {
+        get_priority(transaction)
+    }
+}
+
+/// Fee paid to the leader per requested compute unit, in micro-lamports, approximating how a
+/// revenue-maximizing scheduler ranks transactions.
+#[derive(Debug, Default)]
+pub struct RewardPerCu;
+
+impl PriorityModel for RewardPerCu {
+    fn priority(
+        &self,
+        transaction: &SanitizedVersionedTransaction,
+        meta: &UiTransactionStatusMeta,
+    ) -> u64 {
+        const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+        let reward = meta.fee - meta.fee * BURN_PERCENT / 100;
+        let compute_unit_limit = get_compute_unit_limit(transaction).max(1);
+        u64::try_from(
+            u128::from(reward) * MICRO_LAMPORTS_PER_LAMPORT / u128::from(compute_unit_limit),
+        )
+        .unwrap_or(u64::MAX)
+    }
+}
+
+/// Total fee paid, in lamports, regardless of compute requested.
+#[derive(Debug, Default)]
+pub struct FeeTotal;
+
+impl PriorityModel for FeeTotal {
+    fn priority(
+        &self,
+        _transaction: &SanitizedVersionedTransaction,
+        meta: &UiTransactionStatusMeta,
+    ) -> u64 {
+        meta.fee
+    }
+}
+
+/// Built-in priority models selectable from the command line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum BuiltinPriorityModel {
+    #[default]
+    CuPrice,
+    RewardPerCu,
+    FeeTotal,
+}
+
+impl BuiltinPriorityModel {
+    pub fn model(self) -> Arc<dyn PriorityModel> {
+        match self {
+            BuiltinPriorityModel::CuPrice => Arc::new(CuPrice),
+            BuiltinPriorityModel::RewardPerCu => Arc::new(RewardPerCu),
+            BuiltinPriorityModel::FeeTotal => Arc::new(FeeTotal),
+        }
+    }
+
+    /// Whether the model's priorities are prices per compute unit, in micro-lamports.
+    pub fn is_per_compute_unit(self) -> bool {
+        match self {
+            BuiltinPriorityModel::CuPrice | BuiltinPriorityModel::RewardPerCu => true,
+            BuiltinPriorityModel::FeeTotal => false,
+        }
+    }
+}
+
+/// How non-vote transactions that set no compute unit price are ranked, overriding the priority
+/// model, since the right choice differs per study.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Unprioritized {
+    /// At priority 0.
+    Zero,
+    /// Left out of conflict checks and statistics, like transactions outside the program
+    /// filter.
+    Exclude,
+    /// At the base fee per requested compute unit, in micro-lamports, as if the base fee were
+    /// their bid. Only meaningful for per-compute-unit priority models.
+    BaseFee,
+}
+
+/// Priority imputed to a transaction that sets no compute unit price: its base fee per requested
+/// compute unit, in micro-lamports.
+pub fn base_fee_priority(transaction: &SanitizedVersionedTransaction) -> u64 {
+    const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+    let compute_unit_limit = get_compute_unit_limit(transaction).max(1);
+    u64::try_from(
+        u128::from(base_fee(transaction)) * MICRO_LAMPORTS_PER_LAMPORT
+            / u128::from(compute_unit_limit),
+    )
+    .unwrap_or(u64::MAX)
+}
+
+/// Units per-compute-unit priorities are printed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum PriorityUnit {
+    #[default]
+    MicroLamports,
+    LamportsPerCu,
+    SolPerMillionCu,
+}
+
+impl PriorityUnit {
+    /// `micro_lamports_per_cu` in this unit, exactly, without trailing zeros.
+    pub fn format(self, micro_lamports_per_cu: u64) -> String {
+        match self {
+            PriorityUnit::MicroLamports => micro_lamports_per_cu.to_string(),
+            // A million micro-lamports make a lamport.
+            PriorityUnit::LamportsPerCu => decimal(micro_lamports_per_cu, 6),
+            // A micro-lamport per CU is a lamport per million CUs, and a billion lamports a SOL.
+            PriorityUnit::SolPerMillionCu => decimal(micro_lamports_per_cu, 9),
+        }
+    }
+}
+
+/// Sets the unit [`display_priority`] prints priorities in. Only meaningful for models whose
+/// priorities are prices per compute unit; others are always printed as computed.
+pub fn set_display_unit(unit: PriorityUnit) {
+    let _ = DISPLAY_UNIT.set(unit);
+}
+
+/// `priority` formatted for human-readable output, in the unit set with [`set_display_unit`].
+pub fn display_priority(priority: u64) -> String {
+    DISPLAY_UNIT
+        .get()
+        .copied()
+        .unwrap_or_default()
+        .format(priority)
+}
+
+/// `value` divided by 10 to the `places`, written out in decimal.
+fn decimal(value: u64, places: u32) -> String {
+    let scale = 10u64.pow(places);
+    let fraction = format!("{:0width$}", value % scale, width = places as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (value / scale).to_string()
+    } else {
+        format!("{}.{}", value / scale, fraction)
+    }
+}
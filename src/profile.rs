@@ -0,0 +1,119 @@
+use {
+    crate::analysis::{AnalysisTimings, BlockAnalysis},
+    std::{
+        fmt,
+        time::{Duration, Instant},
+    },
+};
+
+/// Wall time spent on one slot, by phase.
+#[derive(Clone, Copy, Debug, Default)]
+struct SlotProfile {
+    /// Fetching the block from the cache or RPC.
+    fetch: Duration,
+    /// Phases of the analysis itself.
+    analysis: AnalysisTimings,
+    /// Handing the analysis to reporters or printing it.
+    report: Duration,
+}
+
+impl SlotProfile {
+    /// Time spent on the CPU rather than waiting on RPC.
+    fn cpu(&self) -> Duration {
+        self.analysis.total + self.report
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.fetch += other.fetch;
+        self.analysis.decode += other.analysis.decode;
+        self.analysis.priority += other.analysis.priority;
+        self.analysis.conflicts += other.analysis.conflicts;
+        self.analysis.total += other.analysis.total;
+        self.report += other.report;
+    }
+}
+
+impl fmt::Display for SlotProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "fetch {}, decode {}, priority {}, conflicts {}, report {}",
+            millis(self.fetch),
+            millis(self.analysis.decode),
+            millis(self.analysis.priority),
+            millis(self.analysis.conflicts),
+            millis(self.report),
+        )
+    }
+}
+
+/// Prints a phase breakdown per slot to stderr when enabled, and totals on `finish`.
+#[derive(Default)]
+pub struct Profiler {
+    enabled: bool,
+    tag: String,
+    slot_count: usize,
+    total: SlotProfile,
+}
+
+impl Profiler {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Self::default()
+        }
+    }
+
+    /// Prefixes every printed line with `tag`, e.g. the cluster name in watch mode.
+    pub fn tagged(self, tag: String) -> Self {
+        Self { tag, ..self }
+    }
+
+    /// Starts timing a phase if profiling is enabled.
+    pub fn start(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    /// Records the phases of `analysis`, given the time then spent reporting it.
+    pub fn record(&mut self, analysis: &BlockAnalysis, report: Duration) {
+        if !self.enabled {
+            return;
+        }
+        let profile = SlotProfile {
+            fetch: analysis.fetch.duration,
+            analysis: analysis.timings,
+            report,
+        };
+        eprintln!("{}Profile {}: {}", self.tag, analysis.slot, profile);
+        self.slot_count += 1;
+        self.total.add(&profile);
+    }
+
+    pub fn finish(&self) {
+        if !self.enabled || self.slot_count == 0 {
+            return;
+        }
+        eprintln!(
+            "{}Profile total over {} slots: {}",
+            self.tag, self.slot_count, self.total
+        );
+        let (fetch, cpu) = (self.total.fetch, self.total.cpu());
+        let bottleneck = if fetch > cpu { "RPC" } else { "CPU" };
+        eprintln!(
+            "{}{} bound: {} fetching, {} decoding, analyzing, and reporting",
+            self.tag,
+            bottleneck,
+            millis(fetch),
+            millis(cpu)
+        );
+    }
+}
+
+/// Elapsed time since `start`, or zero if the phase was not timed.
+pub fn elapsed(start: Option<Instant>) -> Duration {
+    start.map(|start| start.elapsed()).unwrap_or_default()
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,194 @@
+use {
+    crate::{analysis::percentile, rpc::BatchClient},
+    clap::ValueEnum,
+    serde::Deserialize,
+    serde_json::json,
+    solana_client::rpc_response::RpcPrioritizationFee,
+    solana_sdk::pubkey::Pubkey,
+};
+
+/// APIs some RPC providers offer beyond standard Solana JSON-RPC, served from the same endpoint
+/// as the blocks being analyzed.
+pub trait ProviderAdapter: Send + Sync {
+    /// Name printed next to the provider's figures.
+    fn name(&self) -> &'static str;
+
+    /// The provider's priority fee estimate for transactions write-locking each of `accounts`,
+    /// in the same order.
+    fn priority_fee_estimates(
+        &self,
+        client: &BatchClient,
+        accounts: &[Pubkey],
+    ) -> Vec<Result<PriorityFeeEstimate, String>>;
+}
+
+/// Priority, in micro-lamports per compute unit, a provider estimates a transaction needs at each
+/// level of urgency, as the 25th, 50th, 75th, and 95th percentiles of recently landed
+/// priorities.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PriorityFeeEstimate {
+    pub low: u64,
+    /// The level providers recommend by default.
+    pub medium: u64,
+    pub high: u64,
+    pub very_high: u64,
+}
+
+impl PriorityFeeEstimate {
+    /// Each level with its name, lowest first.
+    pub fn levels(&self) -> [(&'static str, u64); 4] {
+        [
+            ("low", self.low),
+            ("medium", self.medium),
+            ("high", self.high),
+            ("very high", self.very_high),
+        ]
+    }
+}
+
+/// Providers with adapters, selectable from the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Provider {
+    Helius,
+    Triton,
+}
+
+impl Provider {
+    pub fn adapter(self) -> Box<dyn ProviderAdapter> {
+        match self {
+            Provider::Helius => Box::new(Helius),
+            Provider::Triton => Box::new(Triton),
+        }
+    }
+}
+
+/// Helius, estimating fees with its getPriorityFeeEstimate method.
+#[derive(Debug, Default)]
+pub struct Helius;
+
+impl ProviderAdapter for Helius {
+    fn name(&self) -> &'static str {
+        "helius"
+    }
+
+    fn priority_fee_estimates(
+        &self,
+        client: &BatchClient,
+        accounts: &[Pubkey],
+    ) -> Vec<Result<PriorityFeeEstimate, String>> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            priority_fee_levels: Levels,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Levels {
+            low: f64,
+            medium: f64,
+            high: f64,
+            very_high: f64,
+        }
+
+        let params: Vec<_> = accounts
+            .iter()
+            .map(|account| {
+                json!([{
+                    "accountKeys": [account.to_string()],
+                    "options": {"includeAllPriorityFeeLevels": true},
+                }])
+            })
+            .collect();
+        client
+            .call::<Response>("getPriorityFeeEstimate", &params)
+            .into_iter()
+            .map(|result| {
+                let levels = result?.priority_fee_levels;
+                Ok(PriorityFeeEstimate {
+                    low: levels.low.round() as u64,
+                    medium: levels.medium.round() as u64,
+                    high: levels.high.round() as u64,
+                    very_high: levels.very_high.round() as u64,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Triton, estimating fees with the percentile option its getRecentPrioritizationFees accepts,
+/// taking the median over the provider's recent slot window of each slot's percentile.
+#[derive(Debug, Default)]
+pub struct Triton;
+
+impl ProviderAdapter for Triton {
+    fn name(&self) -> &'static str {
+        "triton"
+    }
+
+    fn priority_fee_estimates(
+        &self,
+        client: &BatchClient,
+        accounts: &[Pubkey],
+    ) -> Vec<Result<PriorityFeeEstimate, String>> {
+        // Percentiles of each level, in basis points.
+        const PERCENTILES: [u64; 4] = [2500, 5000, 7500, 9500];
+
+        let params: Vec<_> = accounts
+            .iter()
+            .flat_map(|account| {
+                PERCENTILES.iter().map(
+                    move |percentile| json!([[account.to_string()], {"percentile": percentile}]),
+                )
+            })
+            .collect();
+        let results =
+            client.call::<Vec<RpcPrioritizationFee>>("getRecentPrioritizationFees", &params);
+        results
+            .chunks(PERCENTILES.len())
+            .map(|levels| {
+                let mut medians = [0; PERCENTILES.len()];
+                for (median, level) in medians.iter_mut().zip(levels) {
+                    let mut fees: Vec<_> = level
+                        .as_ref()
+                        .map_err(String::clone)?
+                        .iter()
+                        .map(|fee| fee.prioritization_fee)
+                        .collect();
+                    if fees.is_empty() {
+                        return Err("no recent slots reported".to_string());
+                    }
+                    fees.sort_unstable();
+                    *median = percentile(&fees, 0.5);
+                }
+                let [low, medium, high, very_high] = medians;
+                Ok(PriorityFeeEstimate {
+                    low,
+                    medium,
+                    high,
+                    very_high,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A provider's fee estimate for one of a block's hot accounts, scored against the account's
+/// clearing price, the lowest priority that still won a write lock on it in the block.
+pub struct EstimateEvaluation {
+    pub account: Pubkey,
+    pub clearing_price: u64,
+    pub estimate: PriorityFeeEstimate,
+}
+
+impl EstimateEvaluation {
+    /// How far the recommended level was above the clearing price, negative if below.
+    pub fn error(&self) -> i128 {
+        i128::from(self.estimate.medium) - i128::from(self.clearing_price)
+    }
+
+    /// Whether a transaction bidding `priority` would have priced in on the account.
+    pub fn clears(&self, priority: u64) -> bool {
+        priority >= self.clearing_price
+    }
+}
@@ -0,0 +1,120 @@
+use {
+    crate::{
+        analysis::ratio,
+        report::{BlockRecord, ViolationRecord},
+    },
+    solana_sdk::clock::{Slot, UnixTimestamp},
+    std::{
+        collections::{BTreeMap, HashMap},
+        ops::RangeInclusive,
+    },
+};
+
+/// Violation figures over a group of block records: a leader's blocks, or a day's.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordTotals {
+    pub block_count: usize,
+    pub transaction_count: usize,
+    pub violation_count: usize,
+}
+
+impl RecordTotals {
+    fn add(&mut self, record: &BlockRecord) {
+        self.block_count += 1;
+        self.transaction_count += record.transaction_count;
+        self.violation_count += record.violation_count;
+    }
+
+    /// Violating transactions per analyzed transaction.
+    pub fn violation_rate(&self) -> f64 {
+        ratio(self.violation_count as u64, self.transaction_count as u64)
+    }
+}
+
+/// Leaders of at least `min_blocks` of the recorded blocks in `slots`, highest violation rate
+/// first. Blocks recorded without a leader are left out.
+pub fn worst_leaders(
+    records: &[BlockRecord],
+    slots: RangeInclusive<Slot>,
+    min_blocks: usize,
+) -> Vec<(String, RecordTotals)> {
+    let mut leaders: HashMap<&str, RecordTotals> = HashMap::new();
+    for record in records.iter().filter(|record| slots.contains(&record.slot)) {
+        if let Some(leader) = &record.leader {
+            leaders.entry(leader).or_default().add(record);
+        }
+    }
+    let mut leaders: Vec<_> = leaders
+        .into_iter()
+        .filter(|(_, totals)| totals.block_count >= min_blocks)
+        .map(|(leader, totals)| (leader.to_string(), totals))
+        .collect();
+    leaders.sort_by(|a, b| {
+        b.1.violation_rate()
+            .total_cmp(&a.1.violation_rate())
+            .then(a.0.cmp(&b.0))
+    });
+    leaders
+}
+
+/// Every recorded violation on `account`, in slot order.
+pub fn account_violations<'a>(
+    records: &'a [BlockRecord],
+    account: &str,
+) -> Vec<(&'a BlockRecord, &'a ViolationRecord)> {
+    let mut violations: Vec<_> = records
+        .iter()
+        .flat_map(|record| {
+            record
+                .violations
+                .iter()
+                .filter(|violation| violation.account == account)
+                .map(move |violation| (record, violation))
+        })
+        .collect();
+    violations.sort_by_key(|(record, violation)| (record.slot, violation.index));
+    violations
+}
+
+/// Violation figures per UTC day, oldest first, as `YYYY-MM-DD`, and the number of records
+/// left out for having no block time.
+pub fn daily_trend(records: &[BlockRecord]) -> (Vec<(String, RecordTotals)>, usize) {
+    let mut days: BTreeMap<i64, RecordTotals> = BTreeMap::new();
+    let mut untimed_count = 0;
+    for record in records {
+        match record.block_time {
+            Some(block_time) => days
+                .entry(block_time.div_euclid(SECONDS_PER_DAY))
+                .or_default()
+                .add(record),
+            None => untimed_count += 1,
+        }
+    }
+    let days = days
+        .into_iter()
+        .map(|(day, totals)| (format_date(day * SECONDS_PER_DAY), totals))
+        .collect();
+    (days, untimed_count)
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The UTC date of `timestamp` as `YYYY-MM-DD`, using the proleptic Gregorian calendar.
+fn format_date(timestamp: UnixTimestamp) -> String {
+    // Days since 0000-03-01, so that leap days fall at the end of each year.
+    let days = timestamp.div_euclid(SECONDS_PER_DAY) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,86 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+/// Holds results that completed out of order until every earlier one has arrived, so sinks see
+/// blocks in slot order however the workers producing them interleave.
+pub struct ReorderBuffer<T> {
+    pending: BTreeMap<usize, T>,
+    next: usize,
+}
+
+impl<T> Default for ReorderBuffer<T> {
+    fn default() -> Self {
+        Self {
+            pending: BTreeMap::new(),
+            next: 0,
+        }
+    }
+}
+
+impl<T> ReorderBuffer<T> {
+    pub fn insert(&mut self, position: usize, item: T) {
+        self.pending.insert(position, item);
+    }
+
+    /// The item at the next position, once it has arrived.
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.pending.remove(&self.next)?;
+        self.next += 1;
+        Some(item)
+    }
+
+    /// Number of items released so far, which is also the position of the next one.
+    pub fn released(&self) -> usize {
+        self.next
+    }
+}
+
+/// Shared between the workers producing results and the thread releasing them in order, holding
+/// workers back from starting on a position more than `size` past the next one to be released.
+/// This bounds how many finished results wait in a [`ReorderBuffer`] behind one slow block.
+pub struct ReorderWindow {
+    released: Mutex<usize>,
+    advanced: Condvar,
+    size: usize,
+}
+
+impl ReorderWindow {
+    pub fn new(size: usize) -> Self {
+        Self {
+            released: Mutex::new(0),
+            advanced: Condvar::new(),
+            size: size.max(1),
+        }
+    }
+
+    /// Blocks until `position` is within the window.
+    pub fn wait_for(&self, position: usize) {
+        let released = self.released.lock().unwrap();
+        let _released = self
+            .advanced
+            .wait_while(released, |released| position >= *released + self.size)
+            .unwrap();
+    }
+
+    /// Blocks until `position` is within the window or `timeout` passes, returning whether it
+    /// is within the window.
+    pub fn wait_for_timeout(&self, position: usize, timeout: Duration) -> bool {
+        let released = self.released.lock().unwrap();
+        let (_released, result) = self
+            .advanced
+            .wait_timeout_while(released, timeout, |released| {
+                position >= *released + self.size
+            })
+            .unwrap();
+        !result.timed_out()
+    }
+
+    /// Records that the first `released` results have been released.
+    pub fn advance(&self, released: usize) {
+        *self.released.lock().unwrap() = released;
+        self.advanced.notify_all();
+    }
+}
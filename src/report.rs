@@ -0,0 +1,704 @@
+use {
+    crate::{
+        analysis::{BlockAnalysis, TieStats, ViolationKind},
+        arrow::ArrowReporter,
+        budget::ComputeBudgetAdoption,
+        jumps::QueueJumps,
+        output::{write_output, OutputFile},
+        stats::{RangeStats, SlotCsvWriter},
+    },
+    clap::ValueEnum,
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashMap},
+        fs,
+        io::Write,
+        path::{Path, PathBuf},
+        process::exit,
+        time::Duration,
+    },
+};
+
+/// Receives each block analysis produced by the range driver, in slot order.
+pub trait Reporter {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>);
+
+    /// Called once after the last block.
+    fn finish(&mut self) {}
+}
+
+/// Forwards every block to each of several reporters, in order.
+#[derive(Default)]
+pub struct FanOut<'a> {
+    reporters: Vec<Box<dyn Reporter + 'a>>,
+}
+
+impl<'a> FanOut<'a> {
+    pub fn push(&mut self, reporter: impl Reporter + 'a) {
+        self.reporters.push(Box::new(reporter));
+    }
+}
+
+impl Reporter for FanOut<'_> {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        for reporter in &mut self.reporters {
+            reporter.report(analysis, leader);
+        }
+    }
+
+    fn finish(&mut self) {
+        for reporter in &mut self.reporters {
+            reporter.finish();
+        }
+    }
+}
+
+impl FanOut<'_> {
+    /// Adds the reporter writing `sink`, replacing any file already at its path.
+    pub fn push_sink(&mut self, sink: &OutputSink) {
+        let path = &sink.path;
+        match sink.format {
+            SinkFormat::Json => self.push(JsonReporter::new(path)),
+            SinkFormat::Ndjson => self.push(NdjsonReporter::new(path)),
+            SinkFormat::Csv => self.push(SlotCsvWriter::new(path)),
+            SinkFormat::Prometheus => self.push(PrometheusReporter::new(path)),
+            SinkFormat::Arrow => self.push(ArrowReporter::new(path)),
+        }
+    }
+}
+
+/// Structured output formats that can be written with `--output FORMAT=PATH`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SinkFormat {
+    /// One JSON array of block records, written once the run finishes.
+    Json,
+    /// One block record per line.
+    Ndjson,
+    /// One summary row per block.
+    Csv,
+    /// Prometheus text-format metrics, rewritten after every block.
+    Prometheus,
+    /// Arrow IPC files of transactions and violations, written into the directory PATH.
+    Arrow,
+}
+
+/// A structured output given with `--output FORMAT=PATH`.
+#[derive(Clone, Debug)]
+pub struct OutputSink {
+    pub format: SinkFormat,
+    pub path: PathBuf,
+}
+
+/// Parses a `FORMAT=PATH` output sink.
+pub fn parse_output_sink(sink: &str) -> Result<OutputSink, String> {
+    let (format, path) = sink
+        .split_once('=')
+        .filter(|(_, path)| !path.is_empty())
+        .ok_or_else(|| format!("expected `FORMAT=PATH`, got `{}`", sink))?;
+    let format = <SinkFormat as ValueEnum>::from_str(format, true)?;
+    Ok(OutputSink {
+        format,
+        path: PathBuf::from(path),
+    })
+}
+
+impl<R: Reporter + ?Sized> Reporter for &mut R {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        (**self).report(analysis, leader);
+    }
+
+    fn finish(&mut self) {
+        (**self).finish();
+    }
+}
+
+impl Reporter for RangeStats {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        self.add(analysis, leader);
+    }
+}
+
+impl Reporter for SlotCsvWriter {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        self.write(analysis, leader);
+    }
+
+    fn finish(&mut self) {
+        self.commit();
+    }
+}
+
+impl Reporter for ArrowReporter {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        self.write(analysis, leader);
+    }
+
+    fn finish(&mut self) {
+        self.commit();
+    }
+}
+
+/// Version of the structured output formats: the NDJSON records, the CSV columns, saved range
+/// statistics, the Prometheus metrics and the Arrow tables, which carry it in their schema
+/// metadata.
+///
+/// Compatibility policy: adding a field (or a trailing CSV column) keeps the version, and every
+/// record type defaults missing fields on deserialization so older output still parses. Removing,
+/// renaming, or changing the meaning of a field bumps the version.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Writes one JSON object per analyzed block, one per line.
+pub struct NdjsonReporter {
+    writer: OutputFile,
+}
+
+/// Per-block record written by the NDJSON reporter.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct BlockRecord {
+    pub schema_version: u32,
+    pub slot: u64,
+    /// Unix time the block was produced at, if the provider reported it.
+    pub block_time: Option<i64>,
+    pub leader: Option<String>,
+    pub transaction_count: usize,
+    pub vote_transaction_count: usize,
+    pub violation_count: usize,
+    pub violation_rate: f64,
+    /// Account-level violations where a zero-priority transaction landed first.
+    pub zero_priority_jump_count: usize,
+    pub fill: f64,
+    pub violations: Vec<ViolationRecord>,
+    pub violating_transactions: Vec<ViolatingTransactionRecord>,
+    /// Compute-budget settings made by the non-vote transactions analyzed.
+    pub compute_budget: ComputeBudgetAdoption,
+    /// Conflicts between transactions of equal priority.
+    pub ties: TieStats,
+    /// How many higher-priority conflicting transactions landed after each transaction.
+    pub queue_jumps: QueueJumps,
+    /// Whether the block was fetched at finalized rather than confirmed commitment. watch
+    /// --reanalyze-finalized records each block both ways.
+    pub finalized: bool,
+    /// How the block was fetched and analyzed, for tuning large backfills. Unlike the other
+    /// fields, timings differ between runs over the same block.
+    pub metadata: RecordMetadata,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RecordMetadata {
+    /// Wall time spent reading the block from the cache or fetching it from RPC.
+    pub fetch_ms: f64,
+    /// Wall time spent decoding and sanitizing transactions, included in `analyze_ms`.
+    pub decode_ms: f64,
+    /// Wall time spent on the whole analysis.
+    pub analyze_ms: f64,
+    /// Requests retried while fetching the block after the provider rate limited them.
+    pub rpc_retries: u64,
+    /// Whether the block was read from the cache.
+    pub cache_hit: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ViolationRecord {
+    pub account: String,
+    pub kind: String,
+    pub previous_priority: u64,
+    pub priority: u64,
+    /// Block positions of the earlier and later transactions.
+    pub previous_index: usize,
+    pub index: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ViolatingTransactionRecord {
+    pub signature: String,
+    /// Block position of the transaction.
+    pub index: usize,
+    pub previous_signatures: Vec<String>,
+    /// Block positions of `previous_signatures`, in the same order.
+    pub previous_indexes: Vec<usize>,
+    pub is_durable_nonce: bool,
+    pub inner_instructions: Vec<String>,
+}
+
+impl BlockRecord {
+    pub fn new(analysis: &BlockAnalysis, leader: Option<Pubkey>) -> Self {
+        let transactions: HashMap<_, _> = analysis
+            .transactions
+            .iter()
+            .map(|transaction| (transaction.signature, transaction))
+            .collect();
+        let mut violations: Vec<_> = analysis
+            .violated_accounts
+            .iter()
+            .flat_map(|(account, violations)| {
+                violations.iter().map(move |violation| ViolationRecord {
+                    account: account.to_string(),
+                    kind: violation.kind.to_string(),
+                    previous_priority: violation.previous_priority,
+                    priority: violation.priority,
+                    previous_index: violation.previous_index,
+                    index: violation.index,
+                })
+            })
+            .collect();
+        violations.sort_by(|a, b| a.account.cmp(&b.account).then(a.index.cmp(&b.index)));
+        let violating_transactions = analysis
+            .violating_transaction_signatures
+            .iter()
+            .map(|(previous_signatures, signature)| {
+                let transaction = transactions.get(signature);
+                ViolatingTransactionRecord {
+                    signature: signature.to_string(),
+                    index: transaction.map_or(0, |transaction| transaction.index),
+                    previous_signatures: previous_signatures
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    previous_indexes: previous_signatures
+                        .iter()
+                        .filter_map(|signature| transactions.get(signature))
+                        .map(|transaction| transaction.index)
+                        .collect(),
+                    is_durable_nonce: transaction
+                        .is_some_and(|transaction| transaction.is_durable_nonce),
+                    inner_instructions: transaction
+                        .map(|transaction| {
+                            transaction
+                                .inner_instructions
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            slot: analysis.slot,
+            block_time: analysis.block_time,
+            leader: leader.map(|leader| leader.to_string()),
+            transaction_count: analysis.analyzed_transaction_count(),
+            vote_transaction_count: analysis.vote_transaction_count(),
+            violation_count: analysis.violating_transaction_signatures.len(),
+            violation_rate: analysis.violation_rate(),
+            zero_priority_jump_count: analysis.zero_priority_jumps().len(),
+            fill: analysis.fill(),
+            violations,
+            violating_transactions,
+            compute_budget: analysis.compute_budget_adoption(),
+            ties: analysis.tie_stats(),
+            queue_jumps: QueueJumps::new(analysis),
+            finalized: false,
+            metadata: RecordMetadata {
+                fetch_ms: millis(analysis.fetch.duration),
+                decode_ms: millis(analysis.timings.decode),
+                analyze_ms: millis(analysis.timings.total),
+                rpc_retries: analysis.fetch.rpc_retries,
+                cache_hit: analysis.fetch.cache_hit,
+            },
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Exits if two analyses of the same block produce different structured output, naming the
+/// fields that differ. The metadata is left out, since its timings never match.
+pub fn verify_identical(first: &BlockAnalysis, second: &BlockAnalysis) {
+    let to_value = |analysis| {
+        let record = BlockRecord {
+            metadata: RecordMetadata::default(),
+            ..BlockRecord::new(analysis, None)
+        };
+        serde_json::to_value(record).unwrap_or_else(|err| {
+            eprintln!("Failed to serialize analysis: {}", err);
+            exit(1);
+        })
+    };
+    let (first_record, second_record) = (to_value(first), to_value(second));
+    if first_record == second_record {
+        return;
+    }
+    let differing: Vec<_> = first_record
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(field, value)| second_record.get(field.as_str()) != Some(value))
+        .map(|(field, _)| field.as_str())
+        .collect();
+    eprintln!(
+        "Nondeterministic analysis of slot {}: {} differ between runs",
+        first.slot,
+        differing.join(", ")
+    );
+    exit(1);
+}
+
+/// Reads block records written with `--ndjson`. A file holding a single, possibly
+/// pretty-printed, record also works.
+///
+/// A run killed while appending can leave a truncated last line, which is dropped with a
+/// warning; the next run appending to the file cuts it off and analyzes its slot again.
+pub fn read_records(path: &Path) -> Vec<BlockRecord> {
+    let contents = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Failed to read {}: {}", path.display(), err);
+        exit(1);
+    });
+    let parse = |contents: &str| -> Result<Vec<BlockRecord>, _> {
+        serde_json::Deserializer::from_str(contents)
+            .into_iter()
+            .collect()
+    };
+    let records = parse(&contents)
+        .or_else(|err| {
+            let complete = match contents.rfind('\n') {
+                Some(newline) if !contents.ends_with('\n') => &contents[..newline + 1],
+                _ => return Err(err),
+            };
+            let records = parse(complete).map_err(|_| err)?;
+            eprintln!(
+                "Warning: ignoring the truncated last line of {}",
+                path.display()
+            );
+            Ok(records)
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to parse {}: {}", path.display(), err);
+            exit(1);
+        });
+    if let Some(record) = records
+        .iter()
+        .find(|record| record.schema_version > SCHEMA_VERSION)
+    {
+        eprintln!(
+            "Warning: {} was written with schema version {}, newer than {}",
+            path.display(),
+            record.schema_version,
+            SCHEMA_VERSION
+        );
+    }
+    records
+}
+
+impl NdjsonReporter {
+    /// Writes a new records file, which only replaces `path` once finished.
+    pub fn new(path: &Path) -> Self {
+        Self {
+            writer: OutputFile::create(path),
+        }
+    }
+
+    /// Appends to the records already in `path`, creating it if missing.
+    pub fn append(path: &Path) -> Self {
+        Self {
+            writer: OutputFile::append(path),
+        }
+    }
+}
+
+impl NdjsonReporter {
+    pub fn write_record(&mut self, record: &BlockRecord) {
+        // Written in one go, so a record is never left half in the buffer.
+        serde_json::to_vec(record)
+            .map_err(std::io::Error::from)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                self.writer.write_all(&line)
+            })
+            .and_then(|()| self.writer.flush())
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to write NDJSON: {}", err);
+                exit(1);
+            });
+    }
+}
+
+impl Reporter for NdjsonReporter {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        self.write_record(&BlockRecord::new(analysis, leader));
+    }
+
+    fn finish(&mut self) {
+        self.writer.commit();
+    }
+}
+
+/// Writes the records of every analyzed block as one JSON array once the run finishes.
+pub struct JsonReporter {
+    path: PathBuf,
+    records: Vec<BlockRecord>,
+}
+
+impl JsonReporter {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            records: Vec::new(),
+        }
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn report(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        self.records.push(BlockRecord::new(analysis, leader));
+    }
+
+    fn finish(&mut self) {
+        let mut json = serde_json::to_vec_pretty(&self.records).unwrap_or_else(|err| {
+            eprintln!("Failed to serialize JSON: {}", err);
+            exit(1);
+        });
+        json.push(b'\n');
+        write_output(&self.path, &json);
+    }
+}
+
+/// Keeps a Prometheus text-format metrics file up to date, for node_exporter's textfile
+/// collector. The file is rewritten atomically after every block.
+pub struct PrometheusReporter {
+    path: PathBuf,
+    last_slot: u64,
+    last_fill: f64,
+    last_violation_rate: f64,
+    block_count: usize,
+    transaction_count: usize,
+    violation_count: usize,
+    violations_by_kind: BTreeMap<ViolationKind, usize>,
+}
+
+impl PrometheusReporter {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            last_slot: 0,
+            last_fill: 0.0,
+            last_violation_rate: 0.0,
+            block_count: 0,
+            transaction_count: 0,
+            violation_count: 0,
+            violations_by_kind: BTreeMap::new(),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut metrics = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            metrics.push_str(&format!("# HELP priority_checker_{} {}\n", name, help));
+            metrics.push_str(&format!("# TYPE priority_checker_{} {}\n", name, kind));
+            for (labels, value) in samples {
+                metrics.push_str(&format!("priority_checker_{}{} {}\n", name, labels, value));
+            }
+        };
+        let unlabeled = |value: String| [(String::new(), value)];
+
+        metric(
+            "schema_info",
+            "gauge",
+            "Schema version of these metrics, as the version label.",
+            &[(
+                format!("{{version=\"{}\"}}", SCHEMA_VERSION),
+                "1".to_string(),
+            )],
+        );
+        metric(
+            "last_slot",
+            "gauge",
+            "Slot of the most recently analyzed block.",
+            &unlabeled(self.last_slot.to_string()),
+        );
+        metric(
+            "blocks_total",
+            "counter",
+            "Blocks analyzed.",
+            &unlabeled(self.block_count.to_string()),
+        );
+        metric(
+            "transactions_total",
+            "counter",
+            "Transactions that took part in the analysis.",
+            &unlabeled(self.transaction_count.to_string()),
+        );
+        metric(
+            "violating_transactions_total",
+            "counter",
+            "Transactions that landed after a lower-priority conflicting transaction.",
+            &unlabeled(self.violation_count.to_string()),
+        );
+        let by_kind: Vec<_> = ViolationKind::ALL
+            .iter()
+            .map(|kind| {
+                let count = self
+                    .violations_by_kind
+                    .get(kind)
+                    .copied()
+                    .unwrap_or_default();
+                (format!("{{kind=\"{}\"}}", kind), count.to_string())
+            })
+            .collect();
+        metric(
+            "account_violations_total",
+            "counter",
+            "Account-level violations by conflict type.",
+            &by_kind,
+        );
+        metric(
+            "last_block_violation_rate",
+            "gauge",
+            "Violating transactions per analyzed transaction in the last block.",
+            &unlabeled(self.last_violation_rate.to_string()),
+        );
+        metric(
+            "last_block_fill_ratio",
+            "gauge",
+            "Compute units consumed by the last block over the block limit.",
+            &unlabeled(self.last_fill.to_string()),
+        );
+        metrics
+    }
+}
+
+impl Reporter for PrometheusReporter {
+    fn report(&mut self, analysis: &BlockAnalysis, _leader: Option<Pubkey>) {
+        self.last_slot = analysis.slot;
+        self.last_fill = analysis.fill();
+        self.last_violation_rate = analysis.violation_rate();
+        self.block_count += 1;
+        self.transaction_count += analysis.analyzed_transaction_count();
+        self.violation_count += analysis.violating_transaction_signatures.len();
+        for violation in analysis.violations() {
+            *self.violations_by_kind.entry(violation.kind).or_default() += 1;
+        }
+
+        write_output(&self.path, self.render().as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{env, process},
+    };
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("priority-checker-{}-{}", process::id(), name))
+    }
+
+    fn record_line(slot: u64) -> String {
+        let record = BlockRecord {
+            schema_version: SCHEMA_VERSION,
+            slot,
+            ..BlockRecord::default()
+        };
+        serde_json::to_string(&record).unwrap()
+    }
+
+    fn read_slots(name: &str, contents: &str) -> Vec<u64> {
+        let path = temp_path(name);
+        fs::write(&path, contents).unwrap();
+        let slots = read_records(&path)
+            .iter()
+            .map(|record| record.slot)
+            .collect();
+        fs::remove_file(&path).unwrap();
+        slots
+    }
+
+    #[test]
+    fn parse_output_sink_cases() {
+        let valid = [
+            ("json=out.json", SinkFormat::Json, "out.json"),
+            ("NDJSON=-", SinkFormat::Ndjson, "-"),
+            ("csv=runs/a=b.csv", SinkFormat::Csv, "runs/a=b.csv"),
+            (
+                "Prometheus=metrics.prom",
+                SinkFormat::Prometheus,
+                "metrics.prom",
+            ),
+            ("arrow=tables/", SinkFormat::Arrow, "tables/"),
+        ];
+        for (sink, format, path) in valid {
+            let parsed = parse_output_sink(sink).unwrap();
+            assert_eq!(parsed.format, format, "{}", sink);
+            assert_eq!(parsed.path, Path::new(path), "{}", sink);
+        }
+        for sink in [
+            "json",
+            "",
+            "xml=out.xml",
+            "=out.json",
+            "csv=",
+            "js on=out.json",
+        ] {
+            assert!(parse_output_sink(sink).is_err(), "{} parsed", sink);
+        }
+    }
+
+    #[test]
+    fn read_records_recovers_from_truncation() {
+        let (first, second) = (record_line(100), record_line(101));
+        let pretty = serde_json::to_string_pretty(&BlockRecord {
+            slot: 102,
+            ..BlockRecord::default()
+        })
+        .unwrap();
+        let cases = [
+            (
+                "complete",
+                format!("{}\n{}\n", first, second),
+                vec![100, 101],
+            ),
+            (
+                "unterminated",
+                format!("{}\n{}", first, second),
+                vec![100, 101],
+            ),
+            (
+                "truncated",
+                format!("{}\n{}", first, &second[..second.len() / 2]),
+                vec![100],
+            ),
+            ("pretty", pretty, vec![102]),
+            ("empty", String::new(), vec![]),
+        ];
+        for (name, contents, expected) in cases {
+            assert_eq!(read_slots(name, &contents), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn appending_after_truncation_keeps_every_record() {
+        let path = temp_path("append.ndjson");
+        let second = record_line(101);
+        fs::write(
+            &path,
+            format!("{}\n{}", record_line(100), &second[..second.len() - 3]),
+        )
+        .unwrap();
+        let mut reporter = NdjsonReporter::append(&path);
+        for slot in [101, 102] {
+            reporter.write_record(&BlockRecord {
+                schema_version: SCHEMA_VERSION,
+                slot,
+                ..BlockRecord::default()
+            });
+        }
+        reporter.finish();
+        let slots: Vec<_> = read_records(&path)
+            .iter()
+            .map(|record| record.slot)
+            .collect();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(slots, [100, 101, 102]);
+    }
+}
@@ -0,0 +1,1012 @@
+use {
+    reqwest::{
+        header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+        Proxy, StatusCode,
+    },
+    serde::{de::DeserializeOwned, Deserialize},
+    serde_json::{json, Value},
+    solana_client::{
+        rpc_client::{RpcClient, RpcClientConfig},
+        rpc_config::RpcBlockConfig,
+        rpc_request::{MAX_GET_SLOT_LEADERS, MAX_MULTIPLE_ACCOUNTS},
+        rpc_response::{RpcPrioritizationFee, RpcVersionInfo},
+    },
+    solana_rpc_client::http_sender::HttpSender,
+    solana_sdk::{
+        address_lookup_table::state::AddressLookupTable,
+        clock::Slot,
+        commitment_config::{CommitmentConfig, CommitmentLevel},
+        epoch_schedule::EpochSchedule,
+        message::v0::{LoadedAddresses, MessageAddressTableLookup},
+        pubkey::Pubkey,
+        signature::Signature,
+    },
+    solana_transaction_status::{
+        option_serializer::OptionSerializer, TransactionDetails, UiConfirmedBlock,
+        UiLoadedAddresses, UiTransactionEncoding, UiTransactionStatusMeta,
+    },
+    std::{
+        collections::{BTreeMap, HashMap, HashSet},
+        process::exit,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+    tokio::runtime::Runtime,
+};
+
+pub const MAINNET_URL: &str = "https://api.mainnet-beta.solana.com";
+
+/// Capabilities of each endpoint by URL, probed by the first client to fetch a block from it and
+/// shared by every later one, so each endpoint is probed and warned about once per run.
+static CAPABILITIES: Mutex<BTreeMap<String, RpcCapabilities>> = Mutex::new(BTreeMap::new());
+
+/// Traffic of every [`BatchClient`] by URL, summed across the clients of all workers, for
+/// verbose output.
+static BATCH_STATS: Mutex<BTreeMap<String, BatchStats>> = Mutex::new(BTreeMap::new());
+
+/// HTTP requests sent by batch clients to one endpoint.
+#[derive(Clone, Copy, Debug, Default)]
+struct BatchStats {
+    request_count: u64,
+    /// Requests repeated after a 429 Too Many Requests response.
+    retry_count: u64,
+    /// Time spent backing off after 429 responses.
+    rate_limited_time: Duration,
+    /// Time spent waiting on requests and reading responses.
+    elapsed_time: Duration,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+/// What an RPC endpoint can serve, so block requests can be adjusted to older nodes and to
+/// providers that don't support everything.
+#[derive(Clone, Copy, Debug)]
+pub struct RpcCapabilities {
+    /// Encoding blocks are requested in: base64 wherever the endpoint accepts it, as it is far
+    /// cheaper to decode than base58.
+    pub encoding: UiTransactionEncoding,
+    /// Highest transaction version requested, or `None` for nodes that predate versioned
+    /// transactions and reject the parameter.
+    pub max_transaction_version: Option<u8>,
+}
+
+impl Default for RpcCapabilities {
+    fn default() -> Self {
+        Self {
+            encoding: UiTransactionEncoding::Base64,
+            max_transaction_version: Some(0),
+        }
+    }
+}
+
+/// Settings applied to every RPC client the tool creates.
+#[derive(Clone, Debug)]
+pub struct RpcOptions {
+    /// Timeout for each individual request, so a slow provider cannot stall a scan indefinitely.
+    pub timeout: Duration,
+    /// Initial timeout when confirming transactions; the client default is used if unset.
+    pub confirm_transaction_initial_timeout: Option<Duration>,
+    /// Extra headers sent with every request, e.g. API keys for private endpoints.
+    pub headers: HeaderMap,
+    /// Proxy for all requests. Without one, the standard `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY`
+    /// environment variables are honored.
+    pub proxy: Option<String>,
+}
+
+impl Default for RpcOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            confirm_transaction_initial_timeout: None,
+            headers: HeaderMap::new(),
+            proxy: None,
+        }
+    }
+}
+
+/// Creates RPC clients whose HTTP clients keep connections and their TLS sessions alive and
+/// reuse them across requests, with HTTP/2 multiplexing where the provider supports it.
+///
+/// Connections are only reused within a client: every client gets a connection pool of its own,
+/// even the `RpcClient` and [`BatchClient`] of the same endpoint. Each client runs on its own
+/// single-threaded runtime, and a pooled connection is only driven while the runtime that opened
+/// it is, so a shared pool would let one client's request wait on a connection no one is driving.
+/// reqwest does not expose the state of its pools either, so verbose output reports the requests
+/// each client made rather than connection pool metrics.
+#[derive(Clone)]
+pub struct RpcConnector {
+    options: RpcOptions,
+}
+
+impl RpcConnector {
+    const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+    const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+    pub fn new(options: &RpcOptions) -> Self {
+        Self {
+            options: options.clone(),
+        }
+    }
+
+    fn build_http_client(&self) -> reqwest::Client {
+        let options = &self.options;
+        let mut headers = HttpSender::default_headers();
+        headers.extend(options.headers.clone());
+        let mut builder = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(options.timeout)
+            .pool_idle_timeout(Self::POOL_IDLE_TIMEOUT)
+            .tcp_keepalive(Self::KEEP_ALIVE_INTERVAL)
+            .http2_keep_alive_interval(Self::KEEP_ALIVE_INTERVAL)
+            .http2_keep_alive_while_idle(true)
+            .http2_adaptive_window(true);
+        if let Some(proxy) = &options.proxy {
+            let proxy = Proxy::all(proxy).unwrap_or_else(|err| {
+                eprintln!("Invalid proxy {}: {}", proxy, err);
+                exit(1);
+            });
+            builder = builder.proxy(proxy);
+        }
+        builder.build().unwrap_or_else(|err| {
+            eprintln!("Failed to build HTTP client: {}", err);
+            exit(1);
+        })
+    }
+
+    pub fn connect(&self, url: impl ToString) -> RpcClient {
+        RpcClient::new_sender(
+            HttpSender::new_with_client(url, self.build_http_client()),
+            RpcClientConfig {
+                commitment_config: CommitmentConfig::default(),
+                confirm_transaction_initial_timeout: self
+                    .options
+                    .confirm_transaction_initial_timeout,
+            },
+        )
+    }
+
+    pub fn connect_batch(&self, url: impl ToString) -> BatchClient {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to start RPC runtime: {}", err);
+                exit(1);
+            });
+        BatchClient {
+            http_client: self.build_http_client(),
+            url: url.to_string(),
+            runtime,
+            retry_count: AtomicU64::new(0),
+        }
+    }
+}
+
+/// JSON-RPC client for what `RpcClient` handles poorly: many calls to the same method, sent as
+/// batch requests instead of a round trip each, and multi-megabyte responses, deserialized
+/// straight into their result type instead of through an intermediate `serde_json::Value` tree
+/// several times the size of the response.
+pub struct BatchClient {
+    http_client: reqwest::Client,
+    url: String,
+    runtime: Runtime,
+    retry_count: AtomicU64,
+}
+
+impl BatchClient {
+    /// Calls per HTTP request; providers commonly reject larger batches.
+    const MAX_BATCH_SIZE: usize = 100;
+    /// Attempts per HTTP request while the provider responds 429 Too Many Requests.
+    const MAX_ATTEMPTS: u32 = 5;
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Requests retried so far after the provider responded 429 Too Many Requests.
+    pub fn retry_count(&self) -> u64 {
+        self.retry_count.load(Ordering::Relaxed)
+    }
+
+    /// What the endpoint supports, probed with `getVersion` on first use.
+    pub fn capabilities(&self) -> RpcCapabilities {
+        *CAPABILITIES
+            .lock()
+            .unwrap()
+            .entry(self.url.clone())
+            .or_insert_with(|| self.probe_capabilities())
+    }
+
+    fn probe_capabilities(&self) -> RpcCapabilities {
+        // getBlock replaced getConfirmedBlock in 1.7, and maxSupportedTransactionVersion
+        // arrived with versioned transactions in 1.10.
+        const GET_BLOCK_VERSION: (u64, u64) = (1, 7);
+        const VERSIONED_TRANSACTIONS_VERSION: (u64, u64) = (1, 10);
+
+        let mut capabilities = RpcCapabilities::default();
+        let version = match self.call_one::<RpcVersionInfo>("getVersion", json!([])) {
+            Ok(version) => version.solana_core,
+            Err(err) => {
+                eprintln!(
+                    "Warning: {} did not report its version, assuming it supports current block \
+                     requests: {}",
+                    self.url, err
+                );
+                return capabilities;
+            }
+        };
+        let Some(major_minor) = parse_major_minor(&version) else {
+            eprintln!(
+                "Warning: {} reported unrecognized version {}, assuming it supports current \
+                 block requests",
+                self.url, version
+            );
+            return capabilities;
+        };
+        if major_minor < GET_BLOCK_VERSION {
+            eprintln!(
+                "{} runs {}, which predates getBlock; analysis needs a node running 1.7 or later",
+                self.url, version
+            );
+            exit(1);
+        }
+        if major_minor < VERSIONED_TRANSACTIONS_VERSION {
+            eprintln!(
+                "Warning: {} runs {}, which predates versioned transactions; blocks are \
+                 requested without maxSupportedTransactionVersion",
+                self.url, version
+            );
+            capabilities.max_transaction_version = None;
+        }
+        capabilities
+    }
+
+    /// Falls back to what a more limited endpoint accepts if `err`, the endpoint's response to a
+    /// block request, rejected a parameter, returning whether the request is worth retrying.
+    fn degrade_capabilities(&self, err: &str) -> bool {
+        // JSON-RPC's invalid params code, which nodes answer an encoding they do not know with,
+        // as in `Invalid params: unknown variant `base64``.
+        const INVALID_PARAMS: &str = "RPC response error -32602:";
+
+        let mut endpoints = CAPABILITIES.lock().unwrap();
+        let capabilities = endpoints.entry(self.url.clone()).or_default();
+        if err.contains("maxSupportedTransactionVersion")
+            && capabilities.max_transaction_version.is_some()
+        {
+            eprintln!(
+                "Warning: {} rejected maxSupportedTransactionVersion, requesting blocks \
+                 without it; blocks containing versioned transactions cannot be fetched",
+                self.url
+            );
+            capabilities.max_transaction_version = None;
+            return true;
+        }
+        if err.starts_with(INVALID_PARAMS)
+            && err.contains("base64")
+            && capabilities.encoding == UiTransactionEncoding::Base64
+        {
+            eprintln!(
+                "Warning: {} rejected base64 encoding, requesting blocks in base58, which is \
+                 slower to decode",
+                self.url
+            );
+            capabilities.encoding = UiTransactionEncoding::Base58;
+            return true;
+        }
+        false
+    }
+
+    /// Calls `method` once, deserializing its result directly from the response body.
+    pub fn call_one<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, String> {
+        let request = json!({"jsonrpc": "2.0", "id": 0, "method": method, "params": params});
+        let response: SingleResponse<T> = self.post(request.to_string())?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(format!(
+                "RPC response error {}: {}",
+                error.code, error.message
+            )),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err("empty RPC response".to_string()),
+        }
+    }
+
+    /// Calls `method` once with each entry of `params`, returning the results in the same order.
+    pub fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &[Value],
+    ) -> Vec<Result<T, String>> {
+        params
+            .chunks(Self::MAX_BATCH_SIZE)
+            .flat_map(|chunk| self.send(method, chunk))
+            .collect()
+    }
+
+    fn send<T: DeserializeOwned>(&self, method: &str, params: &[Value]) -> Vec<Result<T, String>> {
+        let requests: Vec<_> = params
+            .iter()
+            .enumerate()
+            .map(|(id, params)| {
+                json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+            })
+            .collect();
+        let responses = match self.post::<Vec<Value>>(Value::Array(requests).to_string()) {
+            Ok(responses) => responses,
+            Err(err) => return params.iter().map(|_| Err(err.clone())).collect(),
+        };
+
+        // Responses may arrive in any order; match them to requests by id.
+        let mut results: Vec<Result<T, String>> = params
+            .iter()
+            .map(|_| Err("missing from batch response".to_string()))
+            .collect();
+        for mut response in responses {
+            let Some(result) = response["id"]
+                .as_u64()
+                .and_then(|id| results.get_mut(id as usize))
+            else {
+                continue;
+            };
+            *result = match response.get("error") {
+                Some(error) => Err(error["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string()),
+                None => {
+                    serde_json::from_value(response["result"].take()).map_err(|err| err.to_string())
+                }
+            };
+        }
+        results
+    }
+
+    /// Posts `body`, backing off while rate limited, and deserializes the response.
+    fn post<T: DeserializeOwned>(&self, body: String) -> Result<T, String> {
+        for attempt in 1.. {
+            let start = Instant::now();
+            let response = self.runtime.block_on(async {
+                self.http_client
+                    .post(&self.url)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(body.clone())
+                    .send()
+                    .await
+            });
+            self.record(|stats| {
+                stats.request_count += 1;
+                stats.bytes_sent += body.len() as u64;
+                stats.elapsed_time += start.elapsed();
+            });
+            let response = response.map_err(|err| err.to_string())?;
+            if response.status() == StatusCode::TOO_MANY_REQUESTS && attempt < Self::MAX_ATTEMPTS {
+                let backoff = Duration::from_millis(500) * attempt;
+                self.retry_count.fetch_add(1, Ordering::Relaxed);
+                self.record(|stats| {
+                    stats.retry_count += 1;
+                    stats.rate_limited_time += backoff;
+                });
+                thread::sleep(backoff);
+                continue;
+            }
+            let start = Instant::now();
+            let response_body = self.runtime.block_on(async {
+                response
+                    .error_for_status()
+                    .map_err(|err| err.to_string())?
+                    .bytes()
+                    .await
+                    .map_err(|err| err.to_string())
+            });
+            self.record(|stats| {
+                stats.elapsed_time += start.elapsed();
+                if let Ok(response_body) = &response_body {
+                    stats.bytes_received += response_body.len() as u64;
+                }
+            });
+            return serde_json::from_slice(&response_body?).map_err(|err| err.to_string());
+        }
+        unreachable!("attempts are unbounded")
+    }
+
+    fn record(&self, update: impl FnOnce(&mut BatchStats)) {
+        update(
+            BATCH_STATS
+                .lock()
+                .unwrap()
+                .entry(self.url.clone())
+                .or_default(),
+        );
+    }
+}
+
+#[derive(Deserialize)]
+struct SingleResponse<T> {
+    result: Option<T>,
+    error: Option<ResponseError>,
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+/// One-line summary per endpoint of the requests every [`BatchClient`] has made, for verbose
+/// output. Block fetches go through these, so they usually carry most of the traffic.
+pub fn describe_batch_stats() -> Vec<String> {
+    BATCH_STATS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(url, stats)| {
+            let mean_latency = stats
+                .elapsed_time
+                .checked_div(stats.request_count as u32)
+                .unwrap_or_default();
+            format!(
+                "{} batch requests to {}, {} retried after 429, {:.1}s rate limited, {:.1} ms \
+                 mean latency, {:.1} MB sent, {:.1} MB received",
+                stats.request_count,
+                url,
+                stats.retry_count,
+                stats.rate_limited_time.as_secs_f64(),
+                mean_latency.as_secs_f64() * 1000.0,
+                stats.bytes_sent as f64 / 1e6,
+                stats.bytes_received as f64 / 1e6,
+            )
+        })
+        .collect()
+}
+
+/// One-line summary of the requests `client` has made, for verbose output.
+pub fn describe_transport_stats(client: &RpcClient) -> String {
+    let stats = client.get_transport_stats();
+    let mean_latency = stats
+        .elapsed_time
+        .checked_div(stats.request_count as u32)
+        .unwrap_or_default();
+    format!(
+        "{} requests to {}, {:.1}s total, {:.1} ms mean latency, {:.1}s rate limited",
+        stats.request_count,
+        client.url(),
+        stats.elapsed_time.as_secs_f64(),
+        mean_latency.as_secs_f64() * 1000.0,
+        stats.rate_limited_time.as_secs_f64(),
+    )
+}
+
+/// The block at `slot`, exiting if it cannot be fetched.
+pub fn fetch_block(client: &RpcClient, batch_client: &BatchClient, slot: Slot) -> UiConfirmedBlock {
+    try_fetch_block(client, batch_client, slot).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    })
+}
+
+/// The block at `slot`, with transactions base64-encoded where the endpoint allows, which is
+/// far cheaper to decode than base58. The response is fetched through `batch_client` to avoid
+/// `RpcClient`'s intermediate JSON tree; `client` resolves any missing lookup table addresses.
+pub fn try_fetch_block(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    slot: Slot,
+) -> Result<UiConfirmedBlock, String> {
+    let mut block = get_block(batch_client, slot, CommitmentLevel::Confirmed).map_err(|err| {
+        format!(
+            "Failed to fetch block at slot {} from {}: {}",
+            slot,
+            batch_client.url(),
+            err
+        )
+    })?;
+    resolve_missing_loaded_addresses(client, slot, &mut block);
+    Ok(block)
+}
+
+/// Fetches the block at `slot` at finalized commitment, or warns and returns `None` if it cannot
+/// be, typically because the slot is not finalized yet.
+pub fn fetch_finalized_block(
+    client: &RpcClient,
+    batch_client: &BatchClient,
+    slot: Slot,
+) -> Option<UiConfirmedBlock> {
+    match get_block(batch_client, slot, CommitmentLevel::Finalized) {
+        Ok(mut block) => {
+            resolve_missing_loaded_addresses(client, slot, &mut block);
+            Some(block)
+        }
+        Err(err) => {
+            eprintln!(
+                "Warning: failed to fetch slot {} at finalized commitment, it may not be \
+                 finalized yet or may have been skipped: {}",
+                slot, err
+            );
+            None
+        }
+    }
+}
+
+/// Requests the block at `slot` with the parameters the endpoint supports, retrying with fewer
+/// if it rejects one, and checks it has everything analysis needs.
+fn get_block(
+    batch_client: &BatchClient,
+    slot: Slot,
+    commitment: CommitmentLevel,
+) -> Result<UiConfirmedBlock, String> {
+    let block: UiConfirmedBlock = loop {
+        let config = block_config(batch_client.capabilities(), commitment);
+        match batch_client.call_one("getBlock", json!([slot, config])) {
+            Ok(block) => break block,
+            Err(err) if batch_client.degrade_capabilities(&err) => continue,
+            Err(err) => return Err(explain_block_error(err)),
+        }
+    };
+    match &block.transactions {
+        None => Err("the endpoint returned the block without its transactions".to_string()),
+        Some(transactions)
+            if transactions
+                .iter()
+                .any(|transaction| transaction.meta.is_none()) =>
+        {
+            Err(
+                "the endpoint returned transactions without their status metadata, which \
+                 analysis needs for fees and compute units"
+                    .to_string(),
+            )
+        }
+        Some(_) => Ok(block),
+    }
+}
+
+/// Adds what the endpoint lacks to `err` when it is why a block request failed.
+fn explain_block_error(err: String) -> String {
+    // JSON-RPC's method-not-found code, and the code nodes without transaction history use.
+    const METHOD_NOT_FOUND: &str = "-32601";
+    const TRANSACTION_HISTORY_NOT_AVAILABLE: &str = "-32011";
+
+    if err.contains(METHOD_NOT_FOUND) {
+        format!(
+            "{}; the endpoint does not serve getBlock, which analysis needs",
+            err
+        )
+    } else if err.contains(TRANSACTION_HISTORY_NOT_AVAILABLE) {
+        format!(
+            "{}; the endpoint does not keep transaction history, try an archival RPC provider",
+            err
+        )
+    } else if err.contains("maxSupportedTransactionVersion") {
+        format!(
+            "{}; the endpoint cannot return the block's versioned transactions",
+            err
+        )
+    } else {
+        err
+    }
+}
+
+fn block_config(capabilities: RpcCapabilities, commitment: CommitmentLevel) -> RpcBlockConfig {
+    RpcBlockConfig {
+        encoding: Some(capabilities.encoding),
+        transaction_details: Some(TransactionDetails::Full),
+        rewards: None,
+        commitment: Some(CommitmentConfig { commitment }),
+        max_supported_transaction_version: capabilities.max_transaction_version,
+    }
+}
+
+/// The major and minor version in a version string like `1.17.6`.
+fn parse_major_minor(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Fills in `loaded_addresses` for transactions whose meta lacks them, as in older RPC responses
+/// and some alternate providers, by fetching and decoding the referenced address lookup tables.
+///
+/// Tables are fetched in their current state rather than as of the block at `slot`. Lookup tables
+/// are append-only and keep their addresses when deactivated, so a table not extended since
+/// `slot` still holds exactly the addresses the block saw. Transactions using a table that has
+/// since been extended or closed, or an index past its end, are left without loaded addresses
+/// with a warning, so analysis falls back to their headers instead of trusting stale addresses.
+fn resolve_missing_loaded_addresses(client: &RpcClient, slot: Slot, block: &mut UiConfirmedBlock) {
+    let Some(transactions) = block.transactions.as_mut() else {
+        return;
+    };
+
+    let mut unresolved: Vec<(
+        Signature,
+        Vec<MessageAddressTableLookup>,
+        &mut UiTransactionStatusMeta,
+    )> = Vec::new();
+    for transaction in transactions.iter_mut() {
+        let Some(meta) = transaction.meta.as_mut() else {
+            continue;
+        };
+        if let OptionSerializer::Some(_) = meta.loaded_addresses {
+            continue;
+        }
+        // Left for analysis to reject, which reports malformed transactions.
+        let Some(versioned_transaction) = transaction.transaction.decode() else {
+            continue;
+        };
+        let Some(&signature) = versioned_transaction.signatures.first() else {
+            continue;
+        };
+        let lookups = versioned_transaction
+            .message
+            .address_table_lookups()
+            .map(<[_]>::to_vec)
+            .unwrap_or_default();
+        unresolved.push((signature, lookups, meta));
+    }
+    if unresolved.is_empty() {
+        return;
+    }
+
+    let table_keys: HashSet<_> = unresolved
+        .iter()
+        .flat_map(|(_, lookups, _)| lookups.iter().map(|lookup| lookup.account_key))
+        .collect();
+    let tables = fetch_lookup_tables(client, &table_keys.into_iter().collect::<Vec<_>>());
+
+    'transactions: for (signature, lookups, meta) in unresolved {
+        let mut loaded_addresses = LoadedAddresses::default();
+        for lookup in &lookups {
+            let resolved = match tables.get(&lookup.account_key) {
+                None => Err("it no longer exists".to_string()),
+                Some(table) if table.last_extended_slot > slot => Err(format!(
+                    "it was extended in slot {}, after the block",
+                    table.last_extended_slot
+                )),
+                Some(table) => {
+                    // As in the runtime, addresses added in the block's own slot are not usable
+                    // until the next one.
+                    let addresses = if table.last_extended_slot < slot {
+                        &table.addresses[..]
+                    } else {
+                        &table.addresses[..table.last_extended_slot_start_index]
+                    };
+                    let resolve = |indexes: &[u8]| -> Result<Vec<Pubkey>, String> {
+                        indexes
+                            .iter()
+                            .map(|index| {
+                                addresses.get(*index as usize).copied().ok_or_else(|| {
+                                    format!(
+                                        "index {} is past its {} usable addresses",
+                                        index,
+                                        addresses.len()
+                                    )
+                                })
+                            })
+                            .collect()
+                    };
+                    resolve(&lookup.writable_indexes)
+                        .and_then(|writable| Ok((writable, resolve(&lookup.readonly_indexes)?)))
+                }
+            };
+            match resolved {
+                Ok((writable, readonly)) => {
+                    loaded_addresses.writable.extend(writable);
+                    loaded_addresses.readonly.extend(readonly);
+                }
+                Err(reason) => {
+                    eprintln!(
+                        "Warning: failed to resolve lookup table {} for transaction {}: {}",
+                        lookup.account_key, signature, reason
+                    );
+                    continue 'transactions;
+                }
+            }
+        }
+        meta.loaded_addresses = OptionSerializer::Some(UiLoadedAddresses::from(&loaded_addresses));
+    }
+}
+
+/// The current state of an address lookup table, as far as resolving lookups needs it.
+struct LookupTable {
+    addresses: Vec<Pubkey>,
+    /// Slot the table was last extended in, or 0 if it never was.
+    last_extended_slot: Slot,
+    /// Number of addresses the table held before that extension.
+    last_extended_slot_start_index: usize,
+}
+
+/// Each of the given lookup tables that still exists.
+fn fetch_lookup_tables(client: &RpcClient, table_keys: &[Pubkey]) -> HashMap<Pubkey, LookupTable> {
+    let mut tables = HashMap::new();
+    for chunk in table_keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client
+            .get_multiple_accounts_with_commitment(chunk, CommitmentConfig::confirmed())
+            .map(|response| response.value)
+            .unwrap_or_else(|err| {
+                eprintln!("Warning: failed to fetch address lookup tables: {}", err);
+                Vec::new()
+            });
+        for (table_key, account) in chunk.iter().zip(accounts) {
+            let Some(account) = account else {
+                continue;
+            };
+            match AddressLookupTable::deserialize(&account.data) {
+                Ok(table) => {
+                    tables.insert(
+                        *table_key,
+                        LookupTable {
+                            addresses: table.addresses.to_vec(),
+                            last_extended_slot: table.meta.last_extended_slot,
+                            last_extended_slot_start_index: usize::from(
+                                table.meta.last_extended_slot_start_index,
+                            )
+                            .min(table.addresses.len()),
+                        },
+                    );
+                }
+                Err(err) => eprintln!(
+                    "Warning: failed to decode address lookup table {}: {}",
+                    table_key, err
+                ),
+            }
+        }
+    }
+    tables
+}
+
+/// Parses a `Name: value` header.
+pub fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| format!("expected `Name: value`, got `{}`", header))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|err| err.to_string())?;
+    let mut value = HeaderValue::from_str(value.trim()).map_err(|err| err.to_string())?;
+    value.set_sensitive(true);
+    Ok((name, value))
+}
+
+/// `Authorization` header for a bearer token.
+pub fn bearer_header(token: &str) -> (HeaderName, HeaderValue) {
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", token)).unwrap_or_else(|err| {
+        eprintln!("Invalid bearer token: {}", err);
+        exit(1);
+    });
+    value.set_sensitive(true);
+    (AUTHORIZATION, value)
+}
+
+/// Slots in `start..=end` that have a confirmed block, exiting if they cannot be fetched.
+pub fn fetch_block_slots(client: &RpcClient, start: Slot, end: Slot) -> Vec<Slot> {
+    try_fetch_block_slots(client, start, end).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    })
+}
+
+/// Slots in `start..=end` that have a confirmed block, skipping slots the leader did not produce.
+pub fn try_fetch_block_slots(
+    client: &RpcClient,
+    start: Slot,
+    end: Slot,
+) -> Result<Vec<Slot>, String> {
+    client
+        .get_blocks_with_commitment(start, Some(end), CommitmentConfig::confirmed())
+        .map_err(|err| {
+            format!(
+                "Failed to fetch blocks in range {}..={}: {}",
+                start, end, err
+            )
+        })
+}
+
+/// Latest confirmed slot, or `None` if the provider could not be reached.
+pub fn fetch_tip_slot(client: &RpcClient) -> Option<Slot> {
+    fetch_slot(client, CommitmentConfig::confirmed())
+}
+
+/// Latest finalized slot, or `None` if the provider could not be reached.
+pub fn fetch_finalized_slot(client: &RpcClient) -> Option<Slot> {
+    fetch_slot(client, CommitmentConfig::finalized())
+}
+
+fn fetch_slot(client: &RpcClient, commitment: CommitmentConfig) -> Option<Slot> {
+    match client.get_slot_with_commitment(commitment) {
+        Ok(slot) => Some(slot),
+        Err(err) => {
+            eprintln!(
+                "Warning: failed to fetch slot from {}: {}",
+                client.url(),
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Leader of `slot`, if the provider still has the leader schedule for its epoch.
+pub fn fetch_leader(client: &RpcClient, slot: Slot) -> Option<Pubkey> {
+    match client.get_slot_leaders(slot, 1) {
+        Ok(leaders) => leaders.into_iter().next(),
+        Err(err) => {
+            eprintln!("Warning: failed to fetch leader for slot {}: {}", slot, err);
+            None
+        }
+    }
+}
+
+/// Leaders of every slot in `start..=end`. Empty if the provider no longer has the schedule.
+pub fn fetch_slot_leaders(client: &BatchClient, start: Slot, end: Slot) -> HashMap<Slot, Pubkey> {
+    let chunks: Vec<(Slot, u64)> = (start..=end)
+        .step_by(MAX_GET_SLOT_LEADERS)
+        .map(|chunk_start| {
+            let limit = (end - chunk_start + 1).min(MAX_GET_SLOT_LEADERS as u64);
+            (chunk_start, limit)
+        })
+        .collect();
+    let params: Vec<_> = chunks
+        .iter()
+        .map(|(chunk_start, limit)| json!([chunk_start, limit]))
+        .collect();
+
+    let mut leaders = HashMap::new();
+    let results = client.call::<Vec<String>>("getSlotLeaders", &params);
+    for ((chunk_start, limit), result) in chunks.into_iter().zip(results) {
+        match result {
+            Ok(chunk) => leaders.extend(
+                (chunk_start..)
+                    .zip(chunk)
+                    .filter_map(|(slot, leader)| Some((slot, Pubkey::from_str(&leader).ok()?))),
+            ),
+            Err(err) => {
+                eprintln!(
+                    "Warning: failed to fetch leaders for slots {}..{}: {}",
+                    chunk_start,
+                    chunk_start + limit,
+                    err
+                );
+            }
+        }
+    }
+    leaders
+}
+
+/// Per-slot prioritization fees the provider reports for transactions write-locking each of
+/// `accounts`, covering only its recent slot window.
+pub fn fetch_recent_prioritization_fees(
+    client: &BatchClient,
+    accounts: &[Pubkey],
+) -> Vec<Vec<(Slot, u64)>> {
+    let params: Vec<_> = accounts
+        .iter()
+        .map(|account| json!([[account.to_string()]]))
+        .collect();
+    let results = client.call::<Vec<RpcPrioritizationFee>>("getRecentPrioritizationFees", &params);
+    accounts
+        .iter()
+        .zip(results)
+        .map(|(account, result)| match result {
+            Ok(fees) => fees
+                .into_iter()
+                .map(|fee| (fee.slot, fee.prioritization_fee))
+                .collect(),
+            Err(err) => {
+                eprintln!(
+                    "Warning: failed to fetch recent prioritization fees for {}: {}",
+                    account, err
+                );
+                Vec::new()
+            }
+        })
+        .collect()
+}
+
+/// Software version reported in gossip by each node identity. Only reflects the cluster's
+/// current state, so versions for historical slots may have changed since.
+pub fn fetch_node_versions(client: &RpcClient) -> HashMap<Pubkey, String> {
+    let nodes = client.get_cluster_nodes().unwrap_or_else(|err| {
+        eprintln!("Warning: failed to fetch cluster nodes: {}", err);
+        Vec::new()
+    });
+    nodes
+        .into_iter()
+        .filter_map(|node| {
+            let pubkey = Pubkey::from_str(&node.pubkey).ok()?;
+            Some((pubkey, node.version?))
+        })
+        .collect()
+}
+
+/// The cluster's epoch schedule, or mainnet's, which has no warmup epochs, if the provider does
+/// not report it.
+pub fn fetch_epoch_schedule(client: &RpcClient) -> EpochSchedule {
+    client.get_epoch_schedule().unwrap_or_else(|err| {
+        eprintln!(
+            "Warning: failed to fetch epoch schedule, assuming mainnet's: {}",
+            err
+        );
+        EpochSchedule::without_warmup()
+    })
+}
+
+/// Compares the transaction sets and orderings of the same block fetched twice, from two
+/// providers or at two commitment levels, warning on any disagreement and returning whether
+/// they agree. `other_source` names where `other` came from. Analysis continues on the primary
+/// block regardless.
+pub fn verify_block_consistency(
+    block: &UiConfirmedBlock,
+    other: &UiConfirmedBlock,
+    other_source: &str,
+) -> bool {
+    if block.blockhash != other.blockhash {
+        eprintln!(
+            "Warning: blockhash mismatch with {}: {} != {}",
+            other_source, block.blockhash, other.blockhash
+        );
+    }
+
+    let signatures = block_signatures(block);
+    let other_signatures = block_signatures(other);
+    if signatures == other_signatures {
+        return block.blockhash == other.blockhash;
+    }
+
+    eprintln!(
+        "Warning: block contents differ from {}: {} transactions vs {} transactions",
+        other_source,
+        signatures.len(),
+        other_signatures.len()
+    );
+
+    let signature_set: HashSet<_> = signatures.iter().collect();
+    let other_signature_set: HashSet<_> = other_signatures.iter().collect();
+    for signature in signatures
+        .iter()
+        .filter(|signature| !other_signature_set.contains(signature))
+    {
+        eprintln!("  missing from {}: {}", other_source, signature);
+    }
+    for signature in other_signatures
+        .iter()
+        .filter(|signature| !signature_set.contains(signature))
+    {
+        eprintln!("  only in {}: {}", other_source, signature);
+    }
+
+    // Compare the relative order of the transactions both providers agree on.
+    let common = signatures
+        .iter()
+        .filter(|signature| other_signature_set.contains(signature));
+    let other_common = other_signatures
+        .iter()
+        .filter(|signature| signature_set.contains(signature));
+    if let Some((index, (signature, other_signature))) = common
+        .zip(other_common)
+        .enumerate()
+        .find(|(_, (signature, other_signature))| signature != other_signature)
+    {
+        eprintln!(
+            "  ordering diverges at common index {}: {} vs {}",
+            index, signature, other_signature
+        );
+    }
+    false
+}
+
+/// Signatures of the block's transactions, in order, skipping any without one.
+fn block_signatures(block: &UiConfirmedBlock) -> Vec<Signature> {
+    block
+        .transactions
+        .iter()
+        .flatten()
+        .filter_map(|transaction| {
+            let versioned_transaction = transaction.transaction.decode().unwrap_or_else(|| {
+                eprintln!("Failed to decode transaction");
+                exit(1);
+            });
+            versioned_transaction.signatures.first().copied()
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+use {
+    crate::analysis::ViolationKind,
+    rhai::{Array, Dynamic, Engine, Scope, AST},
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::{path::Path, process::exit},
+};
+
+/// A user-supplied rhai expression deciding whether a conflicting access is a violation,
+/// replacing the built-in "later access has strictly higher priority" check.
+///
+/// The script sees `prev_access` and `new_access` (`"read"` or `"write"`), `prev_priority`,
+/// `new_priority`, `gap` (`new_priority - prev_priority`, possibly negative), `account` (the
+/// contended account) and `accounts` (every account locked by the later transaction), and must
+/// evaluate to a bool.
+#[derive(Debug)]
+pub struct ViolationRule {
+    engine: Engine,
+    ast: AST,
+}
+
+/// One conflicting access to `account`, as seen by a [`ViolationRule`].
+pub struct Conflict<'a> {
+    pub prev_access: &'static str,
+    pub prev_priority: u64,
+    pub new_access: &'static str,
+    pub new_priority: u64,
+    pub account: &'a Pubkey,
+    pub accounts: &'a [Pubkey],
+}
+
+impl ViolationRule {
+    pub fn load(path: &Path) -> Self {
+        let engine = Engine::new();
+        let ast = engine.compile_file(path.into()).unwrap_or_else(|err| {
+            eprintln!(
+                "Failed to compile violation rule {}: {}",
+                path.display(),
+                err
+            );
+            exit(1);
+        });
+        Self { engine, ast }
+    }
+
+    pub fn is_violation(&self, conflict: &Conflict) -> bool {
+        let prev_priority = to_int(conflict.prev_priority);
+        let new_priority = to_int(conflict.new_priority);
+        let accounts: Array = conflict
+            .accounts
+            .iter()
+            .map(|account| Dynamic::from(account.to_string()))
+            .collect();
+
+        let mut scope = Scope::new();
+        scope
+            .push_constant("prev_access", conflict.prev_access)
+            .push_constant("prev_priority", prev_priority)
+            .push_constant("new_access", conflict.new_access)
+            .push_constant("new_priority", new_priority)
+            .push_constant("gap", new_priority.saturating_sub(prev_priority))
+            .push_constant("account", conflict.account.to_string())
+            .push_constant("accounts", accounts);
+        self.engine
+            .eval_ast_with_scope::<bool>(&mut scope, &self.ast)
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to evaluate violation rule: {}", err);
+                exit(1);
+            })
+    }
+}
+
+/// A user-supplied rhai expression selecting which violations are reported, e.g.
+/// `gap > 1e6 && program == "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"`. Violations it
+/// rejects are dropped before any output sees them.
+///
+/// The expression sees `slot`, `account`, `kind` (e.g. `"write-write"`), `prev_priority`,
+/// `priority`, `gap` (`priority - prev_priority`), `prev_index`, `index`, `prev_fee_payer`,
+/// `fee_payer`, `program` (the later transaction's first top-level program other than the
+/// compute-budget program, or `""`) and `programs` (all of its top-level programs), and must
+/// evaluate to a bool.
+#[derive(Debug)]
+pub struct ViolationFilter {
+    engine: Engine,
+    ast: AST,
+}
+
+/// One violation, as seen by a [`ViolationFilter`].
+pub struct FilterCandidate<'a> {
+    pub slot: Slot,
+    pub account: &'a Pubkey,
+    pub kind: ViolationKind,
+    pub prev_priority: u64,
+    pub priority: u64,
+    pub prev_index: usize,
+    pub index: usize,
+    pub prev_fee_payer: &'a Pubkey,
+    pub fee_payer: &'a Pubkey,
+    pub program: Option<Pubkey>,
+    pub programs: &'a [Pubkey],
+}
+
+impl ViolationFilter {
+    pub fn parse(expression: &str) -> Self {
+        let engine = Engine::new();
+        let ast = engine.compile_expression(expression).unwrap_or_else(|err| {
+            eprintln!("Failed to parse filter {:?}: {}", expression, err);
+            exit(1);
+        });
+        Self { engine, ast }
+    }
+
+    pub fn matches(&self, candidate: &FilterCandidate) -> bool {
+        let prev_priority = to_int(candidate.prev_priority);
+        let priority = to_int(candidate.priority);
+        let programs: Array = candidate
+            .programs
+            .iter()
+            .map(|program| Dynamic::from(program.to_string()))
+            .collect();
+
+        let mut scope = Scope::new();
+        scope
+            .push_constant("slot", to_int(candidate.slot))
+            .push_constant("account", candidate.account.to_string())
+            .push_constant("kind", candidate.kind.to_string())
+            .push_constant("prev_priority", prev_priority)
+            .push_constant("priority", priority)
+            .push_constant("gap", priority.saturating_sub(prev_priority))
+            .push_constant("prev_index", candidate.prev_index as i64)
+            .push_constant("index", candidate.index as i64)
+            .push_constant("prev_fee_payer", candidate.prev_fee_payer.to_string())
+            .push_constant("fee_payer", candidate.fee_payer.to_string())
+            .push_constant(
+                "program",
+                candidate
+                    .program
+                    .map(|program| program.to_string())
+                    .unwrap_or_default(),
+            )
+            .push_constant("programs", programs);
+        self.engine
+            .eval_ast_with_scope::<bool>(&mut scope, &self.ast)
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to evaluate filter: {}", err);
+                exit(1);
+            })
+    }
+}
+
+/// Priorities above `i64::MAX` are clamped, since rhai integers are signed.
+fn to_int(priority: u64) -> i64 {
+    i64::try_from(priority).unwrap_or(i64::MAX)
+}
@@ -0,0 +1,91 @@
+use {
+    crate::analysis::BlockAnalysis,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashSet, str::FromStr},
+};
+
+/// AMM programs whose pool accounts are checked for sandwiches: Raydium AMM v4 and CLMM, Orca
+/// Whirlpools, Meteora DLMM, and Phoenix.
+const AMM_PROGRAMS: [&str; 5] = [
+    "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+    "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK",
+    "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc",
+    "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YdVaPiju",
+    "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY",
+];
+
+/// Three consecutive writes to a pool where the outer two share a fee payer and the middle one
+/// does not: the usual shape of a buy / victim / sell sandwich.
+pub struct Sandwich {
+    pub pool: Pubkey,
+    pub attacker: Pubkey,
+    /// Block positions of the front-run, victim, and back-run transactions.
+    pub front_index: usize,
+    pub victim_index: usize,
+    pub back_index: usize,
+}
+
+/// Sandwiches on violated accounts where one of the three transactions is part of a violation
+/// on that account.
+///
+/// An account counts as a pool if a transaction writing it invokes a known AMM program, at the
+/// top level or, for violating transactions, through CPI. This is a heuristic: arbitrage between
+/// two transactions from the same payer can take the same shape.
+pub fn find_sandwiches(analysis: &BlockAnalysis) -> Vec<Sandwich> {
+    let amm_programs: Vec<_> = AMM_PROGRAMS
+        .iter()
+        .map(|program| Pubkey::from_str(program).expect("valid program id"))
+        .collect();
+
+    let mut sandwiches = Vec::new();
+    let mut accounts: Vec<_> = analysis.violated_accounts.keys().copied().collect();
+    accounts.sort();
+    for account in accounts {
+        let writes: Vec<_> = analysis
+            .account_timeline(&account)
+            .into_iter()
+            .filter(|access| access.is_write)
+            .map(|access| &analysis.transactions[access.index])
+            .collect();
+        let is_pool = writes.iter().any(|transaction| {
+            transaction
+                .program_ids
+                .iter()
+                .chain(
+                    transaction
+                        .inner_instructions
+                        .iter()
+                        .map(|instruction| &instruction.program_id),
+                )
+                .any(|program_id| amm_programs.contains(program_id))
+        });
+        if !is_pool {
+            continue;
+        }
+
+        let violating_indexes: HashSet<_> = analysis.violated_accounts[&account]
+            .iter()
+            .flat_map(|violation| [violation.previous_index, violation.index])
+            .collect();
+        for window in writes.windows(3) {
+            let [front, victim, back] = window else {
+                continue;
+            };
+            if front.fee_payer == back.fee_payer
+                && front.fee_payer != victim.fee_payer
+                && [front.index, victim.index, back.index]
+                    .iter()
+                    .any(|index| violating_indexes.contains(index))
+            {
+                sandwiches.push(Sandwich {
+                    pool: account,
+                    attacker: front.fee_payer,
+                    front_index: front.index,
+                    victim_index: victim.index,
+                    back_index: back.index,
+                });
+            }
+        }
+    }
+    sandwiches
+}
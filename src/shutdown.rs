@@ -0,0 +1,54 @@
+use {
+    std::{
+        process::exit,
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    },
+    tokio::signal::unix::{signal, SignalKind},
+};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Turns the first SIGINT or SIGTERM into a shutdown request that long-running loops check with
+/// [`requested`] between slots, so they can flush their outputs before exiting. A second signal
+/// exits immediately.
+pub fn install() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to create signal handling runtime: {}", err);
+            exit(1);
+        });
+    let (mut interrupt, mut terminate) = runtime.block_on(async {
+        let listen = |kind| {
+            signal(kind).unwrap_or_else(|err| {
+                eprintln!("Failed to install signal handler: {}", err);
+                exit(1);
+            })
+        };
+        (
+            listen(SignalKind::interrupt()),
+            listen(SignalKind::terminate()),
+        )
+    });
+    thread::spawn(move || {
+        runtime.block_on(async {
+            loop {
+                tokio::select! {
+                    _ = interrupt.recv() => {}
+                    _ = terminate.recv() => {}
+                }
+                if REQUESTED.swap(true, Ordering::Relaxed) {
+                    exit(130);
+                }
+                eprintln!("Shutting down after the current slot, signal again to exit now");
+            }
+        })
+    });
+}
+
+/// Whether a shutdown signal has been received.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
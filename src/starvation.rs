@@ -0,0 +1,93 @@
+use {crate::analysis::BlockAnalysis, solana_sdk::pubkey::Pubkey, std::collections::HashMap};
+
+/// Reads of one account kept waiting behind streams of lower-priority writes.
+///
+/// The violation check only compares a read with the write landing immediately before it, so a
+/// read that sat behind a whole run of cheaper writes counts at most once. Here every write in
+/// the run since the account was last read is compared, so an account whose writers keep
+/// cutting in front of its readers shows up in proportion to how long its readers waited.
+pub struct ReadStarvation {
+    pub account: Pubkey,
+    /// Reads landing after the account was first written.
+    pub read_count: usize,
+    /// Reads with at least one lower-priority write landing ahead of them.
+    pub starved_read_count: usize,
+    /// Lower-priority writes landing ahead of reads, summed over reads.
+    pub jumping_write_count: usize,
+    /// Most lower-priority writes ahead of a single read, and that read's block position.
+    pub longest_streak: usize,
+    pub longest_streak_index: usize,
+}
+
+/// Accounts with starved reads, most lower-priority writes ahead of reads first.
+///
+/// A read is compared with the writes landing since the account's previous run of reads, since
+/// any of them could have landed after it instead. Consecutive reads do not conflict, so they
+/// are all compared with the same writes.
+pub fn find_read_starvation(analysis: &BlockAnalysis) -> Vec<ReadStarvation> {
+    #[derive(Default)]
+    struct AccountState {
+        /// Priorities of the writes since the last run of reads.
+        writes: Vec<u64>,
+        after_read: bool,
+        read_count: usize,
+        starved_read_count: usize,
+        jumping_write_count: usize,
+        longest_streak: usize,
+        longest_streak_index: usize,
+    }
+
+    let mut accounts: HashMap<Pubkey, AccountState> = HashMap::new();
+    for transaction in analysis.analyzed_transactions() {
+        for account in &transaction.writable_accounts {
+            if !analysis.write_lock_counts.contains_key(account) {
+                continue;
+            }
+            let state = accounts.entry(*account).or_default();
+            if state.after_read {
+                state.writes.clear();
+                state.after_read = false;
+            }
+            state.writes.push(transaction.priority);
+        }
+        for account in &transaction.readonly_accounts {
+            let Some(state) = accounts.get_mut(account) else {
+                continue;
+            };
+            state.after_read = true;
+            state.read_count += 1;
+            let streak = state
+                .writes
+                .iter()
+                .filter(|priority| **priority < transaction.priority)
+                .count();
+            if streak > 0 {
+                state.starved_read_count += 1;
+                state.jumping_write_count += streak;
+            }
+            if streak > state.longest_streak {
+                state.longest_streak = streak;
+                state.longest_streak_index = transaction.index;
+            }
+        }
+    }
+
+    let mut starvation: Vec<_> = accounts
+        .into_iter()
+        .filter(|(_, state)| state.starved_read_count > 0)
+        .map(|(account, state)| ReadStarvation {
+            account,
+            read_count: state.read_count,
+            starved_read_count: state.starved_read_count,
+            jumping_write_count: state.jumping_write_count,
+            longest_streak: state.longest_streak,
+            longest_streak_index: state.longest_streak_index,
+        })
+        .collect();
+    starvation.sort_by(|a, b| {
+        b.jumping_write_count
+            .cmp(&a.jumping_write_count)
+            .then(a.account.cmp(&b.account))
+    });
+    starvation
+}
@@ -0,0 +1,794 @@
+use {
+    crate::{
+        analysis::{
+            percentile, ratio, BlockAnalysis, NormalizedRates, TieStats, ViolationKind,
+            MAX_BLOCK_UNITS,
+        },
+        budget::ComputeBudgetAdoption,
+        fees::FeeTotals,
+        output::{write_output, OutputFile},
+        priority::display_priority,
+        report::SCHEMA_VERSION,
+        threads::{ThreadInference, DEFAULT_BANKING_THREADS},
+        tiebreak::TieBreaking,
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::{
+        collections::{BTreeMap, HashMap, VecDeque},
+        fs,
+        io::Write,
+        path::Path,
+        process::exit,
+    },
+};
+
+/// Per-block figures kept around for windowed statistics.
+pub struct SlotSummary {
+    pub slot: Slot,
+    pub transaction_count: usize,
+    pub violation_count: usize,
+    pub compute_units_consumed: u64,
+    pub priorities: Vec<u64>,
+}
+
+impl SlotSummary {
+    pub fn new(analysis: &BlockAnalysis) -> Self {
+        Self {
+            slot: analysis.slot,
+            transaction_count: analysis.analyzed_transaction_count(),
+            violation_count: analysis.violating_transaction_signatures.len(),
+            compute_units_consumed: analysis.compute_units_consumed,
+            priorities: analysis.priorities(),
+        }
+    }
+}
+
+/// Statistics over the most recent `capacity` analyzed slots.
+pub struct RollingWindow {
+    capacity: usize,
+    summaries: VecDeque<SlotSummary>,
+}
+
+impl RollingWindow {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            summaries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, summary: SlotSummary) {
+        if self.summaries.len() == self.capacity {
+            self.summaries.pop_front();
+        }
+        self.summaries.push_back(summary);
+    }
+
+    pub fn len(&self) -> usize {
+        self.summaries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.summaries.is_empty()
+    }
+
+    /// Slot of the oldest block still in the window.
+    pub fn oldest_slot(&self) -> Option<Slot> {
+        self.summaries.front().map(|s| s.slot)
+    }
+
+    /// Fraction of transactions in the window that violated priority ordering.
+    pub fn violation_rate(&self) -> f64 {
+        let violations = self
+            .summaries
+            .iter()
+            .map(|s| s.violation_count)
+            .sum::<usize>();
+        let transactions = self
+            .summaries
+            .iter()
+            .map(|s| s.transaction_count)
+            .sum::<usize>();
+        ratio(violations as u64, transactions as u64)
+    }
+
+    /// Median priority over all transactions in the window.
+    pub fn median_priority(&self) -> u64 {
+        let mut priorities: Vec<_> = self
+            .summaries
+            .iter()
+            .flat_map(|s| s.priorities.iter().copied())
+            .collect();
+        priorities.sort_unstable();
+        percentile(&priorities, 0.5)
+    }
+
+    /// Mean block fill over the window.
+    pub fn mean_fill(&self) -> f64 {
+        let consumed = self
+            .summaries
+            .iter()
+            .map(|s| s.compute_units_consumed as f64)
+            .sum::<f64>();
+        if self.is_empty() {
+            0.0
+        } else {
+            consumed / self.summaries.len() as f64 / MAX_BLOCK_UNITS as f64
+        }
+    }
+}
+
+impl std::fmt::Display for RollingWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(oldest_slot) = self.oldest_slot() else {
+            return write!(f, "no blocks yet");
+        };
+        write!(
+            f,
+            "last {} blocks (since slot {}): violation rate {:.2}%, median priority {}, mean fill \
+             {:.1}%",
+            self.len(),
+            oldest_slot,
+            self.violation_rate() * 100.0,
+            display_priority(self.median_priority()),
+            self.mean_fill() * 100.0,
+        )
+    }
+}
+
+/// Accounts that stay among the most contended block after block, the standing hot spots of a
+/// range as opposed to one-off bursts.
+pub struct HotAccountPersistence {
+    /// How many of each block's most write-locked accounts count as hot in it.
+    top_count: usize,
+    /// Blocks added so far, which numbers them so runs can tell whether they are consecutive.
+    block_count: usize,
+    accounts: HashMap<Pubkey, PersistentHotAccount>,
+}
+
+/// One account's history among a range's hot accounts.
+pub struct PersistentHotAccount {
+    pub account: Pubkey,
+    /// Blocks the account was hot in.
+    pub block_count: usize,
+    /// Most consecutive blocks the account was hot in, and the slot that run started at.
+    pub longest_streak: usize,
+    pub longest_streak_start: Slot,
+    /// The account's clearing price in each block it was hot in, the lowest priority that
+    /// still won a write lock on it.
+    pub clearing_prices: Vec<u64>,
+    streak: usize,
+    streak_start: Slot,
+    last_block: usize,
+}
+
+impl PersistentHotAccount {
+    pub fn median_clearing_price(&self) -> u64 {
+        let mut clearing_prices = self.clearing_prices.clone();
+        clearing_prices.sort_unstable();
+        percentile(&clearing_prices, 0.5)
+    }
+
+    pub fn max_clearing_price(&self) -> u64 {
+        self.clearing_prices
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl HotAccountPersistence {
+    pub fn new(top_count: usize) -> Self {
+        Self {
+            top_count,
+            block_count: 0,
+            accounts: HashMap::new(),
+        }
+    }
+
+    /// Adds the next block of the range. Blocks must be added in slot order; skipped slots do
+    /// not break a run, since no block could have been hot in them.
+    pub fn add(&mut self, analysis: &BlockAnalysis) {
+        self.block_count += 1;
+        let hot_accounts = analysis
+            .top_contended_accounts(self.top_count)
+            .into_iter()
+            .filter(|(_, write_count)| *write_count > 1);
+        for (account, _) in hot_accounts {
+            let hot_account =
+                self.accounts
+                    .entry(account)
+                    .or_insert_with(|| PersistentHotAccount {
+                        account,
+                        block_count: 0,
+                        longest_streak: 0,
+                        longest_streak_start: analysis.slot,
+                        clearing_prices: Vec::new(),
+                        streak: 0,
+                        streak_start: analysis.slot,
+                        last_block: 0,
+                    });
+            if hot_account.last_block + 1 == self.block_count && hot_account.streak > 0 {
+                hot_account.streak += 1;
+            } else {
+                hot_account.streak = 1;
+                hot_account.streak_start = analysis.slot;
+            }
+            if hot_account.streak > hot_account.longest_streak {
+                hot_account.longest_streak = hot_account.streak;
+                hot_account.longest_streak_start = hot_account.streak_start;
+            }
+            hot_account.last_block = self.block_count;
+            hot_account.block_count += 1;
+            let clearing_price = analysis
+                .write_lock_priorities(&account)
+                .into_iter()
+                .min()
+                .unwrap_or_default();
+            hot_account.clearing_prices.push(clearing_price);
+        }
+    }
+
+    /// Accounts hot in at least `min_streak` consecutive blocks, longest run first.
+    pub fn persistent(&self, min_streak: usize) -> Vec<&PersistentHotAccount> {
+        let mut accounts: Vec<_> = self
+            .accounts
+            .values()
+            .filter(|hot_account| hot_account.longest_streak >= min_streak)
+            .collect();
+        accounts.sort_by(|a, b| {
+            b.longest_streak
+                .cmp(&a.longest_streak)
+                .then(b.block_count.cmp(&a.block_count))
+                .then(a.account.cmp(&b.account))
+        });
+        accounts
+    }
+}
+
+/// Aggregate figures over many blocks, without keeping per-block detail.
+///
+/// Partial aggregates over disjoint sets of blocks, e.g. from separate machines each scanning
+/// part of a range, can be saved and combined with [`RangeStats::merge`].
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RangeStats {
+    /// [`SCHEMA_VERSION`] of the build that saved the statistics, or 0 if it predates
+    /// versioning.
+    pub schema_version: u32,
+    pub block_count: usize,
+    /// Transactions that took part in the analysis.
+    pub transaction_count: usize,
+    pub vote_transaction_count: usize,
+    /// Analyzed transactions other than votes.
+    pub non_vote_transaction_count: usize,
+    pub violation_count: usize,
+    /// Accounts write-locked by more than one analyzed transaction, summed over blocks.
+    pub contended_account_count: usize,
+    pub violations_by_kind: BTreeMap<ViolationKind, usize>,
+    /// Account-level violations where a zero-priority transaction landed first.
+    pub zero_priority_jump_count: usize,
+    /// Priority gaps of account-level violations.
+    pub priority_gaps: GapHistogram,
+    #[serde(with = "pubkey_keys")]
+    pub leaders: HashMap<Pubkey, LeaderStats>,
+    /// Analyzed transactions by primary top-level program.
+    #[serde(with = "pubkey_keys")]
+    pub programs: HashMap<Pubkey, ProgramStats>,
+    pub fee_totals: FeeTotals,
+    pub compute_budget: ComputeBudgetAdoption,
+    pub ties: TieStats,
+    pub tie_breaking: TieBreaking,
+}
+
+/// Transactions and compute attributed to one top-level program.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgramStats {
+    pub transaction_count: usize,
+    pub compute_units_consumed: u64,
+}
+
+impl ProgramStats {
+    pub fn merge(&mut self, other: Self) {
+        self.transaction_count += other.transaction_count;
+        self.compute_units_consumed += other.compute_units_consumed;
+    }
+}
+
+/// Aggregate figures over the blocks produced by one leader.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeaderStats {
+    pub block_count: usize,
+    /// Transactions that took part in the analysis.
+    pub transaction_count: usize,
+    /// Analyzed transactions other than votes.
+    pub non_vote_transaction_count: usize,
+    pub violation_count: usize,
+    pub account_violation_count: usize,
+    /// Accounts write-locked by more than one analyzed transaction, summed over blocks.
+    pub contended_account_count: usize,
+    /// Consecutive analyzed transaction pairs whose priority did not increase.
+    pub non_increasing_pairs: u64,
+    pub adjacent_pairs: u64,
+    /// Account violations whose transactions were inferred to have run on the same banking
+    /// thread, and on different ones, by [`ThreadInference`] with the default thread count.
+    pub same_thread_violation_count: usize,
+    pub cross_thread_violation_count: usize,
+    pub fee_totals: FeeTotals,
+    pub tie_breaking: TieBreaking,
+}
+
+impl LeaderStats {
+    pub fn merge(&mut self, other: Self) {
+        self.block_count += other.block_count;
+        self.transaction_count += other.transaction_count;
+        self.non_vote_transaction_count += other.non_vote_transaction_count;
+        self.violation_count += other.violation_count;
+        self.account_violation_count += other.account_violation_count;
+        self.contended_account_count += other.contended_account_count;
+        self.non_increasing_pairs += other.non_increasing_pairs;
+        self.adjacent_pairs += other.adjacent_pairs;
+        self.same_thread_violation_count += other.same_thread_violation_count;
+        self.cross_thread_violation_count += other.cross_thread_violation_count;
+        self.fee_totals.add(other.fee_totals);
+        self.tie_breaking.add(other.tie_breaking);
+    }
+
+    /// Violating transactions per analyzed transaction.
+    pub fn violation_rate(&self) -> f64 {
+        ratio(self.violation_count as u64, self.transaction_count as u64)
+    }
+
+    pub fn normalized_rates(&self) -> NormalizedRates {
+        NormalizedRates::new(
+            self.violation_count,
+            self.non_vote_transaction_count,
+            self.account_violation_count,
+            self.contended_account_count,
+        )
+    }
+
+    /// Fraction of consecutive analyzed transaction pairs whose priority did not increase.
+    pub fn priority_monotonicity(&self) -> f64 {
+        ratio(self.non_increasing_pairs, self.adjacent_pairs)
+    }
+
+    /// Fraction of account violations whose transactions were inferred to have run on different
+    /// banking threads, or `None` without violations.
+    pub fn cross_thread_share(&self) -> Option<f64> {
+        let total = self.same_thread_violation_count + self.cross_thread_violation_count;
+        (total > 0).then(|| ratio(self.cross_thread_violation_count as u64, total as u64))
+    }
+
+    /// Guesses which banking-stage scheduler produced this leader's blocks, with a confidence
+    /// in `[0, 1]`.
+    ///
+    /// A central scheduler only hands out conflicting transactions in priority order, so its
+    /// blocks have almost no violations, and the few it has are feasible on a single thread, e.g.
+    /// a transaction that arrived after its conflict was scheduled. Thread-local schedulers race
+    /// their queues against each other, producing a steady violation rate that mostly needs two
+    /// threads to explain. The violation rate decides the class, or the thread feasibility of the
+    /// violations when the rate falls in between; feasibility and priority monotonicity support
+    /// it, and confidence grows with the number of blocks observed.
+    pub fn classify_scheduler(&self) -> (SchedulerClass, f64) {
+        const CENTRAL_MAX_VIOLATION_RATE: f64 = 0.002;
+        const THREAD_LOCAL_MIN_VIOLATION_RATE: f64 = 0.01;
+        const MIDPOINT: f64 = (CENTRAL_MAX_VIOLATION_RATE + THREAD_LOCAL_MIN_VIOLATION_RATE) / 2.0;
+        // Cross-thread share of violations beyond which feasibility alone decides the class.
+        const DECISIVE_CROSS_THREAD_SHARE: f64 = 0.8;
+
+        let violation_rate = self.violation_rate();
+        let monotonicity = self.priority_monotonicity();
+        let cross_thread_share = self.cross_thread_share();
+        let class = if violation_rate <= CENTRAL_MAX_VIOLATION_RATE {
+            SchedulerClass::Central
+        } else if violation_rate >= THREAD_LOCAL_MIN_VIOLATION_RATE {
+            SchedulerClass::ThreadLocal
+        } else {
+            match cross_thread_share {
+                Some(share) if share >= DECISIVE_CROSS_THREAD_SHARE => SchedulerClass::ThreadLocal,
+                Some(share) if share <= 1.0 - DECISIVE_CROSS_THREAD_SHARE => {
+                    SchedulerClass::Central
+                }
+                _ => return (SchedulerClass::Inconclusive, 0.0),
+            }
+        };
+        // Without violations there is nothing a single thread could not have produced.
+        let cross_thread_share = cross_thread_share.unwrap_or_default();
+        let (monotonicity_agreement, feasibility_agreement) = match class {
+            SchedulerClass::Central => (monotonicity, 1.0 - cross_thread_share),
+            _ => (1.0 - monotonicity, cross_thread_share),
+        };
+
+        let separation = ((violation_rate - MIDPOINT).abs() / MIDPOINT).min(1.0);
+        let sample_weight = 1.0 - (-(self.block_count as f64) / 4.0).exp();
+        let confidence = sample_weight
+            * (0.6 * separation + 0.25 * feasibility_agreement + 0.15 * monotonicity_agreement);
+        (class, confidence)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchedulerClass {
+    Central,
+    ThreadLocal,
+    Inconclusive,
+}
+
+impl std::fmt::Display for SchedulerClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SchedulerClass::Central => "central",
+            SchedulerClass::ThreadLocal => "thread-local",
+            SchedulerClass::Inconclusive => "inconclusive",
+        })
+    }
+}
+
+impl RangeStats {
+    pub fn add(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        self.block_count += 1;
+        self.transaction_count += analysis.analyzed_transaction_count();
+        self.vote_transaction_count += analysis.vote_transaction_count();
+        self.non_vote_transaction_count += analysis.non_vote_transaction_count();
+        self.violation_count += analysis.violating_transaction_signatures.len();
+        self.contended_account_count += analysis.contended_account_count();
+        self.fee_totals.add(analysis.fee_totals());
+        self.compute_budget.add(analysis.compute_budget_adoption());
+        self.ties.add(analysis.tie_stats());
+        let tie_breaking = TieBreaking::new(analysis);
+        self.tie_breaking.add(tie_breaking);
+        if let Some(leader) = leader {
+            let leader_stats = self.leaders.entry(leader).or_default();
+            leader_stats.block_count += 1;
+            leader_stats.transaction_count += analysis.analyzed_transaction_count();
+            leader_stats.non_vote_transaction_count += analysis.non_vote_transaction_count();
+            leader_stats.violation_count += analysis.violating_transaction_signatures.len();
+            leader_stats.account_violation_count += analysis.violations().count();
+            leader_stats.contended_account_count += analysis.contended_account_count();
+            let (non_increasing_pairs, adjacent_pairs) = analysis.adjacent_priority_pairs();
+            leader_stats.non_increasing_pairs += non_increasing_pairs;
+            leader_stats.adjacent_pairs += adjacent_pairs;
+            let thread_inference = ThreadInference::new(analysis, DEFAULT_BANKING_THREADS);
+            leader_stats.same_thread_violation_count +=
+                thread_inference.same_thread_violation_count;
+            leader_stats.cross_thread_violation_count +=
+                thread_inference.cross_thread_violation_count;
+            leader_stats.fee_totals.add(analysis.fee_totals());
+            leader_stats.tie_breaking.add(tie_breaking);
+        }
+        for transaction in analysis.analyzed_transactions() {
+            let Some(program_id) = transaction.primary_program() else {
+                continue;
+            };
+            let program_stats = self.programs.entry(program_id).or_default();
+            program_stats.transaction_count += 1;
+            program_stats.compute_units_consumed += transaction.compute_units_consumed;
+        }
+        for violation in analysis.violations() {
+            *self.violations_by_kind.entry(violation.kind).or_default() += 1;
+            self.zero_priority_jump_count += usize::from(violation.is_zero_priority_jump());
+            self.priority_gaps.add(violation.gap());
+        }
+    }
+
+    pub fn new() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            ..Self::default()
+        }
+    }
+
+    /// Adds the figures of `other`, aggregated over blocks disjoint from this one's.
+    pub fn merge(&mut self, other: Self) {
+        self.block_count += other.block_count;
+        self.transaction_count += other.transaction_count;
+        self.vote_transaction_count += other.vote_transaction_count;
+        self.non_vote_transaction_count += other.non_vote_transaction_count;
+        self.violation_count += other.violation_count;
+        self.contended_account_count += other.contended_account_count;
+        for (kind, count) in other.violations_by_kind {
+            *self.violations_by_kind.entry(kind).or_default() += count;
+        }
+        self.zero_priority_jump_count += other.zero_priority_jump_count;
+        self.priority_gaps.merge(other.priority_gaps);
+        for (leader, leader_stats) in other.leaders {
+            self.leaders.entry(leader).or_default().merge(leader_stats);
+        }
+        for (program_id, program_stats) in other.programs {
+            self.programs
+                .entry(program_id)
+                .or_default()
+                .merge(program_stats);
+        }
+        self.fee_totals.add(other.fee_totals);
+        self.compute_budget.add(other.compute_budget);
+        self.ties.add(other.ties);
+        self.tie_breaking.add(other.tie_breaking);
+    }
+
+    /// Reads partial statistics written by [`RangeStats::save`].
+    pub fn load(path: &Path) -> Self {
+        let contents = fs::read(path).unwrap_or_else(|err| {
+            eprintln!("Failed to read {}: {}", path.display(), err);
+            exit(1);
+        });
+        let stats: Self = serde_json::from_slice(&contents).unwrap_or_else(|err| {
+            eprintln!("Failed to parse {}: {}", path.display(), err);
+            exit(1);
+        });
+        // Fields missing from another version's file would silently merge as zeros.
+        if stats.schema_version != SCHEMA_VERSION {
+            eprintln!(
+                "{} holds statistics of schema version {}, but this build merges version {}; \
+                 save them again with this build",
+                path.display(),
+                stats.schema_version,
+                SCHEMA_VERSION
+            );
+            exit(1);
+        }
+        stats
+    }
+
+    pub fn save(&self, path: &Path) {
+        let contents = serde_json::to_vec(self).unwrap_or_else(|err| {
+            eprintln!("Failed to serialize statistics: {}", err);
+            exit(1);
+        });
+        write_output(path, &contents);
+    }
+
+    /// Violating transactions per analyzed transaction.
+    pub fn violation_rate(&self) -> f64 {
+        ratio(self.violation_count as u64, self.transaction_count as u64)
+    }
+
+    pub fn normalized_rates(&self) -> NormalizedRates {
+        NormalizedRates::new(
+            self.violation_count,
+            self.non_vote_transaction_count,
+            self.violations_by_kind.values().sum(),
+            self.contended_account_count,
+        )
+    }
+
+    /// Programs by compute consumed, most first.
+    pub fn top_programs(&self, count: usize) -> Vec<(Pubkey, &ProgramStats)> {
+        let mut programs: Vec<_> = self
+            .programs
+            .iter()
+            .map(|(program_id, program_stats)| (*program_id, program_stats))
+            .collect();
+        programs.sort_by(|a, b| {
+            b.1.compute_units_consumed
+                .cmp(&a.1.compute_units_consumed)
+                .then(a.0.cmp(&b.0))
+        });
+        programs.truncate(count);
+        programs
+    }
+
+    /// Number of violations per decade of priority gap, keyed by `floor(log10(gap))`.
+    pub fn gap_histogram(&self) -> BTreeMap<u32, usize> {
+        self.priority_gaps.decades()
+    }
+
+    /// Priority gap at each of `fractions`, in order.
+    pub fn gap_percentiles(&self, fractions: &[f64]) -> Vec<u64> {
+        fractions
+            .iter()
+            .map(|fraction| self.priority_gaps.percentile(*fraction))
+            .collect()
+    }
+}
+
+/// Counts of priority gaps in buckets of their two leading digits, so gaps from any number of
+/// blocks take bounded space to keep, save and merge. Percentiles are rounded down to the bucket,
+/// by less than 10%, except for gaps under 100 and the maximum, which are exact.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GapHistogram {
+    /// Gaps counted in each bucket, keyed by the smallest gap in it.
+    buckets: BTreeMap<u64, usize>,
+    max: u64,
+}
+
+impl GapHistogram {
+    pub fn add(&mut self, gap: u64) {
+        let bucket = match gap.checked_ilog10() {
+            Some(digits) if digits >= 2 => {
+                let scale = 10u64.pow(digits - 1);
+                gap / scale * scale
+            }
+            _ => gap,
+        };
+        *self.buckets.entry(bucket).or_default() += 1;
+        self.max = self.max.max(gap);
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        for (bucket, count) in other.buckets {
+            *self.buckets.entry(bucket).or_default() += count;
+        }
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn count(&self) -> usize {
+        self.buckets.values().sum()
+    }
+
+    /// Nearest-rank percentile, as [`percentile`] over every gap. Returns 0 without gaps.
+    pub fn percentile(&self, fraction: f64) -> u64 {
+        let count = self.count();
+        if count == 0 {
+            return 0;
+        }
+        let rank = ((count - 1) as f64 * fraction).round() as usize;
+        if rank == count - 1 {
+            return self.max;
+        }
+        let mut seen = 0;
+        for (bucket, bucket_count) in &self.buckets {
+            seen += bucket_count;
+            if seen > rank {
+                return *bucket;
+            }
+        }
+        self.max
+    }
+
+    /// Number of gaps per decade, keyed by `floor(log10(gap))`, with zero counted as 1.
+    pub fn decades(&self) -> BTreeMap<u32, usize> {
+        let mut decades = BTreeMap::new();
+        for (bucket, count) in &self.buckets {
+            *decades.entry(bucket.max(&1).ilog10()).or_default() += count;
+        }
+        decades
+    }
+}
+
+/// Serializes maps keyed by pubkey with base58 keys, since JSON object keys must be strings.
+mod pubkey_keys {
+    use {
+        serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer},
+        solana_sdk::pubkey::Pubkey,
+        std::{collections::HashMap, str::FromStr},
+    };
+
+    pub fn serialize<S: Serializer, V: Serialize>(
+        map: &HashMap<Pubkey, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(key, value)| (key.to_string(), value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<Pubkey, V>, D::Error> {
+        HashMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(key, value)| {
+                Pubkey::from_str(&key)
+                    .map(|key| (key, value))
+                    .map_err(D::Error::custom)
+            })
+            .collect()
+    }
+}
+
+/// Writes one compact CSV row per analyzed block, for plotting range scans.
+pub struct SlotCsvWriter {
+    writer: OutputFile,
+}
+
+impl SlotCsvWriter {
+    const HEADER: &'static str = "slot,leader,tx_count,violations,max_gap,fill_pct,schema_version";
+
+    /// Writes a new CSV file, which only replaces `path` once committed.
+    pub fn new(path: &Path) -> Self {
+        let mut csv_writer = Self {
+            writer: OutputFile::create(path),
+        };
+        csv_writer.write_line(format_args!("{}", Self::HEADER));
+        csv_writer
+    }
+
+    pub fn write(&mut self, analysis: &BlockAnalysis, leader: Option<Pubkey>) {
+        let leader = leader.map(|leader| leader.to_string()).unwrap_or_default();
+        let max_gap = analysis
+            .violations()
+            .map(|violation| violation.gap())
+            .max()
+            .unwrap_or_default();
+        self.write_line(format_args!(
+            "{},{},{},{},{},{:.2},{}",
+            analysis.slot,
+            leader,
+            analysis.analyzed_transaction_count(),
+            analysis.violating_transaction_signatures.len(),
+            max_gap,
+            analysis.fill() * 100.0,
+            SCHEMA_VERSION,
+        ));
+    }
+
+    pub fn commit(&mut self) {
+        self.writer.commit();
+    }
+
+    fn write_line(&mut self, line: std::fmt::Arguments) {
+        writeln!(self.writer, "{}", line)
+            .and_then(|()| self.writer.flush())
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to write CSV: {}", err);
+                exit(1);
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gap_histogram_matches_exact_percentiles() {
+        let gaps: Vec<u64> = (0..1000).map(|i| i * i * 37 % 1_000_003).collect();
+        let mut halves = (GapHistogram::default(), GapHistogram::default());
+        for (i, gap) in gaps.iter().enumerate() {
+            if i % 2 == 0 {
+                halves.0.add(*gap);
+            } else {
+                halves.1.add(*gap);
+            }
+        }
+        let (mut histogram, other) = halves;
+        histogram.merge(other);
+        let mut sorted = gaps.clone();
+        sorted.sort_unstable();
+
+        assert_eq!(histogram.count(), 1000);
+        for fraction in [0.0, 0.1, 0.5, 0.9, 0.99, 1.0] {
+            let exact = percentile(&sorted, fraction);
+            let approximate = histogram.percentile(fraction);
+            assert!(
+                approximate <= exact,
+                "p{}: {} > {}",
+                fraction,
+                approximate,
+                exact
+            );
+            assert!(
+                exact - approximate <= exact / 10,
+                "p{}: {} too far below {}",
+                fraction,
+                approximate,
+                exact
+            );
+        }
+        assert_eq!(histogram.percentile(1.0), *sorted.last().unwrap());
+
+        let mut decades = BTreeMap::new();
+        for gap in &gaps {
+            *decades.entry(gap.max(&1).ilog10()).or_default() += 1;
+        }
+        assert_eq!(histogram.decades(), decades);
+    }
+
+    #[test]
+    fn gap_histogram_small_gaps_are_exact() {
+        let mut histogram = GapHistogram::default();
+        for gap in [0, 5, 99, 99, 100, 12_345] {
+            histogram.add(gap);
+        }
+        assert_eq!(histogram.percentile(0.0), 0);
+        assert_eq!(histogram.percentile(0.4), 99);
+        assert_eq!(histogram.percentile(0.8), 100);
+        assert_eq!(histogram.percentile(1.0), 12_345);
+        assert_eq!(GapHistogram::default().percentile(0.5), 0);
+    }
+}
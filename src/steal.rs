@@ -0,0 +1,92 @@
+use {
+    solana_sdk::clock::Slot,
+    std::{
+        collections::HashMap,
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// Fetches in progress across a pool of workers, so that a worker with nothing else to do can
+/// take over one that has stalled instead of leaving the whole scan waiting on it.
+pub struct InFlight {
+    fetches: Mutex<HashMap<usize, InFlightFetch>>,
+    stall_after: Duration,
+}
+
+struct InFlightFetch {
+    slot: Slot,
+    started: Instant,
+    /// Whether another worker has already taken over the fetch, which happens at most once.
+    stolen: bool,
+    /// Whether one of the two attempts at a stolen fetch has already failed.
+    failed: bool,
+}
+
+/// What a failed fetch attempt means for its block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailedAttempt {
+    /// Another attempt already delivered the block, so the failure can be ignored.
+    Superseded,
+    /// Another attempt is still running, and the block is left to it.
+    Pending,
+    /// Every attempt at the block has failed.
+    Failed,
+}
+
+impl InFlight {
+    pub fn new(stall_after: Duration) -> Self {
+        Self {
+            fetches: Mutex::new(HashMap::new()),
+            stall_after,
+        }
+    }
+
+    pub fn start(&self, position: usize, slot: Slot) {
+        self.fetches.lock().unwrap().insert(
+            position,
+            InFlightFetch {
+                slot,
+                started: Instant::now(),
+                stolen: false,
+                failed: false,
+            },
+        );
+    }
+
+    /// The longest-running fetch that has stalled and not been taken over yet, marked as taken
+    /// over by the caller.
+    pub fn steal(&self) -> Option<(usize, Slot, Duration)> {
+        let mut fetches = self.fetches.lock().unwrap();
+        let (position, fetch) = fetches
+            .iter_mut()
+            .filter(|(_, fetch)| !fetch.stolen && fetch.started.elapsed() >= self.stall_after)
+            .min_by_key(|(_, fetch)| fetch.started)?;
+        fetch.stolen = true;
+        Some((*position, fetch.slot, fetch.started.elapsed()))
+    }
+
+    /// Marks the fetch at `position` finished. Returns false if another worker finished it
+    /// first, in which case the result should be dropped.
+    pub fn finish(&self, position: usize) -> bool {
+        self.fetches.lock().unwrap().remove(&position).is_some()
+    }
+
+    /// Records that an attempt at the fetch at `position` failed.
+    pub fn fail(&self, position: usize) -> FailedAttempt {
+        let mut fetches = self.fetches.lock().unwrap();
+        let Some(fetch) = fetches.get_mut(&position) else {
+            return FailedAttempt::Superseded;
+        };
+        if fetch.stolen && !fetch.failed {
+            fetch.failed = true;
+            return FailedAttempt::Pending;
+        }
+        fetches.remove(&position);
+        FailedAttempt::Failed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fetches.lock().unwrap().is_empty()
+    }
+}
@@ -0,0 +1,98 @@
+use {
+    crate::{analysis::BlockAnalysis, output::write_output, priority::display_priority},
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashSet, path::Path},
+};
+
+const LABEL_WIDTH: f64 = 340.0;
+const PLOT_WIDTH: f64 = 1200.0;
+const ROW_HEIGHT: f64 = 18.0;
+const HEADER_HEIGHT: f64 = 40.0;
+
+/// Writes a Gantt-style SVG of the block with one row per account in `accounts`.
+///
+/// Execution is estimated as strictly sequential in block order, so each transaction occupies
+/// the span of cumulative consumed compute units it added to the block. Bars are colored by
+/// priority on a log scale from blue (lowest) to red (highest), with gray for zero priority;
+/// read locks are drawn faded, and accesses that violated ordering are outlined.
+pub fn write_timeline_svg(path: &Path, analysis: &BlockAnalysis, accounts: &[Pubkey]) {
+    let total_units = analysis.compute_units_consumed.max(1) as f64;
+    let max_priority = analysis.priorities().into_iter().max().unwrap_or_default();
+    let height = HEADER_HEIGHT + ROW_HEIGHT * accounts.len() as f64;
+    let width = LABEL_WIDTH + PLOT_WIDTH;
+
+    let mut svg = String::new();
+    svg.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         font-family=\"monospace\" font-size=\"11\">\n"
+    ));
+    svg.push_str(&format!(
+        "<text x=\"4\" y=\"16\">Slot {}: {} compute units, priority 0..{}</text>\n",
+        analysis.slot,
+        analysis.compute_units_consumed,
+        display_priority(max_priority)
+    ));
+
+    let violating_accesses: HashSet<_> = analysis
+        .violated_accounts
+        .iter()
+        .flat_map(|(account, violations)| {
+            violations
+                .iter()
+                .map(move |violation| (*account, violation.index))
+        })
+        .collect();
+    let mut starts = Vec::with_capacity(analysis.transactions.len());
+    let mut cumulative_units = 0;
+    for transaction in &analysis.transactions {
+        starts.push(cumulative_units);
+        cumulative_units += transaction.compute_units_consumed;
+    }
+
+    for (row, account) in accounts.iter().enumerate() {
+        let y = HEADER_HEIGHT + ROW_HEIGHT * row as f64;
+        if row % 2 == 1 {
+            svg.push_str(&format!(
+                "<rect x=\"0\" y=\"{y}\" width=\"{width}\" height=\"{ROW_HEIGHT}\" \
+                 fill=\"#f4f4f4\"/>\n"
+            ));
+        }
+        svg.push_str(&format!(
+            "<text x=\"4\" y=\"{}\">{}</text>\n",
+            y + ROW_HEIGHT - 5.0,
+            account
+        ));
+        for access in analysis.account_timeline(account) {
+            let transaction = &analysis.transactions[access.index];
+            let x = LABEL_WIDTH + starts[access.index] as f64 / total_units * PLOT_WIDTH;
+            let bar_width =
+                (transaction.compute_units_consumed as f64 / total_units * PLOT_WIDTH).max(1.0);
+            let opacity = if access.is_write { 1.0 } else { 0.4 };
+            let outline = if violating_accesses.contains(&(*account, access.index)) {
+                " stroke=\"black\" stroke-width=\"1\""
+            } else {
+                ""
+            };
+            svg.push_str(&format!(
+                "<rect x=\"{x:.2}\" y=\"{}\" width=\"{bar_width:.2}\" height=\"{}\" fill=\"{}\" \
+                 fill-opacity=\"{opacity}\"{outline}><title>{} {}</title></rect>\n",
+                y + 2.0,
+                ROW_HEIGHT - 4.0,
+                priority_color(access.priority, max_priority),
+                access,
+                transaction.signature,
+            ));
+        }
+    }
+    svg.push_str("</svg>\n");
+
+    write_output(path, svg.as_bytes());
+}
+
+fn priority_color(priority: u64, max_priority: u64) -> String {
+    if priority == 0 {
+        return "#999999".to_string();
+    }
+    let scale = ((priority as f64).ln_1p() / (max_priority as f64).ln_1p()).clamp(0.0, 1.0);
+    format!("hsl({:.0}, 80%, 50%)", 240.0 * (1.0 - scale))
+}
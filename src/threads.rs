@@ -0,0 +1,99 @@
+use crate::analysis::{BlockAnalysis, Violation};
+
+/// Non-vote banking threads in the default validator configuration.
+pub const DEFAULT_BANKING_THREADS: usize = 4;
+
+/// A guess at which banking thread executed each analyzed transaction.
+///
+/// Under a thread-local scheduler each thread drains its own queue highest priority first, so the
+/// block is modeled as an interleaving of at most `max_threads` streams of non-increasing
+/// priority. Transactions are assigned in block order to the stream whose last priority is the
+/// lowest one still at or above theirs, opening a new stream when none fits. Once the limit is
+/// reached, a transaction that fits no stream joins the one with the highest last priority.
+///
+/// Violations between two streams then look like threads racing each other for an account,
+/// while violations within one stream mean the thread itself did not run in priority order,
+/// e.g. because the later transaction arrived after the earlier one was scheduled.
+pub struct ThreadInference {
+    /// Inferred thread of each transaction, by block index. `None` for transactions excluded from
+    /// the analysis.
+    threads: Vec<Option<usize>>,
+    /// Per inferred thread, in order of first appearance.
+    pub thread_stats: Vec<ThreadStats>,
+    pub cross_thread_violation_count: usize,
+    pub same_thread_violation_count: usize,
+}
+
+#[derive(Default)]
+pub struct ThreadStats {
+    pub transaction_count: usize,
+    /// Priority of the first and last transactions assigned to the thread.
+    pub first_priority: u64,
+    pub last_priority: u64,
+    /// Account violations where both transactions were assigned to this thread.
+    pub same_thread_violation_count: usize,
+}
+
+impl ThreadInference {
+    pub fn new(analysis: &BlockAnalysis, max_threads: usize) -> Self {
+        let mut threads = vec![None; analysis.transactions.len()];
+        let mut thread_stats: Vec<ThreadStats> = Vec::new();
+        for transaction in analysis.analyzed_transactions() {
+            let priority = transaction.priority;
+            let fitting = thread_stats
+                .iter()
+                .enumerate()
+                .filter(|(_, stats)| stats.last_priority >= priority)
+                .min_by_key(|(_, stats)| stats.last_priority)
+                .map(|(thread, _)| thread);
+            let thread = match fitting {
+                Some(thread) => thread,
+                None if thread_stats.len() < max_threads.max(1) => {
+                    thread_stats.push(ThreadStats {
+                        first_priority: priority,
+                        ..ThreadStats::default()
+                    });
+                    thread_stats.len() - 1
+                }
+                None => thread_stats
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, stats)| stats.last_priority)
+                    .map(|(thread, _)| thread)
+                    .unwrap_or_default(),
+            };
+            let stats = &mut thread_stats[thread];
+            stats.transaction_count += 1;
+            stats.last_priority = priority;
+            threads[transaction.index] = Some(thread);
+        }
+
+        let mut inference = Self {
+            threads,
+            thread_stats,
+            cross_thread_violation_count: 0,
+            same_thread_violation_count: 0,
+        };
+        for violation in analysis.violations() {
+            match inference.same_thread(violation) {
+                Some(thread) => {
+                    inference.same_thread_violation_count += 1;
+                    inference.thread_stats[thread].same_thread_violation_count += 1;
+                }
+                None => inference.cross_thread_violation_count += 1,
+            }
+        }
+        inference
+    }
+
+    /// Inferred thread of the transaction at `index` in the block.
+    pub fn thread(&self, index: usize) -> Option<usize> {
+        self.threads.get(index).copied().flatten()
+    }
+
+    /// The thread both transactions of `violation` were assigned to, if it is the same one.
+    pub fn same_thread(&self, violation: &Violation) -> Option<usize> {
+        let thread = self.thread(violation.index)?;
+        (self.thread(violation.previous_index) == Some(thread)).then_some(thread)
+    }
+}
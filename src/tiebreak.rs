@@ -0,0 +1,139 @@
+use {
+    crate::analysis::{ratio, BlockAnalysis},
+    serde::{Deserialize, Serialize},
+    std::collections::BTreeSet,
+};
+
+/// How a leader ordered transactions of equal priority that conflicted on an account.
+///
+/// Priority says nothing about the order of tied transactions, so a scheduler that breaks ties
+/// by arrival should put the lower signature or fee payer first in about half of the pairs. A
+/// share far from half means ties were broken by the key itself, which lets senders buy their way
+/// ahead by grinding keys instead of paying priority. Transactions tied on several accounts are
+/// counted once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TieBreaking {
+    pub pair_count: usize,
+    /// Pairs where the earlier transaction has the lower signature.
+    pub signature_ascending_count: usize,
+    /// Pairs with different fee payers, the only ones fee payer order can tell anything about.
+    pub fee_payer_pair_count: usize,
+    /// Of those, pairs where the earlier transaction has the lower fee payer.
+    pub fee_payer_ascending_count: usize,
+}
+
+/// A transaction key a leader appears to break ties on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TieBreakKey {
+    Signature,
+    FeePayer,
+}
+
+impl std::fmt::Display for TieBreakKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TieBreakKey::Signature => "signature",
+            TieBreakKey::FeePayer => "fee payer",
+        })
+    }
+}
+
+impl TieBreaking {
+    pub fn new(analysis: &BlockAnalysis) -> Self {
+        let pairs: BTreeSet<_> = analysis
+            .ties
+            .iter()
+            .map(|tie| (tie.previous_index, tie.index))
+            .collect();
+        let mut tie_breaking = Self::default();
+        for (previous_index, index) in pairs {
+            let earlier = &analysis.transactions[previous_index];
+            let later = &analysis.transactions[index];
+            tie_breaking.pair_count += 1;
+            tie_breaking.signature_ascending_count +=
+                usize::from(earlier.signature.as_ref() < later.signature.as_ref());
+            if earlier.fee_payer != later.fee_payer {
+                tie_breaking.fee_payer_pair_count += 1;
+                tie_breaking.fee_payer_ascending_count +=
+                    usize::from(earlier.fee_payer < later.fee_payer);
+            }
+        }
+        tie_breaking
+    }
+
+    pub fn add(&mut self, other: TieBreaking) {
+        self.pair_count += other.pair_count;
+        self.signature_ascending_count += other.signature_ascending_count;
+        self.fee_payer_pair_count += other.fee_payer_pair_count;
+        self.fee_payer_ascending_count += other.fee_payer_ascending_count;
+    }
+
+    /// Keys whose order the tied pairs follow too consistently to be chance, with the fraction
+    /// of pairs in ascending order.
+    ///
+    /// Under arrival-order tie breaking the ascending count is binomial with half the pairs as
+    /// its mean; a key is reported once its count is more than `MAX_Z_SCORE` standard deviations
+    /// away, and only with enough pairs for that to mean anything.
+    pub fn patterns(&self) -> Vec<(TieBreakKey, f64)> {
+        const MIN_PAIRS: usize = 30;
+        const MAX_Z_SCORE: f64 = 3.0;
+
+        [
+            (
+                TieBreakKey::Signature,
+                self.signature_ascending_count,
+                self.pair_count,
+            ),
+            (
+                TieBreakKey::FeePayer,
+                self.fee_payer_ascending_count,
+                self.fee_payer_pair_count,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, ascending, pairs)| {
+            let mean = *pairs as f64 / 2.0;
+            let deviation = (*pairs as f64 / 4.0).sqrt();
+            *pairs >= MIN_PAIRS && (*ascending as f64 - mean).abs() > MAX_Z_SCORE * deviation
+        })
+        .map(|(key, ascending, pairs)| (key, ratio(ascending as u64, pairs as u64)))
+        .collect()
+    }
+}
+
+impl std::fmt::Display for TieBreaking {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} tied pairs, {:.1}% in ascending signature order, {:.1}% of {} with different fee \
+             payers in ascending fee payer order",
+            self.pair_count,
+            ratio(
+                self.signature_ascending_count as u64,
+                self.pair_count as u64
+            ) * 100.0,
+            ratio(
+                self.fee_payer_ascending_count as u64,
+                self.fee_payer_pair_count as u64
+            ) * 100.0,
+            self.fee_payer_pair_count
+        )?;
+        let patterns = self.patterns();
+        if patterns.is_empty() {
+            return Ok(());
+        }
+        let patterns: Vec<_> = patterns
+            .iter()
+            .map(|(key, ascending)| {
+                let direction = if *ascending > 0.5 {
+                    "ascending"
+                } else {
+                    "descending"
+                };
+                format!("{} {}", direction, key)
+            })
+            .collect();
+        write!(f, "; ties look broken by {}", patterns.join(" and "))
+    }
+}